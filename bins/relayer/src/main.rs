@@ -1,12 +1,22 @@
 use config::config::{AppConfig, Configuration};
 use db::db::DbState;
+use std::process::ExitCode;
 use std::sync::Arc;
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     dotenvy::dotenv().ok();
 
-    let configuration: Configuration = Configuration::load();
+    let configuration: Configuration = match Configuration::load() {
+        Ok(configuration) => configuration,
+        Err(e) => {
+            eprintln!("{e}");
+            eprintln!(
+                "Set the keys above in the file named by APP_CONFIG_FILE or as APP_ environment variables, e.g. queue.concurrency as APP_QUEUE__CONCURRENCY."
+            );
+            return ExitCode::FAILURE;
+        }
+    };
     let db: DbState = DbState::default(
         &configuration.database_url,
         configuration.max_db_connection as u32,
@@ -18,4 +28,6 @@ async fn main() {
     routes::routes::start_app(app_arc_config, db)
         .await
         .expect("Couldn't start app");
+
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,197 @@
+use crate::rules::{AlertRules, EvaluationContext};
+use crate::sinks::{AlertEvent, AlertEventKind, AlertSink};
+use config::config::AlertingConfig;
+use db::alerts::{Alert, AlertRepository, NewAlert};
+use futures::future::join_all;
+use metrics::{HealthChecker, MetricSample, MetricsCollector};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// Per-rule bookkeeping kept between evaluations.
+#[derive(Default)]
+struct EngineState {
+    /// Since when each rule's condition has held.
+    pending_since: HashMap<String, Instant>,
+    /// Alert each rule fired last, to notice when it was resolved by hand.
+    fired: HashMap<String, Uuid>,
+    /// Earlier metric snapshots for ratio rules, oldest first.
+    history: VecDeque<(Instant, Vec<MetricSample>)>,
+}
+
+/// Evaluates the alert rules on an interval, records fired and resolved alerts in
+/// `alert_history` and notifies every sink.
+#[derive(Clone)]
+pub struct AlertEngine {
+    rules: Arc<AlertRules>,
+    interval: Duration,
+    alerts: AlertRepository,
+    health: Arc<HealthChecker>,
+    metrics: MetricsCollector,
+    sinks: Vec<Arc<dyn AlertSink>>,
+}
+
+impl AlertEngine {
+    pub fn new(
+        config: &AlertingConfig,
+        rules: AlertRules,
+        alerts: AlertRepository,
+        health: Arc<HealthChecker>,
+        metrics: MetricsCollector,
+    ) -> Self {
+        Self {
+            rules: Arc::new(rules),
+            interval: config.evaluation_interval,
+            alerts,
+            health,
+            metrics,
+            sinks: Vec::new(),
+        }
+    }
+
+    pub fn with_sink(mut self, sink: impl AlertSink + 'static) -> Self {
+        self.sinks.push(Arc::new(sink));
+        self
+    }
+
+    pub fn spawn(self, shutdown: watch::Receiver<bool>) -> JoinHandle<()> {
+        tokio::spawn(self.run(shutdown))
+    }
+
+    pub async fn run(self, mut shutdown: watch::Receiver<bool>) {
+        tracing::info!(
+            rules = self.rules.rules.len(),
+            sinks = ?self.sinks.iter().map(|s| s.name()).collect::<Vec<_>>(),
+            "Alert engine started"
+        );
+
+        let mut state = EngineState::default();
+        while !*shutdown.borrow() {
+            if let Err(e) = self.evaluate(&mut state).await {
+                tracing::error!("Alert evaluation failed: {}", e);
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(self.interval) => {}
+                _ = shutdown.changed() => {}
+            }
+        }
+
+        tracing::info!("Alert engine stopped");
+    }
+
+    /// Resolves an open alert by hand and notifies the sinks. `None` if it was not open.
+    pub async fn resolve(&self, id: Uuid) -> anyhow::Result<Option<Alert>> {
+        let resolved = self.alerts.resolve(id).await?;
+        if let Some(alert) = &resolved {
+            self.notify(AlertEventKind::Resolved, alert.clone()).await;
+        }
+        Ok(resolved)
+    }
+
+    async fn evaluate(&self, state: &mut EngineState) -> anyhow::Result<()> {
+        if self.rules.rules.is_empty() {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let health = if self.rules.needs_health() {
+            Some(self.health.check_system_health().await)
+        } else {
+            None
+        };
+        let samples = self.metrics.snapshot();
+        let history: Vec<(Duration, Vec<MetricSample>)> = state
+            .history
+            .iter()
+            .map(|(taken_at, samples)| (now.duration_since(*taken_at), samples.clone()))
+            .collect();
+        let context = EvaluationContext {
+            samples: &samples,
+            history: &history,
+            health: health.as_ref(),
+        };
+
+        let open: HashMap<String, Alert> = self
+            .alerts
+            .list_open()
+            .await?
+            .into_iter()
+            .map(|alert| (alert.alert_name.clone(), alert))
+            .collect();
+
+        for rule in &self.rules.rules {
+            let Some(observation) = rule.condition.evaluate(&context) else {
+                continue;
+            };
+
+            // Resolved by hand while still firing: the condition has to hold for `for_secs` again.
+            if let Some(id) = state.fired.get(&rule.name) {
+                if open.get(&rule.name).is_none_or(|alert| alert.id != *id) {
+                    state.fired.remove(&rule.name);
+                    state.pending_since.insert(rule.name.clone(), now);
+                }
+            }
+
+            if !observation.firing {
+                state.pending_since.remove(&rule.name);
+                state.fired.remove(&rule.name);
+                if let Some(alert) = open.get(&rule.name) {
+                    if let Some(resolved) = self.alerts.resolve(alert.id).await? {
+                        self.notify(AlertEventKind::Resolved, resolved).await;
+                    }
+                }
+                continue;
+            }
+
+            let since = *state.pending_since.entry(rule.name.clone()).or_insert(now);
+            if now.duration_since(since) < Duration::from_secs(rule.for_secs)
+                || open.contains_key(&rule.name)
+            {
+                continue;
+            }
+
+            let new_alert = NewAlert {
+                alert_name: rule.name.clone(),
+                severity: rule.severity,
+                message: observation.message,
+                details: Some(serde_json::json!({
+                    "description": rule.description,
+                    "value": observation.value,
+                })),
+            };
+            // `None` means another replica fired it first.
+            if let Some(alert) = self.alerts.fire(&new_alert).await? {
+                state.fired.insert(rule.name.clone(), alert.id);
+                self.notify(AlertEventKind::Fired, alert).await;
+            }
+        }
+
+        let max_window = self.rules.max_window();
+        if !max_window.is_zero() {
+            state.history.push_back((now, samples));
+            while state
+                .history
+                .front()
+                .is_some_and(|(taken_at, _)| now.duration_since(*taken_at) > max_window)
+            {
+                state.history.pop_front();
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn notify(&self, kind: AlertEventKind, alert: Alert) {
+        let event = AlertEvent { event: kind, alert };
+        let results = join_all(self.sinks.iter().map(|sink| sink.notify(&event))).await;
+        for (sink, result) in self.sinks.iter().zip(results) {
+            if let Err(e) = result {
+                tracing::error!(sink = sink.name(), id = %event.alert.id, "Failed to deliver alert: {}", e);
+            }
+        }
+    }
+}
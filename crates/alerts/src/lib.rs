@@ -0,0 +1,7 @@
+pub mod engine;
+pub mod rules;
+pub mod sinks;
+
+pub use engine::*;
+pub use rules::*;
+pub use sinks::*;
@@ -0,0 +1,422 @@
+use db::alerts::AlertSeverity;
+use metrics::{HealthStatus, MetricSample, MetricValue, SystemHealth};
+use serde::{Deserialize, Deserializer};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+
+/// Contents of the alert rules file.
+///
+/// ```toml
+/// [[rules]]
+/// name = "relayer_balance_low"
+/// severity = "critical"
+/// condition = { kind = "threshold", metric = "gas_relayer_balance_eth", op = "below", value = 0.5 }
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AlertRules {
+    #[serde(default)]
+    pub rules: Vec<AlertRule>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlertRule {
+    /// Unique; at most one alert per rule is open at a time.
+    pub name: String,
+    pub severity: AlertSeverity,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// How long the condition must hold before the alert fires.
+    #[serde(default)]
+    pub for_secs: u64,
+    pub condition: Condition,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Condition {
+    /// Current value of a counter or gauge, summed over the series matching `labels`.
+    /// Histograms are addressed as `<name>_count` and `<name>_sum`.
+    Threshold {
+        metric: String,
+        #[serde(default)]
+        labels: BTreeMap<String, String>,
+        op: Comparison,
+        value: f64,
+    },
+    /// Increase of `numerator` divided by the increase of `denominator` over the last
+    /// `window_secs`, e.g. failed over total transactions. Not evaluated until the
+    /// denominator grew by at least `min_events`.
+    Ratio {
+        numerator: String,
+        denominator: String,
+        window_secs: u64,
+        op: Comparison,
+        value: f64,
+        #[serde(default = "default_min_events")]
+        min_events: f64,
+    },
+    /// A health component, or the overall status without `component`, at `status` or worse.
+    Health {
+        #[serde(default)]
+        component: Option<String>,
+        #[serde(deserialize_with = "health_status")]
+        status: HealthStatus,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Comparison {
+    Above,
+    Below,
+}
+
+/// What a condition saw during one evaluation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    pub firing: bool,
+    pub message: String,
+    pub value: serde_json::Value,
+}
+
+/// Inputs of one evaluation. `history` holds earlier metric snapshots, oldest first,
+/// each with its age relative to `samples`.
+pub struct EvaluationContext<'a> {
+    pub samples: &'a [MetricSample],
+    pub history: &'a [(Duration, Vec<MetricSample>)],
+    pub health: Option<&'a SystemHealth>,
+}
+
+impl AlertRules {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("failed to read {}: {e}", path.display()))?;
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> anyhow::Result<Self> {
+        let rules: AlertRules = toml::from_str(contents)?;
+
+        let mut names = HashSet::new();
+        for rule in &rules.rules {
+            anyhow::ensure!(!rule.name.is_empty(), "alert rule names must not be empty");
+            anyhow::ensure!(
+                names.insert(rule.name.as_str()),
+                "duplicate alert rule {}",
+                rule.name
+            );
+            if let Condition::Ratio { window_secs, .. } = rule.condition {
+                anyhow::ensure!(
+                    window_secs > 0,
+                    "{}: window_secs must be greater than zero",
+                    rule.name
+                );
+            }
+        }
+
+        Ok(rules)
+    }
+
+    /// Longest ratio window, i.e. how much metric history the engine has to keep.
+    pub fn max_window(&self) -> Duration {
+        self.rules
+            .iter()
+            .filter_map(|rule| match rule.condition {
+                Condition::Ratio { window_secs, .. } => Some(Duration::from_secs(window_secs)),
+                _ => None,
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    pub fn needs_health(&self) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| matches!(rule.condition, Condition::Health { .. }))
+    }
+}
+
+impl Comparison {
+    fn holds(&self, observed: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Above => observed > threshold,
+            Comparison::Below => observed < threshold,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Comparison::Above => "above",
+            Comparison::Below => "below",
+        }
+    }
+}
+
+impl Condition {
+    /// `None` when there is not enough data yet; the rule then keeps its previous state.
+    pub fn evaluate(&self, context: &EvaluationContext) -> Option<Observation> {
+        match self {
+            Condition::Threshold {
+                metric,
+                labels,
+                op,
+                value,
+            } => {
+                let observed = metric_value(context.samples, metric, labels)?;
+                Some(Observation {
+                    firing: op.holds(observed, *value),
+                    message: format!("{metric} is {observed}, {} {value}", op.as_str()),
+                    value: serde_json::json!(observed),
+                })
+            }
+            Condition::Ratio {
+                numerator,
+                denominator,
+                window_secs,
+                op,
+                value,
+                min_events,
+            } => {
+                let window = Duration::from_secs(*window_secs);
+                let (_, oldest) = context.history.iter().find(|(age, _)| *age <= window)?;
+
+                let no_labels = BTreeMap::new();
+                let increase = |name: &str| {
+                    let now = metric_value(context.samples, name, &no_labels)?;
+                    let then = metric_value(oldest, name, &no_labels).unwrap_or_default();
+                    Some((now - then).max(0.0))
+                };
+                let events = increase(denominator)?;
+                if events < *min_events || events == 0.0 {
+                    return Some(Observation {
+                        firing: false,
+                        message: format!("{denominator} grew by {events} over {window_secs}s"),
+                        value: serde_json::Value::Null,
+                    });
+                }
+
+                let ratio = increase(numerator)? / events;
+                Some(Observation {
+                    firing: op.holds(ratio, *value),
+                    message: format!(
+                        "{numerator} / {denominator} is {ratio:.4} over {window_secs}s, {} {value}",
+                        op.as_str()
+                    ),
+                    value: serde_json::json!(ratio),
+                })
+            }
+            Condition::Health { component, status } => {
+                let health = context.health?;
+                let (subject, observed) = match component {
+                    Some(name) => (name.as_str(), health.components.get(name)?.status),
+                    None => ("overall", health.overall_status),
+                };
+                Some(Observation {
+                    firing: observed >= *status,
+                    message: format!("{subject} health is {}", observed.as_str()),
+                    value: serde_json::json!(observed.as_str()),
+                })
+            }
+        }
+    }
+}
+
+/// Sum over the series of `name` whose labels include `labels`.
+fn metric_value(
+    samples: &[MetricSample],
+    name: &str,
+    labels: &BTreeMap<String, String>,
+) -> Option<f64> {
+    let mut total = None;
+    for sample in samples {
+        if !labels.iter().all(|(k, v)| sample.labels.get(k) == Some(v)) {
+            continue;
+        }
+        let value = match sample.value {
+            MetricValue::Counter(value) | MetricValue::Gauge(value) if sample.name == name => value,
+            MetricValue::Histogram { count, sum } => {
+                match name.strip_prefix(sample.name.as_str()) {
+                    Some("_count") => count as f64,
+                    Some("_sum") => sum,
+                    _ => continue,
+                }
+            }
+            _ => continue,
+        };
+        *total.get_or_insert(0.0) += value;
+    }
+    total
+}
+
+fn default_min_events() -> f64 {
+    1.0
+}
+
+fn health_status<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HealthStatus, D::Error> {
+    match String::deserialize(deserializer)?.as_str() {
+        "healthy" => Ok(HealthStatus::Healthy),
+        "degraded" => Ok(HealthStatus::Degraded),
+        "unhealthy" => Ok(HealthStatus::Unhealthy),
+        other => Err(serde::de::Error::custom(format!(
+            "invalid health status {other}, expected healthy, degraded or unhealthy"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metrics::ComponentHealth;
+
+    const RULES: &str = r#"
+        [[rules]]
+        name = "relayer_balance_low"
+        severity = "critical"
+        condition = { kind = "threshold", metric = "gas_relayer_balance_eth", op = "below", value = 0.5 }
+
+        [[rules]]
+        name = "failure_rate_high"
+        severity = "warning"
+        condition = { kind = "ratio", numerator = "gas_relayer_transactions_failed_total", denominator = "gas_relayer_transactions_total", window_secs = 600, op = "above", value = 0.1 }
+
+        [[rules]]
+        name = "queue_backlog"
+        severity = "warning"
+        for_secs = 300
+        condition = { kind = "threshold", metric = "gas_relayer_queue_depth", op = "above", value = 100 }
+
+        [[rules]]
+        name = "database_down"
+        severity = "critical"
+        condition = { kind = "health", component = "database", status = "unhealthy" }
+    "#;
+
+    fn sample(name: &str, value: MetricValue) -> MetricSample {
+        MetricSample {
+            name: name.to_string(),
+            labels: BTreeMap::new(),
+            value,
+        }
+    }
+
+    fn context<'a>(
+        samples: &'a [MetricSample],
+        history: &'a [(Duration, Vec<MetricSample>)],
+    ) -> EvaluationContext<'a> {
+        EvaluationContext {
+            samples,
+            history,
+            health: None,
+        }
+    }
+
+    #[test]
+    fn parses_rules_and_rejects_duplicates() {
+        let rules = AlertRules::parse(RULES).unwrap();
+        assert_eq!(rules.rules.len(), 4);
+        assert_eq!(rules.rules[2].for_secs, 300);
+        assert_eq!(rules.max_window(), Duration::from_secs(600));
+        assert!(rules.needs_health());
+
+        let duplicated = r#"
+            [[rules]]
+            name = "queue_backlog"
+            severity = "info"
+            condition = { kind = "threshold", metric = "gas_relayer_queue_depth", op = "above", value = 10 }
+        "#;
+        assert!(AlertRules::parse(&format!("{RULES}{duplicated}")).is_err());
+    }
+
+    #[test]
+    fn parses_the_example_rules() {
+        let rules = AlertRules::parse(include_str!("../../../alerts.example.toml")).unwrap();
+        assert_eq!(rules.rules.len(), 5);
+    }
+
+    #[test]
+    fn evaluates_thresholds() {
+        let rules = AlertRules::parse(RULES).unwrap();
+        let samples = vec![
+            sample("gas_relayer_balance_eth", MetricValue::Gauge(0.2)),
+            sample("gas_relayer_queue_depth", MetricValue::Gauge(20.0)),
+        ];
+
+        let balance = rules.rules[0]
+            .condition
+            .evaluate(&context(&samples, &[]))
+            .unwrap();
+        assert!(balance.firing);
+        assert_eq!(balance.message, "gas_relayer_balance_eth is 0.2, below 0.5");
+
+        let backlog = rules.rules[2]
+            .condition
+            .evaluate(&context(&samples, &[]))
+            .unwrap();
+        assert!(!backlog.firing);
+
+        let missing = rules.rules[0].condition.evaluate(&context(&[], &[]));
+        assert!(missing.is_none());
+    }
+
+    #[test]
+    fn evaluates_ratios_over_the_window() {
+        let rules = AlertRules::parse(RULES).unwrap();
+        let counters = |failed: f64, total: f64| {
+            vec![
+                sample(
+                    "gas_relayer_transactions_failed_total",
+                    MetricValue::Counter(failed),
+                ),
+                sample(
+                    "gas_relayer_transactions_total",
+                    MetricValue::Counter(total),
+                ),
+            ]
+        };
+        let now = counters(30.0, 200.0);
+        let history = vec![
+            // Outside the window, ignored.
+            (Duration::from_secs(900), counters(0.0, 0.0)),
+            (Duration::from_secs(540), counters(10.0, 100.0)),
+        ];
+
+        let observation = rules.rules[1]
+            .condition
+            .evaluate(&context(&now, &history))
+            .unwrap();
+        assert!(observation.firing);
+        assert_eq!(observation.value, serde_json::json!(0.2));
+
+        // Without history in the window there is nothing to compare against.
+        assert!(rules.rules[1]
+            .condition
+            .evaluate(&context(&now, &history[..1]))
+            .is_none());
+
+        // No new transactions means no failure rate.
+        let idle = rules.rules[1]
+            .condition
+            .evaluate(&context(&now, &[(Duration::from_secs(60), now.clone())]))
+            .unwrap();
+        assert!(!idle.firing);
+    }
+
+    #[test]
+    fn evaluates_health_components() {
+        let rules = AlertRules::parse(RULES).unwrap();
+        let mut health = SystemHealth::new(0);
+        health.add_component("database", ComponentHealth::unhealthy("down"));
+
+        let observation = rules.rules[3]
+            .condition
+            .evaluate(&EvaluationContext {
+                samples: &[],
+                history: &[],
+                health: Some(&health),
+            })
+            .unwrap();
+        assert!(observation.firing);
+        assert_eq!(observation.message, "database health is unhealthy");
+    }
+}
@@ -0,0 +1,85 @@
+use db::alerts::{Alert, AlertSeverity};
+use futures::future::BoxFuture;
+use serde::Serialize;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertEventKind {
+    Fired,
+    Resolved,
+}
+
+/// Payload handed to every sink when an alert opens or closes.
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertEvent {
+    pub event: AlertEventKind,
+    #[serde(flatten)]
+    pub alert: Alert,
+}
+
+/// Destination for alert notifications.
+pub trait AlertSink: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn notify<'a>(&'a self, event: &'a AlertEvent) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+/// Writes alerts to the service log at a level matching their severity.
+pub struct LogSink;
+
+/// POSTs every event as JSON to a fixed URL.
+pub struct WebhookSink {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl AlertSink for LogSink {
+    fn name(&self) -> &str {
+        "log"
+    }
+
+    fn notify<'a>(&'a self, event: &'a AlertEvent) -> BoxFuture<'a, anyhow::Result<()>> {
+        let alert = &event.alert;
+        match (event.event, alert.severity) {
+            (AlertEventKind::Resolved, _) => {
+                tracing::info!(id = %alert.id, name = %alert.alert_name, "Alert resolved: {}", alert.message)
+            }
+            (AlertEventKind::Fired, AlertSeverity::Critical) => {
+                tracing::error!(id = %alert.id, name = %alert.alert_name, "Alert fired: {}", alert.message)
+            }
+            (AlertEventKind::Fired, AlertSeverity::Warning) => {
+                tracing::warn!(id = %alert.id, name = %alert.alert_name, "Alert fired: {}", alert.message)
+            }
+            (AlertEventKind::Fired, AlertSeverity::Info) => {
+                tracing::info!(id = %alert.id, name = %alert.alert_name, "Alert fired: {}", alert.message)
+            }
+        }
+        Box::pin(async { Ok(()) })
+    }
+}
+
+impl WebhookSink {
+    pub fn new(url: String, timeout: Duration) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder().timeout(timeout).build()?;
+        Ok(Self { url, client })
+    }
+}
+
+impl AlertSink for WebhookSink {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    fn notify<'a>(&'a self, event: &'a AlertEvent) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            self.client
+                .post(&self.url)
+                .json(event)
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok(())
+        })
+    }
+}
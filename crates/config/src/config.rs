@@ -1,6 +1,25 @@
+use crate::loader::{ConfigError, ConfigSource, Reader};
 use serde::Deserialize;
+use std::fmt;
 use std::net::{Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
+
+/// Derivation path of the first account of a BIP-44 Ethereum wallet.
+pub const DEFAULT_DERIVATION_PATH: &str = "m/44'/60'/0'/0/0";
+
+/// Priority fee reward percentiles of the slow, normal and fast suggestions.
+const DEFAULT_FEE_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+
+const SIGNER_KEYS: [&str; 6] = [
+    "signer.private_key",
+    "signer.keystore_path",
+    "signer.keystore_password",
+    "signer.keystore_password_file",
+    "signer.mnemonic",
+    "signer.derivation_path",
+];
 
 #[derive(Deserialize, Clone, Debug)]
 pub enum Environment {
@@ -8,6 +27,231 @@ pub enum Environment {
     Production,
 }
 
+/// A string that must never end up in logs; `Debug` prints it redacted.
+#[derive(Clone, Deserialize)]
+pub struct SecretString(String);
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum KeystorePassword {
+    Value(SecretString),
+    File(PathBuf),
+}
+
+/// Where the relayer account's key comes from, selected by `signer.kind`.
+#[derive(Debug, Clone, Deserialize)]
+pub enum SignerConfig {
+    PrivateKey(SecretString),
+    Keystore {
+        path: PathBuf,
+        password: KeystorePassword,
+    },
+    Mnemonic {
+        phrase: SecretString,
+        derivation_path: String,
+    },
+}
+
+/// Tuning for the background worker that broadcasts queued requests.
+#[derive(Debug, Clone, Deserialize)]
+pub struct QueueConfig {
+    pub concurrency: usize,
+    pub poll_interval: Duration,
+    pub batch_size: u32,
+    pub max_retries: u32,
+}
+
+/// How submitted transactions are followed until they are final.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrackerConfig {
+    pub confirmations: u64,
+    pub poll_interval: Duration,
+    /// Time without a receipt before a transaction is replaced with higher fees.
+    pub bump_after: Duration,
+    /// Bumping stops once the next replacement would pay more than this, in wei per gas.
+    pub max_fee_cap: u128,
+    /// Time since the first broadcast after which a transaction without a receipt counts as
+    /// stuck in `gas_relayer_transactions_stuck`.
+    pub max_pending: Duration,
+}
+
+/// JSON-RPC endpoints of the chain and how the pool fails over between them.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RpcConfig {
+    pub urls: Vec<String>,
+    /// Consecutive failures that open an endpoint's circuit breaker.
+    pub failure_threshold: u32,
+    /// How long an open circuit keeps the endpoint out of rotation.
+    pub cooldown: Duration,
+    /// Extra attempts for read-only calls, each on the next healthiest endpoint.
+    pub max_retries: u32,
+    pub request_timeout: Duration,
+}
+
+/// Fee suggestions derived from `eth_feeHistory`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GasOracleConfig {
+    /// Priority fee reward percentiles for the slow, normal and fast suggestions.
+    pub percentiles: [f64; 3],
+    /// Number of recent blocks sampled.
+    pub block_count: u64,
+    pub cache_ttl: Duration,
+}
+
+/// Thresholds at which `/health` reports a component as degraded.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HealthConfig {
+    pub min_relayer_balance_eth: f64,
+    pub max_block_lag: u64,
+    pub max_queue_backlog: i64,
+    pub max_db_latency: Duration,
+}
+
+/// How often health results and metric values are written to the monitoring tables.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MonitoringConfig {
+    pub snapshot_interval: Duration,
+    /// Rows older than this are pruned.
+    pub retention: Duration,
+}
+
+/// Where alert rules are read from and where fired alerts are sent.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlertingConfig {
+    /// TOML file with the alert rules; no rules are evaluated without it.
+    pub rules_path: Option<PathBuf>,
+    pub evaluation_interval: Duration,
+    /// Fired and resolved alerts are POSTed here as JSON, in addition to the log.
+    pub webhook_url: Option<String>,
+    pub webhook_timeout: Duration,
+}
+
+/// Where the relay policy (allowed targets, selectors and limits) is read from.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum PolicySource {
+    /// Every correctly signed request is relayed.
+    None,
+    File(PathBuf),
+    /// The newest row of `relay_policies`.
+    Database,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PolicyConfig {
+    pub source: PolicySource,
+    /// How often the source is re-read, so edits apply without a restart.
+    pub reload_interval: Duration,
+}
+
+/// Where rate limit buckets live: per process, or in Postgres so replicas share them.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum RateLimitBackend {
+    Memory,
+    Postgres,
+}
+
+/// A token bucket: `burst` requests at once, refilled at `per_minute`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct RateLimit {
+    pub per_minute: u32,
+    pub burst: u32,
+}
+
+/// Limit of the API keys in a tier, read from `rate_limit.tiers.<name>`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitTier {
+    pub name: String,
+    pub limit: RateLimit,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub backend: RateLimitBackend,
+    /// Per client IP, taken from `X-Forwarded-For` when `trust_forwarded_for` is set.
+    pub ip: RateLimit,
+    pub trust_forwarded_for: bool,
+    /// Per recovered `from` address of relay requests.
+    pub sender: RateLimit,
+    /// Per API key, by the tier stored with the key; keys without one get the `default` tier.
+    pub tiers: Vec<RateLimitTier>,
+}
+
+/// Which routes need an API key, and the key of the admin API.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthConfig {
+    /// Without it every request is attributed to the default tenant.
+    pub enabled: bool,
+    /// Bearer token of the `/admin` routes, which are disabled without one.
+    pub admin_key: Option<SecretString>,
+    /// Health probes and their history answer without an API key.
+    pub public_health: bool,
+    /// `/metrics` and the metric history answer without an API key.
+    pub public_metrics: bool,
+}
+
+/// How relay status callbacks are delivered from the `webhook_deliveries` outbox.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+    /// Signs the callbacks of tenants that have no webhook secret of their own.
+    pub secret: Option<SecretString>,
+    pub timeout: Duration,
+    pub poll_interval: Duration,
+    pub batch_size: u32,
+    /// Attempts before a delivery is dead-lettered.
+    pub max_attempts: u32,
+    /// Wait after the first failure, doubled after each further one up to `max_backoff`.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+/// Pre-flight `eth_call` of `TrustedForwarder.execute`, so requests that would revert are
+/// refused instead of paid for.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SimulationConfig {
+    pub enabled: bool,
+    /// Requests queued longer than this are simulated again right before broadcast.
+    pub resimulate_after: Duration,
+}
+
+/// Grouping of relay requests into one `TrustedForwarder.executeBatch` transaction.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchingConfig {
+    /// Let the queue worker batch compatible pending requests on its own. Batches sent
+    /// through `POST /relay/batch` are relayed either way.
+    pub enabled: bool,
+    /// Gas limit a batch transaction may reach.
+    pub max_gas: u64,
+    /// Longest a claimed request waits for others to share its batch.
+    pub window: Duration,
+    pub max_requests: usize,
+    /// Receives the value of batched requests the forwarder skips; the relayer account
+    /// that paid it when unset.
+    pub refund_receiver: Option<String>,
+}
+
+/// How relay status changes are streamed to SSE and WebSocket clients.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventStreamConfig {
+    /// Events kept for slow subscribers; ones that fall further behind miss events.
+    pub buffer_size: usize,
+    /// Idle streams get a keep-alive this often so proxies do not close them.
+    pub keep_alive: Duration,
+}
+
+/// One chain the relayer submits to, read from `chains.<name>`. Keys missing there fall back
+/// to the top-level ones, which also describe the only chain when there is no `chains` section.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChainConfig {
+    pub name: String,
+    pub chain_id: u64,
+    pub rpc: RpcConfig,
+    pub forwarder_address: String,
+    pub forwarder_name: String,
+    pub signer: SignerConfig,
+    pub tracker: TrackerConfig,
+    pub gas_oracle: GasOracleConfig,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Configuration {
     pub environment: Environment,
@@ -15,35 +259,675 @@ pub struct Configuration {
     pub app_port: u16,
     pub max_db_connection: u8,
     pub listening_addr: SocketAddr,
+    pub chains: Vec<ChainConfig>,
+    pub queue: QueueConfig,
+    pub health: HealthConfig,
+    pub monitoring: MonitoringConfig,
+    pub alerting: AlertingConfig,
+    pub policy: PolicyConfig,
+    pub rate_limit: RateLimitConfig,
+    pub auth: AuthConfig,
+    pub webhooks: WebhookConfig,
+    pub events: EventStreamConfig,
+    pub simulation: SimulationConfig,
+    pub batching: BatchingConfig,
 }
 
 impl Configuration {
-    pub fn load() -> Self {
-        let environment = load_env_var("APP_ENVIRONMENT")
-            .try_into()
-            .expect("APP_ENVIRONMENT is not a valid environment");
-        let port: u16 = load_env_var("APP_PORT")
-            .parse::<u16>()
-            .expect("APP_PORT is not a valid port");
-        let database_url = load_env_var("DATABASE_URL")
-            .try_into()
-            .expect("DATABASE_URL is not set");
-        let max_db_connection: u8 = load_env_var("MAX_DB_CONNECTION")
-            .parse::<u8>()
-            .expect("MAX_DB_CONNECTION is not a valid number");
+    /// Loads the layered configuration: defaults, then the file named by `APP_CONFIG_FILE`,
+    /// then `APP_*` environment variables.
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::from_source(&ConfigSource::from_env()?)
+    }
+
+    /// Builds and validates the configuration, reporting every invalid field at once.
+    pub fn from_source(source: &ConfigSource) -> Result<Self, ConfigError> {
+        let mut reader = source.reader();
+
+        let environment = match reader.or("environment", "Local".to_string()).try_into() {
+            Ok(environment) => environment,
+            Err(_) => {
+                reader.invalid("environment", "must be Local or Production");
+                Environment::Local
+            }
+        };
+        let port: u16 = reader.or("port", 8080);
+        let database_url: String = reader.required("database_url").unwrap_or_default();
+        let chains = read_chains(&mut reader);
+        let queue = QueueConfig::read(&mut reader);
+
+        // Enough for every chain's worker plus the API
+        let default_connections = (queue.concurrency * chains.len() + 1).clamp(5, u8::MAX as usize);
+        let max_db_connection: u8 = reader.or("max_db_connections", default_connections as u8);
+        reader.ensure(
+            max_db_connection > 0,
+            "max_db_connections",
+            "must be greater than zero",
+        );
+        let health = HealthConfig::read(&mut reader);
+        let monitoring = MonitoringConfig::read(&mut reader);
+        let alerting = AlertingConfig::read(&mut reader);
+        let policy = PolicyConfig::read(&mut reader);
+        let rate_limit = RateLimitConfig::read(&mut reader);
+        let auth = AuthConfig::read(&mut reader);
+        let webhooks = WebhookConfig::read(&mut reader);
+        let events = EventStreamConfig::read(&mut reader);
+        let simulation = SimulationConfig::read(&mut reader);
+        let batching = BatchingConfig::read(&mut reader);
+
+        // Constraints spanning several sections
+        reader.ensure(
+            max_db_connection as usize > queue.concurrency * chains.len(),
+            "max_db_connections",
+            "must be greater than queue.concurrency times the number of chains so the API keeps a connection while the workers are busy",
+        );
+        reader.ensure(
+            monitoring.retention > monitoring.snapshot_interval,
+            "monitoring.retention_hours",
+            "must be longer than monitoring.interval_secs",
+        );
+
+        reader.finish()?;
 
         let listening_addr: SocketAddr = SocketAddr::from((Ipv6Addr::UNSPECIFIED, port));
 
-        Self {
+        Ok(Self {
             environment,
             database_url,
             app_port: port,
             max_db_connection,
             listening_addr,
+            chains,
+            queue,
+            health,
+            monitoring,
+            alerting,
+            policy,
+            rate_limit,
+            auth,
+            webhooks,
+            events,
+            simulation,
+            batching,
+        })
+    }
+}
+
+/// Every `chains.<name>` entry, or the top-level keys as a single chain named `default`.
+fn read_chains(reader: &mut Reader) -> Vec<ChainConfig> {
+    let names = reader.children("chains");
+    if names.is_empty() {
+        return vec![ChainConfig::read(reader, "default".to_string())];
+    }
+
+    let mut chains: Vec<ChainConfig> = Vec::new();
+    for name in names {
+        let scope = format!("chains.{name}");
+        reader.ensure(
+            name.chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'),
+            &scope,
+            "chain names may only contain a-z, 0-9 and _ so they can be set from the environment",
+        );
+        let chain = reader.scoped(&scope, |reader| {
+            let chain = ChainConfig::read(reader, name);
+            let duplicate = chains
+                .iter()
+                .find(|other| chain.chain_id != 0 && other.chain_id == chain.chain_id);
+            if let Some(other) = duplicate {
+                let message = format!("duplicates the chain id of chains.{}", other.name);
+                reader.invalid("chain_id", &message);
+            }
+            chain
+        });
+        chains.push(chain);
+    }
+    chains
+}
+
+impl ChainConfig {
+    fn read(reader: &mut Reader, name: String) -> Self {
+        let chain_id: u64 = reader.required("chain_id").unwrap_or_default();
+        let forwarder_address: String = reader.required("forwarder_address").unwrap_or_default();
+        let forwarder_name: String = reader.or("forwarder_name", "TrustedForwarder".to_string());
+
+        reader.ensure(
+            forwarder_address.is_empty() || is_hex_address(&forwarder_address),
+            "forwarder_address",
+            "must be a 0x-prefixed 20 byte hex address",
+        );
+
+        let rpc = RpcConfig::read(reader);
+        let signer = SignerConfig::read(reader);
+        let tracker = TrackerConfig::read(reader);
+        let gas_oracle = GasOracleConfig::read(reader);
+
+        reader.ensure(
+            tracker.bump_after > tracker.poll_interval,
+            "tracker.bump_after_secs",
+            "must be longer than tracker.poll_interval_ms, or transactions are replaced before their receipt is checked",
+        );
+        reader.ensure(
+            tracker.max_pending > tracker.bump_after,
+            "tracker.max_pending_secs",
+            "must be longer than tracker.bump_after_secs",
+        );
+
+        Self {
+            name,
+            chain_id,
+            rpc,
+            forwarder_address,
+            forwarder_name,
+            signer,
+            tracker,
+            gas_oracle,
         }
     }
 }
 
+impl QueueConfig {
+    fn read(reader: &mut Reader) -> Self {
+        let concurrency: usize = reader.or("queue.concurrency", 4);
+        let poll_interval_ms: u64 = reader.or("queue.poll_interval_ms", 1000);
+        let batch_size: u32 = reader.or("queue.batch_size", 10);
+        let max_retries: u32 = reader.or("queue.max_retries", 3);
+
+        reader.ensure(
+            concurrency > 0,
+            "queue.concurrency",
+            "must be greater than zero",
+        );
+        reader.ensure(
+            batch_size > 0,
+            "queue.batch_size",
+            "must be greater than zero",
+        );
+
+        Self {
+            concurrency,
+            poll_interval: Duration::from_millis(poll_interval_ms),
+            batch_size,
+            max_retries,
+        }
+    }
+}
+
+impl TrackerConfig {
+    fn read(reader: &mut Reader) -> Self {
+        let confirmations: u64 = reader.or("tracker.confirmations", 2);
+        let poll_interval_ms: u64 = reader.or("tracker.poll_interval_ms", 3000);
+        let bump_after_secs: u64 = reader.or("tracker.bump_after_secs", 60);
+        let max_fee_cap_gwei: u64 = reader.or("tracker.max_fee_per_gas_gwei", 500);
+        let max_pending_secs: u64 = reader.or("tracker.max_pending_secs", 3600);
+
+        reader.ensure(
+            confirmations > 0,
+            "tracker.confirmations",
+            "must be greater than zero",
+        );
+        reader.ensure(
+            max_fee_cap_gwei > 0,
+            "tracker.max_fee_per_gas_gwei",
+            "must be greater than zero",
+        );
+
+        Self {
+            confirmations,
+            poll_interval: Duration::from_millis(poll_interval_ms),
+            bump_after: Duration::from_secs(bump_after_secs),
+            max_fee_cap: max_fee_cap_gwei as u128 * 1_000_000_000,
+            max_pending: Duration::from_secs(max_pending_secs),
+        }
+    }
+}
+
+impl RpcConfig {
+    fn read(reader: &mut Reader) -> Self {
+        let urls: Vec<String> = match reader.list("rpc.urls") {
+            Some(urls) if !urls.is_empty() => urls,
+            Some(_) => {
+                reader.invalid("rpc.urls", "must contain at least one URL");
+                Vec::new()
+            }
+            None => {
+                reader.required::<String>("rpc.urls");
+                Vec::new()
+            }
+        };
+        let failure_threshold: u32 = reader.or("rpc.failure_threshold", 3);
+        let cooldown_secs: u64 = reader.or("rpc.cooldown_secs", 30);
+        let max_retries: u32 = reader.or("rpc.max_retries", 2);
+        let timeout_ms: u64 = reader.or("rpc.timeout_ms", 10_000);
+
+        reader.ensure(
+            urls.iter()
+                .all(|url| is_url(url, &["http", "https", "ws", "wss"])),
+            "rpc.urls",
+            "must be http(s) or ws(s) URLs",
+        );
+        reader.ensure(
+            failure_threshold > 0,
+            "rpc.failure_threshold",
+            "must be greater than zero",
+        );
+        reader.ensure(
+            timeout_ms > 0,
+            "rpc.timeout_ms",
+            "must be greater than zero",
+        );
+
+        Self {
+            urls,
+            failure_threshold,
+            cooldown: Duration::from_secs(cooldown_secs),
+            max_retries,
+            request_timeout: Duration::from_millis(timeout_ms),
+        }
+    }
+}
+
+impl GasOracleConfig {
+    fn read(reader: &mut Reader) -> Self {
+        let percentiles = match reader.list::<f64>("gas_oracle.percentiles") {
+            Some(values) => match <[f64; 3]>::try_from(values) {
+                Ok(percentiles) => percentiles,
+                Err(_) => {
+                    reader.invalid(
+                        "gas_oracle.percentiles",
+                        "needs exactly three values: slow, normal, fast",
+                    );
+                    DEFAULT_FEE_PERCENTILES
+                }
+            },
+            None => DEFAULT_FEE_PERCENTILES,
+        };
+        let block_count: u64 = reader.or("gas_oracle.blocks", 20);
+        let cache_ttl_ms: u64 = reader.or("gas_oracle.ttl_ms", 5000);
+
+        reader.ensure(
+            percentiles.windows(2).all(|w| w[0] <= w[1])
+                && percentiles.iter().all(|p| (0.0..=100.0).contains(p)),
+            "gas_oracle.percentiles",
+            "must be ascending values between 0 and 100",
+        );
+        reader.ensure(
+            block_count > 0,
+            "gas_oracle.blocks",
+            "must be greater than zero",
+        );
+
+        Self {
+            percentiles,
+            block_count,
+            cache_ttl: Duration::from_millis(cache_ttl_ms),
+        }
+    }
+}
+
+impl HealthConfig {
+    fn read(reader: &mut Reader) -> Self {
+        let min_relayer_balance_eth: f64 = reader.or("health.min_balance_eth", 0.1);
+        let max_db_latency_ms: u64 = reader.or("health.max_db_latency_ms", 250);
+
+        reader.ensure(
+            min_relayer_balance_eth >= 0.0,
+            "health.min_balance_eth",
+            "must not be negative",
+        );
+
+        Self {
+            min_relayer_balance_eth,
+            max_block_lag: reader.or("health.max_block_lag", 5),
+            max_queue_backlog: reader.or("health.max_queue_backlog", 100),
+            max_db_latency: Duration::from_millis(max_db_latency_ms),
+        }
+    }
+}
+
+impl MonitoringConfig {
+    fn read(reader: &mut Reader) -> Self {
+        let interval_secs: u64 = reader.or("monitoring.interval_secs", 60);
+        let retention_hours: u64 = reader.or("monitoring.retention_hours", 168);
+
+        reader.ensure(
+            interval_secs > 0,
+            "monitoring.interval_secs",
+            "must be greater than zero",
+        );
+        reader.ensure(
+            retention_hours > 0,
+            "monitoring.retention_hours",
+            "must be greater than zero",
+        );
+
+        Self {
+            snapshot_interval: Duration::from_secs(interval_secs),
+            retention: Duration::from_secs(retention_hours * 3600),
+        }
+    }
+}
+
+impl AlertingConfig {
+    fn read(reader: &mut Reader) -> Self {
+        let rules_path: Option<PathBuf> = reader.optional("alerting.rules_path");
+        let interval_secs: u64 = reader.or("alerting.eval_interval_secs", 30);
+        let webhook_url: Option<String> = reader.optional("alerting.webhook_url");
+        let webhook_timeout_ms: u64 = reader.or("alerting.webhook_timeout_ms", 5000);
+
+        reader.ensure(
+            interval_secs > 0,
+            "alerting.eval_interval_secs",
+            "must be greater than zero",
+        );
+        reader.ensure(
+            rules_path.as_ref().is_none_or(|path| path.is_file()),
+            "alerting.rules_path",
+            "must be an existing file",
+        );
+        reader.ensure(
+            webhook_url
+                .as_deref()
+                .is_none_or(|url| is_url(url, &["http", "https"])),
+            "alerting.webhook_url",
+            "must be an http(s) URL",
+        );
+
+        Self {
+            rules_path,
+            evaluation_interval: Duration::from_secs(interval_secs),
+            webhook_url,
+            webhook_timeout: Duration::from_millis(webhook_timeout_ms),
+        }
+    }
+}
+
+impl PolicyConfig {
+    fn read(reader: &mut Reader) -> Self {
+        let rules_path: Option<PathBuf> = reader.optional("policy.rules_path");
+        let default_source = if rules_path.is_some() { "file" } else { "none" };
+        let source: String = reader.or("policy.source", default_source.to_string());
+        let reload_interval_secs: u64 = reader.or("policy.reload_interval_secs", 30);
+
+        reader.ensure(
+            reload_interval_secs > 0,
+            "policy.reload_interval_secs",
+            "must be greater than zero",
+        );
+        let source = match (source.as_str(), rules_path) {
+            ("none", _) => PolicySource::None,
+            ("database", _) => PolicySource::Database,
+            ("file", Some(path)) => {
+                reader.ensure(
+                    path.is_file(),
+                    "policy.rules_path",
+                    "must be an existing file",
+                );
+                PolicySource::File(path)
+            }
+            ("file", None) => {
+                reader.required::<PathBuf>("policy.rules_path");
+                PolicySource::None
+            }
+            _ => {
+                reader.invalid("policy.source", "must be one of none, file or database");
+                PolicySource::None
+            }
+        };
+
+        Self {
+            source,
+            reload_interval: Duration::from_secs(reload_interval_secs),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    fn read(reader: &mut Reader) -> Self {
+        let enabled: bool = reader.or("rate_limit.enabled", true);
+        let backend = match reader
+            .or("rate_limit.backend", "memory".to_string())
+            .as_str()
+        {
+            "memory" => RateLimitBackend::Memory,
+            "postgres" => RateLimitBackend::Postgres,
+            _ => {
+                reader.invalid("rate_limit.backend", "must be memory or postgres");
+                RateLimitBackend::Memory
+            }
+        };
+        let ip = RateLimit::read(reader, "rate_limit.ip", 120, 30);
+        let trust_forwarded_for: bool = reader.or("rate_limit.trust_forwarded_for", false);
+        let sender = RateLimit::read(reader, "rate_limit.sender", 30, 10);
+
+        let mut names = reader.children("rate_limit.tiers");
+        names.insert("default".to_string());
+        let tiers = names
+            .into_iter()
+            .map(|name| {
+                let limit = RateLimit::read(reader, &format!("rate_limit.tiers.{name}"), 60, 20);
+                RateLimitTier { name, limit }
+            })
+            .collect();
+
+        Self {
+            enabled,
+            backend,
+            ip,
+            trust_forwarded_for,
+            sender,
+            tiers,
+        }
+    }
+}
+
+impl AuthConfig {
+    fn read(reader: &mut Reader) -> Self {
+        let enabled: bool = reader.or("auth.enabled", true);
+        let admin_key: Option<String> = reader.optional("auth.admin_key");
+
+        reader.ensure(
+            admin_key.as_ref().is_none_or(|key| key.len() >= 16),
+            "auth.admin_key",
+            "must be at least 16 characters",
+        );
+
+        Self {
+            enabled,
+            admin_key: admin_key.map(SecretString::new),
+            public_health: reader.or("auth.public_health", true),
+            public_metrics: reader.or("auth.public_metrics", true),
+        }
+    }
+}
+
+impl WebhookConfig {
+    fn read(reader: &mut Reader) -> Self {
+        let secret: Option<String> = reader.optional("webhooks.secret");
+        let timeout_ms: u64 = reader.or("webhooks.timeout_ms", 5000);
+        let poll_interval_ms: u64 = reader.or("webhooks.poll_interval_ms", 1000);
+        let batch_size: u32 = reader.or("webhooks.batch_size", 20);
+        let max_attempts: u32 = reader.or("webhooks.max_attempts", 10);
+        let initial_backoff_secs: u64 = reader.or("webhooks.initial_backoff_secs", 10);
+        let max_backoff_secs: u64 = reader.or("webhooks.max_backoff_secs", 3600);
+
+        reader.ensure(
+            secret.as_ref().is_none_or(|secret| secret.len() >= 16),
+            "webhooks.secret",
+            "must be at least 16 characters",
+        );
+        reader.ensure(
+            timeout_ms > 0,
+            "webhooks.timeout_ms",
+            "must be greater than zero",
+        );
+        reader.ensure(
+            batch_size > 0,
+            "webhooks.batch_size",
+            "must be greater than zero",
+        );
+        reader.ensure(
+            max_attempts > 0,
+            "webhooks.max_attempts",
+            "must be greater than zero",
+        );
+        reader.ensure(
+            initial_backoff_secs > 0,
+            "webhooks.initial_backoff_secs",
+            "must be greater than zero",
+        );
+        reader.ensure(
+            max_backoff_secs >= initial_backoff_secs,
+            "webhooks.max_backoff_secs",
+            "must not be shorter than webhooks.initial_backoff_secs",
+        );
+
+        Self {
+            secret: secret.map(SecretString::new),
+            timeout: Duration::from_millis(timeout_ms),
+            poll_interval: Duration::from_millis(poll_interval_ms),
+            batch_size,
+            max_attempts,
+            initial_backoff: Duration::from_secs(initial_backoff_secs),
+            max_backoff: Duration::from_secs(max_backoff_secs),
+        }
+    }
+}
+
+impl SimulationConfig {
+    fn read(reader: &mut Reader) -> Self {
+        Self {
+            enabled: reader.or("simulation.enabled", true),
+            resimulate_after: Duration::from_secs(
+                reader.or("simulation.resimulate_after_secs", 30),
+            ),
+        }
+    }
+}
+
+impl BatchingConfig {
+    fn read(reader: &mut Reader) -> Self {
+        let max_gas: u64 = reader.or("batching.max_gas", 5_000_000);
+        let window_ms: u64 = reader.or("batching.window_ms", 2000);
+        let max_requests: usize = reader.or("batching.max_requests", 20);
+        let refund_receiver: Option<String> = reader.optional("batching.refund_receiver");
+
+        reader.ensure(max_gas > 0, "batching.max_gas", "must be greater than zero");
+        reader.ensure(
+            max_requests > 1,
+            "batching.max_requests",
+            "must be at least 2",
+        );
+        reader.ensure(
+            refund_receiver.as_deref().is_none_or(is_hex_address),
+            "batching.refund_receiver",
+            "must be a 0x-prefixed 20 byte hex address",
+        );
+
+        Self {
+            enabled: reader.or("batching.enabled", false),
+            max_gas,
+            window: Duration::from_millis(window_ms),
+            max_requests,
+            refund_receiver,
+        }
+    }
+}
+
+impl EventStreamConfig {
+    fn read(reader: &mut Reader) -> Self {
+        let buffer_size: usize = reader.or("events.buffer_size", 1024);
+        let keep_alive_secs: u64 = reader.or("events.keep_alive_secs", 15);
+
+        reader.ensure(
+            buffer_size > 0,
+            "events.buffer_size",
+            "must be greater than zero",
+        );
+        reader.ensure(
+            keep_alive_secs > 0,
+            "events.keep_alive_secs",
+            "must be greater than zero",
+        );
+
+        Self {
+            buffer_size,
+            keep_alive: Duration::from_secs(keep_alive_secs),
+        }
+    }
+}
+
+impl RateLimit {
+    fn read(reader: &mut Reader, prefix: &str, per_minute: u32, burst: u32) -> Self {
+        let per_minute_key = format!("{prefix}.per_minute");
+        let burst_key = format!("{prefix}.burst");
+        let per_minute: u32 = reader.or(&per_minute_key, per_minute);
+        let burst: u32 = reader.or(&burst_key, burst);
+
+        reader.ensure(per_minute > 0, &per_minute_key, "must be greater than zero");
+        reader.ensure(burst > 0, &burst_key, "must be greater than zero");
+
+        Self { per_minute, burst }
+    }
+}
+
+impl SignerConfig {
+    fn read(reader: &mut Reader) -> Self {
+        let kind: String = reader.or("signer.kind", "private_key".to_string());
+        let secret = |reader: &mut Reader, key: &str| {
+            SecretString::new(reader.required(key).unwrap_or_default())
+        };
+        // Settings of the other kinds may stay around, e.g. while switching between them
+        for key in SIGNER_KEYS {
+            reader.ignore(key);
+        }
+
+        match kind.as_str() {
+            "keystore" => {
+                let path: PathBuf = reader.required("signer.keystore_path").unwrap_or_default();
+                let password = match reader.optional("signer.keystore_password_file") {
+                    // The file wins when both are set
+                    Some(file) => KeystorePassword::File(file),
+                    None => KeystorePassword::Value(secret(reader, "signer.keystore_password")),
+                };
+                SignerConfig::Keystore { path, password }
+            }
+            "mnemonic" => SignerConfig::Mnemonic {
+                phrase: secret(reader, "signer.mnemonic"),
+                derivation_path: reader.or(
+                    "signer.derivation_path",
+                    DEFAULT_DERIVATION_PATH.to_string(),
+                ),
+            },
+            other => {
+                if other != "private_key" {
+                    reader.invalid(
+                        "signer.kind",
+                        "must be one of private_key, keystore or mnemonic",
+                    );
+                }
+                SignerConfig::PrivateKey(secret(reader, "signer.private_key"))
+            }
+        }
+    }
+}
+
+impl SecretString {
+    pub fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString(***)")
+    }
+}
+
 impl Environment {
     pub fn environment_as_string(&self) -> &'static str {
         match self {
@@ -66,8 +950,338 @@ impl TryFrom<String> for Environment {
 }
 
 pub type AppConfig = Arc<Configuration>;
-pub fn load_env_var(name: &str) -> String {
-    std::env::var(name)
-        .map_err(|e| format!("{name}: {e}"))
-        .expect("Missing environment variable")
+
+fn is_hex_address(value: &str) -> bool {
+    value
+        .strip_prefix("0x")
+        .is_some_and(|hex| hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn is_url(value: &str, schemes: &[&str]) -> bool {
+    value
+        .split_once("://")
+        .is_some_and(|(scheme, rest)| schemes.contains(&scheme) && !rest.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::Origin;
+    use std::path::Path;
+
+    const PRIVATE_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn minimal_env() -> Vec<(String, String)> {
+        env(&[
+            ("APP_DATABASE_URL", "postgres://postgres@localhost/relayer"),
+            ("APP_CHAIN_ID", "11155111"),
+            ("APP_RPC__URLS", "http://localhost:8545"),
+            (
+                "APP_FORWARDER_ADDRESS",
+                "0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f",
+            ),
+            ("APP_SIGNER__PRIVATE_KEY", PRIVATE_KEY),
+        ])
+    }
+
+    fn load(
+        file: Option<(&str, &str)>,
+        vars: Vec<(String, String)>,
+    ) -> Result<Configuration, ConfigError> {
+        let mut source = ConfigSource::default();
+        if let Some((path, contents)) = file {
+            source.merge_file_contents(Path::new(path), contents)?;
+        }
+        source.merge_env(vars);
+        Configuration::from_source(&source)
+    }
+
+    #[test]
+    fn fills_in_defaults() {
+        let config = load(None, minimal_env()).unwrap();
+        assert_eq!(config.app_port, 8080);
+        assert_eq!(config.queue.concurrency, 4);
+        assert_eq!(config.chains[0].rpc.urls, vec!["http://localhost:8545"]);
+        assert_eq!(
+            config.chains[0].gas_oracle.percentiles,
+            DEFAULT_FEE_PERCENTILES
+        );
+        assert!(matches!(
+            config.chains[0].signer,
+            SignerConfig::PrivateKey(_)
+        ));
+        assert!(config.auth.enabled && config.auth.admin_key.is_none());
+    }
+
+    #[test]
+    fn env_overrides_the_file() {
+        let file = r#"
+            port = 9000
+            [queue]
+            concurrency = 2
+            batch_size = 50
+            [gas_oracle]
+            percentiles = [5, 50, 95]
+        "#;
+        let mut vars = minimal_env();
+        vars.extend(env(&[("APP_QUEUE__CONCURRENCY", "3"), ("UNRELATED", "1")]));
+
+        let config = load(Some(("relayer.toml", file)), vars).unwrap();
+        assert_eq!(config.app_port, 9000);
+        assert_eq!(config.queue.concurrency, 3);
+        assert_eq!(config.queue.batch_size, 50);
+        assert_eq!(config.chains[0].gas_oracle.percentiles, [5.0, 50.0, 95.0]);
+    }
+
+    #[test]
+    fn reads_yaml_files() {
+        let file = "rpc:\n  urls:\n    - http://a:8545\n    - http://b:8545\ntracker:\n  confirmations: 6\n";
+        let mut vars = minimal_env();
+        vars.retain(|(name, _)| name != "APP_RPC__URLS");
+
+        let config = load(Some(("relayer.yaml", file)), vars).unwrap();
+        assert_eq!(
+            config.chains[0].rpc.urls,
+            vec!["http://a:8545", "http://b:8545"]
+        );
+        assert_eq!(config.chains[0].tracker.confirmations, 6);
+    }
+
+    #[test]
+    fn reports_every_invalid_field() {
+        let vars = env(&[
+            ("APP_PORT", "eighty"),
+            ("APP_QUEUE__CONCURRENCY", "0"),
+            ("APP_SIGNER__KIND", "ledger"),
+            ("APP_QUEUE__CONCURENCY", "2"),
+        ]);
+
+        let error = load(None, vars).unwrap_err();
+        let fields = error.fields();
+        for field in [
+            "port",
+            "database_url",
+            "chain_id",
+            "forwarder_address",
+            "rpc.urls",
+            "signer.kind",
+            "signer.private_key",
+            "queue.concurrency",
+            "queue.concurency",
+        ] {
+            assert!(fields.contains(&field), "{field} missing from {fields:?}");
+        }
+
+        let port = &error.errors[0];
+        assert_eq!(port.origin, Some(Origin::Env("APP_PORT".to_string())));
+        assert_eq!(
+            port.to_string(),
+            "port: expected u16, got \"eighty\" (from APP_PORT)"
+        );
+        assert!(error.to_string().starts_with(&format!(
+            "invalid configuration ({} problems):",
+            error.errors.len()
+        )));
+    }
+
+    #[test]
+    fn validates_cross_field_constraints() {
+        let mut vars = minimal_env();
+        vars.extend(env(&[
+            ("APP_MAX_DB_CONNECTIONS", "4"),
+            ("APP_TRACKER__BUMP_AFTER_SECS", "2"),
+        ]));
+
+        let error = load(None, vars).unwrap_err();
+        assert_eq!(
+            error.fields(),
+            vec!["tracker.bump_after_secs", "max_db_connections"]
+        );
+    }
+
+    #[test]
+    fn reads_the_selected_signer_kind() {
+        let mut vars = minimal_env();
+        vars.push(("APP_SIGNER__KIND".to_string(), "keystore".to_string()));
+        let error = load(None, vars.clone()).unwrap_err();
+        assert_eq!(
+            error.fields(),
+            vec!["signer.keystore_path", "signer.keystore_password"]
+        );
+
+        vars.extend(env(&[
+            ("APP_SIGNER__KEYSTORE_PATH", "/run/secrets/relayer.json"),
+            (
+                "APP_SIGNER__KEYSTORE_PASSWORD_FILE",
+                "/run/secrets/password",
+            ),
+        ]));
+        let config = load(None, vars).unwrap();
+        assert!(matches!(
+            config.chains[0].signer,
+            SignerConfig::Keystore {
+                password: KeystorePassword::File(_),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn reads_chains_with_top_level_defaults() {
+        let file = r#"
+            forwarder_address = "0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f"
+            [tracker]
+            confirmations = 3
+            [chains.sepolia]
+            chain_id = 11155111
+            rpc.urls = ["https://sepolia.example"]
+            [chains.op_sepolia]
+            chain_id = 11155420
+            rpc.urls = ["https://op-sepolia.example"]
+            tracker.confirmations = 1
+        "#;
+        let vars = env(&[
+            ("APP_DATABASE_URL", "postgres://postgres@localhost/relayer"),
+            ("APP_SIGNER__PRIVATE_KEY", PRIVATE_KEY),
+            ("APP_CHAINS__OP_SEPOLIA__GAS_ORACLE__BLOCKS", "5"),
+        ]);
+
+        let config = load(Some(("relayer.toml", file)), vars).unwrap();
+        let [op, sepolia] = &config.chains[..] else {
+            panic!("expected two chains, got {:?}", config.chains);
+        };
+        assert_eq!((op.name.as_str(), op.chain_id), ("op_sepolia", 11155420));
+        assert_eq!(
+            (sepolia.name.as_str(), sepolia.chain_id),
+            ("sepolia", 11155111)
+        );
+        assert_eq!(op.rpc.urls, vec!["https://op-sepolia.example"]);
+        assert_eq!(
+            (op.tracker.confirmations, sepolia.tracker.confirmations),
+            (1, 3)
+        );
+        assert_eq!(
+            (op.gas_oracle.block_count, sepolia.gas_oracle.block_count),
+            (5, 20)
+        );
+        assert_eq!(op.forwarder_address, sepolia.forwarder_address);
+        assert!(matches!(sepolia.signer, SignerConfig::PrivateKey(_)));
+        assert_eq!(config.max_db_connection, 9);
+    }
+
+    #[test]
+    fn reports_errors_per_chain() {
+        let file = r#"
+            [chains.sepolia]
+            chain_id = 11155111
+            rpc.urls = ["https://sepolia.example"]
+            [chains.mainnet]
+            chain_id = 11155111
+            rpc.timeout_ms = "slow"
+        "#;
+        let vars = env(&[
+            ("APP_DATABASE_URL", "postgres://postgres@localhost/relayer"),
+            (
+                "APP_FORWARDER_ADDRESS",
+                "0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f",
+            ),
+            ("APP_SIGNER__PRIVATE_KEY", PRIVATE_KEY),
+        ]);
+
+        let error = load(Some(("relayer.toml", file)), vars).unwrap_err();
+        assert_eq!(
+            error.fields(),
+            vec![
+                "chains.mainnet.rpc.urls",
+                "chains.mainnet.rpc.timeout_ms",
+                "chains.sepolia.chain_id"
+            ]
+        );
+        assert!(
+            error.errors[0]
+                .message
+                .contains("APP_CHAINS__MAINNET__RPC__URLS")
+        );
+        assert_eq!(
+            error.errors[2].message,
+            "duplicates the chain id of chains.mainnet"
+        );
+    }
+
+    #[test]
+    fn reads_rate_limit_tiers() {
+        let file = r#"
+            [rate_limit]
+            backend = "postgres"
+            tiers.partner = { per_minute = 600, burst = 100 }
+            tiers.default = { per_minute = 10 }
+        "#;
+        let config = load(Some(("relayer.toml", file)), minimal_env()).unwrap();
+        let rate_limit = &config.rate_limit;
+        assert_eq!(rate_limit.backend, RateLimitBackend::Postgres);
+        assert_eq!(
+            rate_limit.ip,
+            RateLimit {
+                per_minute: 120,
+                burst: 30
+            }
+        );
+
+        let tiers: Vec<_> = rate_limit
+            .tiers
+            .iter()
+            .map(|tier| (tier.name.as_str(), tier.limit))
+            .collect();
+        assert_eq!(
+            tiers,
+            vec![
+                (
+                    "default",
+                    RateLimit {
+                        per_minute: 10,
+                        burst: 20
+                    }
+                ),
+                (
+                    "partner",
+                    RateLimit {
+                        per_minute: 600,
+                        burst: 100
+                    }
+                ),
+            ]
+        );
+
+        let file = r#"
+            rate_limit.tiers.partner.per_minute = 0
+            rate_limit.sender.burst = 0
+        "#;
+        let error = load(Some(("relayer.toml", file)), minimal_env()).unwrap_err();
+        assert_eq!(
+            error.fields(),
+            vec![
+                "rate_limit.sender.burst",
+                "rate_limit.tiers.partner.per_minute"
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_the_example_file() {
+        let config = load(
+            Some((
+                "relayer.example.toml",
+                include_str!("../../../relayer.example.toml"),
+            )),
+            env(&[("APP_SIGNER__PRIVATE_KEY", PRIVATE_KEY)]),
+        )
+        .unwrap();
+        assert_eq!(config.chains[0].chain_id, 11155111);
+    }
 }
@@ -0,0 +1,362 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Prefix of the environment variables that override configuration keys.
+pub const ENV_PREFIX: &str = "APP_";
+
+/// Environment variable naming an optional TOML or YAML configuration file.
+pub const CONFIG_FILE_VAR: &str = "APP_CONFIG_FILE";
+
+/// Where a configuration value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    File(PathBuf),
+    Env(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    /// Dotted key, e.g. `queue.concurrency`.
+    pub field: String,
+    /// `None` when the value is missing or was a default.
+    pub origin: Option<Origin>,
+    pub message: String,
+}
+
+/// Every problem found while loading the configuration, not just the first one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    pub errors: Vec<FieldError>,
+}
+
+/// Raw configuration values keyed like `queue.concurrency`. Later layers override earlier
+/// ones: the file first, then `APP_` environment variables. Keys found in neither fall back
+/// to the defaults in [`crate::config::Configuration::from_source`].
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSource {
+    values: BTreeMap<String, (String, Origin)>,
+}
+
+/// Reads typed values out of a [`ConfigSource`], collecting errors instead of stopping at the first.
+pub struct Reader<'a> {
+    source: &'a ConfigSource,
+    used: BTreeSet<String>,
+    errors: Vec<FieldError>,
+    /// Set while reading e.g. `chains.sepolia`, whose keys fall back to the top-level ones.
+    scope: Option<String>,
+}
+
+/// Environment variable overriding `key`: `queue.max_retries` is `APP_QUEUE__MAX_RETRIES`.
+pub fn env_var_name(key: &str) -> String {
+    format!("{ENV_PREFIX}{}", key.to_uppercase().replace('.', "__"))
+}
+
+impl ConfigSource {
+    /// The file named by `APP_CONFIG_FILE`, if any, overridden by the process environment.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let vars: Vec<(String, String)> = std::env::vars().collect();
+        let mut source = Self::default();
+        if let Some((_, path)) = vars.iter().find(|(name, _)| name == CONFIG_FILE_VAR) {
+            source.merge_file(Path::new(path))?;
+        }
+        source.merge_env(vars);
+        Ok(source)
+    }
+
+    pub fn merge_file(&mut self, path: &Path) -> Result<(), ConfigError> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            ConfigError::single(
+                CONFIG_FILE_VAR,
+                None,
+                format!("cannot read {}: {e}", path.display()),
+            )
+        })?;
+        self.merge_file_contents(path, &contents)
+    }
+
+    /// Parses `contents` as YAML for `.yaml`/`.yml` paths and as TOML otherwise.
+    pub fn merge_file_contents(&mut self, path: &Path, contents: &str) -> Result<(), ConfigError> {
+        let is_yaml = matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("yaml" | "yml")
+        );
+        let parsed: Result<serde_json::Value, String> = if is_yaml {
+            serde_yaml::from_str(contents).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(contents).map_err(|e| e.to_string())
+        };
+        let value = parsed.map_err(|e| {
+            ConfigError::single(
+                CONFIG_FILE_VAR,
+                None,
+                format!("cannot parse {}: {e}", path.display()),
+            )
+        })?;
+
+        let mut flat = BTreeMap::new();
+        flatten(&value, String::new(), &mut flat);
+        for (key, value) in flat {
+            self.values
+                .insert(key, (value, Origin::File(path.to_path_buf())));
+        }
+        Ok(())
+    }
+
+    /// Applies every non-empty `APP_*` variable except `APP_CONFIG_FILE`.
+    pub fn merge_env(&mut self, vars: impl IntoIterator<Item = (String, String)>) {
+        for (name, value) in vars {
+            if name == CONFIG_FILE_VAR || value.is_empty() {
+                continue;
+            }
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let key = key.to_lowercase().replace("__", ".");
+            self.values.insert(key, (value, Origin::Env(name)));
+        }
+    }
+
+    pub fn reader(&self) -> Reader<'_> {
+        Reader {
+            source: self,
+            used: BTreeSet::new(),
+            errors: Vec::new(),
+            scope: None,
+        }
+    }
+}
+
+impl<'a> Reader<'a> {
+    /// Reads the keys of `read` under `scope`: `scope.key` if set, else the top-level `key`.
+    pub fn scoped<T>(&mut self, scope: &str, read: impl FnOnce(&mut Self) -> T) -> T {
+        let outer = self.scope.replace(scope.to_string());
+        let value = read(self);
+        self.scope = outer;
+        value
+    }
+
+    /// Names of the tables under `prefix`, e.g. `sepolia` for `chains.sepolia.chain_id`.
+    pub fn children(&mut self, prefix: &str) -> BTreeSet<String> {
+        let prefix = format!("{prefix}.");
+        self.source
+            .values
+            .keys()
+            .filter_map(|key| key.strip_prefix(&prefix)?.split_once('.'))
+            .map(|(child, _)| child.to_string())
+            .collect()
+    }
+
+    /// The key errors are reported under: the inherited top-level key if that is what
+    /// supplied the value, else the key in the current scope.
+    fn field(&self, key: &str) -> String {
+        match &self.scope {
+            Some(scope) => {
+                let scoped = format!("{scope}.{key}");
+                if !self.source.values.contains_key(&scoped) && self.source.values.contains_key(key)
+                {
+                    key.to_string()
+                } else {
+                    scoped
+                }
+            }
+            None => key.to_string(),
+        }
+    }
+
+    fn raw(&mut self, key: &str) -> Option<(String, &'a (String, Origin))> {
+        let field = self.field(key);
+        self.used.insert(key.to_string());
+        self.used.insert(field.clone());
+        let value = self.source.values.get(&field)?;
+        Some((field, value))
+    }
+
+    fn parse<T: FromStr>(&mut self, field: &str, value: &str, origin: &Origin) -> Option<T> {
+        match value.trim().parse::<T>() {
+            Ok(parsed) => Some(parsed),
+            Err(_) => {
+                // Secrets are plain strings and always parse, so echoing the value is safe
+                let expected = std::any::type_name::<T>()
+                    .rsplit("::")
+                    .next()
+                    .unwrap_or("value");
+                self.errors.push(FieldError {
+                    field: field.to_string(),
+                    origin: Some(origin.clone()),
+                    message: format!("expected {expected}, got {value:?}"),
+                });
+                None
+            }
+        }
+    }
+
+    pub fn optional<T: FromStr>(&mut self, key: &str) -> Option<T> {
+        let (field, (value, origin)) = self.raw(key)?;
+        self.parse(&field, value, origin)
+    }
+
+    /// `default` when the key is unset. An unparsable value is reported and replaced by `default`.
+    pub fn or<T: FromStr>(&mut self, key: &str, default: T) -> T {
+        self.optional(key).unwrap_or(default)
+    }
+
+    pub fn required<T: FromStr>(&mut self, key: &str) -> Option<T> {
+        if let Some((field, (value, origin))) = self.raw(key) {
+            return self.parse(&field, value, origin);
+        }
+        let field = self.field(key);
+        self.errors.push(FieldError {
+            message: format!(
+                "is required (set {} or {field} in the config file)",
+                env_var_name(&field)
+            ),
+            field,
+            origin: None,
+        });
+        None
+    }
+
+    /// Comma-separated in the environment, an array in the config file.
+    pub fn list<T: FromStr>(&mut self, key: &str) -> Option<Vec<T>> {
+        let (field, (value, origin)) = self.raw(key)?;
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| self.parse(&field, item, origin))
+            .collect()
+    }
+
+    /// Marks `key` as known without reading it, e.g. settings of another signer kind.
+    pub fn ignore(&mut self, key: &str) {
+        self.raw(key);
+    }
+
+    /// Reports `message` for `key` unless `ok` holds.
+    pub fn ensure(&mut self, ok: bool, key: &str, message: &str) {
+        if !ok {
+            self.invalid(key, message);
+        }
+    }
+
+    pub fn invalid(&mut self, key: &str, message: &str) {
+        let field = self.field(key);
+        self.errors.push(FieldError {
+            origin: self
+                .source
+                .values
+                .get(&field)
+                .map(|(_, origin)| origin.clone()),
+            field,
+            message: message.to_string(),
+        });
+    }
+
+    /// Fails with every error collected so far, plus keys that no section reads.
+    pub fn finish(mut self) -> Result<(), ConfigError> {
+        for (key, (_, origin)) in &self.source.values {
+            if !self.used.contains(key) {
+                self.errors.push(FieldError {
+                    field: key.clone(),
+                    origin: Some(origin.clone()),
+                    message: "is not a known configuration key".to_string(),
+                });
+            }
+        }
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError {
+                errors: self.errors,
+            })
+        }
+    }
+}
+
+impl ConfigError {
+    fn single(field: &str, origin: Option<Origin>, message: String) -> Self {
+        Self {
+            errors: vec![FieldError {
+                field: field.to_string(),
+                origin,
+                message,
+            }],
+        }
+    }
+
+    pub fn fields(&self) -> Vec<&str> {
+        self.errors.iter().map(|e| e.field.as_str()).collect()
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::File(path) => write!(f, "{}", path.display()),
+            Origin::Env(name) => f.write_str(name),
+        }
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)?;
+        if let Some(origin) = &self.origin {
+            write!(f, " (from {origin})")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid configuration ({} problem", self.errors.len())?;
+        if self.errors.len() != 1 {
+            f.write_str("s")?;
+        }
+        f.write_str("):")?;
+        for error in &self.errors {
+            write!(f, "\n  - {error}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// `{"queue": {"concurrency": 4}}` becomes `queue.concurrency = "4"`; arrays are comma-joined.
+fn flatten(value: &serde_json::Value, key: String, out: &mut BTreeMap<String, String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (name, child) in map {
+                let child_key = if key.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{key}.{name}")
+                };
+                flatten(child, child_key, out);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            let joined = items
+                .iter()
+                .map(|item| match item {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            out.insert(key, joined);
+        }
+        serde_json::Value::String(s) => {
+            out.insert(key, s.clone());
+        }
+        serde_json::Value::Null => {}
+        other => {
+            out.insert(key, other.to_string());
+        }
+    }
+}
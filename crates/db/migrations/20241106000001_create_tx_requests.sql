@@ -0,0 +1,28 @@
+-- Meta-transaction requests submitted to the relayer
+-- Each row is an ERC-2771 ForwardRequest relayed through the TrustedForwarder
+
+CREATE TABLE IF NOT EXISTS tx_requests (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    chain_id BIGINT NOT NULL,
+    from_address VARCHAR(42) NOT NULL,  -- signer of the forward request
+    to_address VARCHAR(42) NOT NULL,    -- target contract called by the forwarder
+    value NUMERIC(78, 0) NOT NULL DEFAULT 0,
+    gas BIGINT NOT NULL,
+    deadline BIGINT NOT NULL,           -- uint48 unix timestamp after which the request expires
+    data BYTEA NOT NULL,
+    signature BYTEA NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    tx_hash VARCHAR(66),                -- hash of the relayer transaction carrying the request
+    nonce BIGINT,                       -- relayer account nonce used for the transaction
+    gas_used BIGINT,                    -- filled in once the relayed transaction is mined
+    error TEXT,                         -- failure reason once the request is marked failed
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Indexes for request lookups
+CREATE INDEX IF NOT EXISTS idx_tx_requests_status ON tx_requests(status);
+CREATE INDEX IF NOT EXISTS idx_tx_requests_from_address ON tx_requests(from_address);
+CREATE INDEX IF NOT EXISTS idx_tx_requests_created_at ON tx_requests(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_tx_requests_tx_hash ON tx_requests(tx_hash);
+CREATE INDEX IF NOT EXISTS idx_tx_requests_chain_status ON tx_requests(chain_id, status);
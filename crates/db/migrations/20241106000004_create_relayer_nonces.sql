@@ -0,0 +1,26 @@
+-- Nonce bookkeeping for relayer accounts
+-- Nonces are handed out from Postgres so that concurrent workers and replicas never collide
+
+-- Next nonce to hand out per relayer account
+CREATE TABLE IF NOT EXISTS relayer_nonces (
+    chain_id BIGINT NOT NULL,
+    address VARCHAR(42) NOT NULL,
+    next_nonce BIGINT NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    PRIMARY KEY (chain_id, address)
+);
+
+-- Every nonce handed out and what happened to it
+CREATE TABLE IF NOT EXISTS nonce_reservations (
+    chain_id BIGINT NOT NULL,
+    address VARCHAR(42) NOT NULL,
+    nonce BIGINT NOT NULL,
+    state VARCHAR(20) NOT NULL,         -- reserved, broadcast, released
+    tx_request_id UUID REFERENCES tx_requests(id) ON DELETE SET NULL,
+    reserved_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    PRIMARY KEY (chain_id, address, nonce)
+);
+
+-- Index for reusing released nonces
+CREATE INDEX IF NOT EXISTS idx_nonce_reservations_state ON nonce_reservations(chain_id, address, state);
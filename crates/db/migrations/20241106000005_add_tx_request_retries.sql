@@ -0,0 +1,7 @@
+-- Bookkeeping for the background queue worker
+
+ALTER TABLE tx_requests
+    ADD COLUMN IF NOT EXISTS retry_count INTEGER NOT NULL DEFAULT 0;  -- failed broadcast attempts so far
+
+-- Index for claiming the oldest pending requests of a chain
+CREATE INDEX IF NOT EXISTS idx_tx_requests_pending ON tx_requests(chain_id, created_at) WHERE status = 'pending';
@@ -0,0 +1,8 @@
+-- Receipt data the tracker records once a submitted transaction is mined
+
+ALTER TABLE tx_requests
+    ADD COLUMN IF NOT EXISTS effective_gas_price NUMERIC(78, 0),  -- wei per gas actually paid
+    ADD COLUMN IF NOT EXISTS block_number BIGINT;                 -- block the transaction was included in
+
+-- Index for polling receipts of submitted requests
+CREATE INDEX IF NOT EXISTS idx_tx_requests_submitted ON tx_requests(chain_id, updated_at) WHERE status = 'submitted';
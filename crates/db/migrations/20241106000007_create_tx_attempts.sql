@@ -0,0 +1,19 @@
+-- Every broadcast of a relay request, including fee-bumped replacements sharing its nonce
+
+CREATE TABLE IF NOT EXISTS tx_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    tx_request_id UUID NOT NULL REFERENCES tx_requests(id) ON DELETE CASCADE,
+    tx_hash VARCHAR(66) NOT NULL,
+    nonce BIGINT NOT NULL,
+    to_address VARCHAR(42) NOT NULL,
+    value NUMERIC(78, 0) NOT NULL,
+    input BYTEA NOT NULL,                               -- calldata, kept so the transaction can be re-signed
+    gas_limit BIGINT NOT NULL,
+    max_fee_per_gas NUMERIC(78, 0) NOT NULL,
+    max_priority_fee_per_gas NUMERIC(78, 0) NOT NULL,
+    mined BOOLEAN NOT NULL DEFAULT FALSE,               -- the attempt that made it on chain
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_tx_attempts_tx_request_id ON tx_attempts(tx_request_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_tx_attempts_tx_hash ON tx_attempts(tx_hash);
@@ -0,0 +1,19 @@
+-- The alerting engine fires at most one open alert per rule
+
+UPDATE alert_history SET is_resolved = FALSE WHERE is_resolved IS NULL;
+UPDATE alert_history SET fired_at = NOW() WHERE fired_at IS NULL;
+
+ALTER TABLE alert_history
+    ALTER COLUMN is_resolved SET NOT NULL,
+    ALTER COLUMN fired_at SET NOT NULL;
+
+-- Close all but the newest duplicate before enforcing uniqueness
+UPDATE alert_history a
+SET is_resolved = TRUE, resolved_at = NOW()
+WHERE NOT a.is_resolved
+  AND EXISTS (
+      SELECT 1 FROM alert_history b
+      WHERE b.alert_name = a.alert_name AND NOT b.is_resolved AND b.fired_at > a.fired_at
+  );
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_alert_history_open ON alert_history(alert_name) WHERE NOT is_resolved;
@@ -0,0 +1,9 @@
+-- Versions of the relay policy document (allowed targets, selectors, limits, senders).
+-- The newest row is the active policy; older rows are kept as history.
+
+CREATE TABLE IF NOT EXISTS relay_policies (
+    id BIGSERIAL PRIMARY KEY,
+    document JSONB NOT NULL,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
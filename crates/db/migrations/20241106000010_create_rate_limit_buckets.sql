@@ -0,0 +1,12 @@
+-- Token buckets shared by every relayer replica when rate_limit.backend = "postgres".
+-- `allowed` records whether the last request was let through, so one statement can
+-- refill, take a token and report the outcome.
+
+CREATE TABLE IF NOT EXISTS rate_limit_buckets (
+    key TEXT PRIMARY KEY,
+    tokens DOUBLE PRECISION NOT NULL,
+    allowed BOOLEAN NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_rate_limit_buckets_updated_at ON rate_limit_buckets(updated_at);
@@ -0,0 +1,30 @@
+-- Gas budgets per tenant and the ledger of what each tenant's relayed transactions cost.
+-- Budgets are wei per UTC day and calendar month; NULL means unlimited.
+
+ALTER TABLE tenants
+    ADD COLUMN IF NOT EXISTS daily_budget_wei NUMERIC(78, 0),
+    ADD COLUMN IF NOT EXISTS monthly_budget_wei NUMERIC(78, 0);
+
+-- Gas limit times the max fee when the request was accepted; held against the budget until it settles
+ALTER TABLE tx_requests
+    ADD COLUMN IF NOT EXISTS estimated_cost_wei NUMERIC(78, 0);
+
+CREATE TABLE IF NOT EXISTS tenant_spend (
+    id BIGSERIAL PRIMARY KEY,
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    tx_request_id UUID NOT NULL UNIQUE REFERENCES tx_requests(id),
+    chain_id BIGINT NOT NULL,
+    gas_used BIGINT NOT NULL,
+    effective_gas_price NUMERIC(78, 0) NOT NULL,
+    cost_wei NUMERIC(78, 0) NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_tenant_spend_tenant_recorded_at ON tenant_spend(tenant_id, recorded_at);
+
+-- Requests settled before the ledger existed
+INSERT INTO tenant_spend (tenant_id, tx_request_id, chain_id, gas_used, effective_gas_price, cost_wei, recorded_at)
+SELECT tenant_id, id, chain_id, gas_used, effective_gas_price, gas_used * effective_gas_price, COALESCE(updated_at, NOW())
+FROM tx_requests
+WHERE status IN ('confirmed', 'failed') AND gas_used IS NOT NULL AND effective_gas_price IS NOT NULL
+ON CONFLICT (tx_request_id) DO NOTHING;
@@ -0,0 +1,6 @@
+-- Keyset pagination of a tenant's requests, newest first, optionally by sender or target
+
+DROP INDEX IF EXISTS idx_tx_requests_tenant_created_at;
+CREATE INDEX IF NOT EXISTS idx_tx_requests_tenant_page ON tx_requests(tenant_id, created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS idx_tx_requests_tenant_from_page ON tx_requests(tenant_id, from_address, created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS idx_tx_requests_tenant_to_page ON tx_requests(tenant_id, to_address, created_at DESC, id DESC);
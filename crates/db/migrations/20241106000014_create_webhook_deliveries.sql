@@ -0,0 +1,31 @@
+-- Callbacks on relay status transitions. Events are written to the outbox in the same
+-- statement as the transition and POSTed by the webhook dispatcher.
+
+ALTER TABLE tx_requests
+    ADD COLUMN IF NOT EXISTS callback_url TEXT;
+
+-- Used when a request brings no callback URL; the secret signs the tenant's callbacks
+ALTER TABLE tenants
+    ADD COLUMN IF NOT EXISTS webhook_url TEXT,
+    ADD COLUMN IF NOT EXISTS webhook_secret TEXT;
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),          -- also the event id sent to the receiver
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    tx_request_id UUID NOT NULL REFERENCES tx_requests(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    event_type VARCHAR(32) NOT NULL,
+    payload JSONB NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',          -- pending, delivered or dead
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_status_code INTEGER,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(next_attempt_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_tenant ON webhook_deliveries(tenant_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_tx_request ON webhook_deliveries(tx_request_id);
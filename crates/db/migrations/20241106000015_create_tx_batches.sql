@@ -0,0 +1,18 @@
+-- Requests relayed together in one TrustedForwarder.executeBatch transaction. Batches sent
+-- through POST /relay/batch exist from the start and are atomic; the queue worker creates
+-- the ones it forms on its own once they are broadcast.
+
+CREATE TABLE IF NOT EXISTS tx_batches (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    chain_id BIGINT NOT NULL,
+    tenant_id UUID REFERENCES tenants(id),              -- only set for batches a tenant sent itself
+    atomic BOOLEAN NOT NULL,                            -- one invalid request reverts the whole batch
+    refund_receiver VARCHAR(42),                        -- gets the value of skipped requests; NULL when atomic
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL
+);
+
+ALTER TABLE tx_requests
+    ADD COLUMN IF NOT EXISTS batch_id UUID REFERENCES tx_batches(id),
+    ADD COLUMN IF NOT EXISTS batch_index INTEGER;       -- position in the executeBatch call
+
+CREATE INDEX IF NOT EXISTS idx_tx_requests_batch ON tx_requests(batch_id, batch_index) WHERE batch_id IS NOT NULL;
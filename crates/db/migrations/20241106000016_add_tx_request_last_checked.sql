@@ -0,0 +1,8 @@
+-- When the receipt tracker last looked at a submitted request. Polling the least recently
+-- checked ones first keeps transactions that never mine from starving the rest.
+
+ALTER TABLE tx_requests ADD COLUMN IF NOT EXISTS last_checked_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX IF NOT EXISTS idx_tx_requests_submitted_checks
+    ON tx_requests(chain_id, last_checked_at NULLS FIRST, updated_at)
+    WHERE status = 'submitted';
@@ -0,0 +1,136 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, Postgres};
+use uuid::Uuid;
+
+const ALERT_COLUMNS: &str =
+    "id, alert_name, severity, message, details, fired_at, resolved_at, is_resolved";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertSeverity {
+    Critical,
+    Warning,
+    Info,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewAlert {
+    pub alert_name: String,
+    pub severity: AlertSeverity,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct Alert {
+    pub id: Uuid,
+    pub alert_name: String,
+    #[sqlx(try_from = "String")]
+    pub severity: AlertSeverity,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+    pub fired_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub is_resolved: bool,
+}
+
+/// Fired and resolved alerts in `alert_history`. At most one alert per name is open at a time.
+#[derive(Clone, Debug)]
+pub struct AlertRepository {
+    pool: Pool<Postgres>,
+}
+
+impl AlertSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertSeverity::Critical => "critical",
+            AlertSeverity::Warning => "warning",
+            AlertSeverity::Info => "info",
+        }
+    }
+}
+
+impl TryFrom<String> for AlertSeverity {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "critical" => Ok(AlertSeverity::Critical),
+            "warning" => Ok(AlertSeverity::Warning),
+            "info" => Ok(AlertSeverity::Info),
+            _ => Err(format!("Invalid alert severity: {value}")),
+        }
+    }
+}
+
+impl AlertRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Opens an alert. Returns `None` if one with the same name is already open,
+    /// which is how repeats (and other replicas firing the same rule) are deduplicated.
+    pub async fn fire(&self, alert: &NewAlert) -> anyhow::Result<Option<Alert>> {
+        let fired = sqlx::query_as::<_, Alert>(&format!(
+            "INSERT INTO alert_history (alert_name, severity, message, details)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (alert_name) WHERE NOT is_resolved DO NOTHING
+             RETURNING {ALERT_COLUMNS}"
+        ))
+        .bind(&alert.alert_name)
+        .bind(alert.severity.as_str())
+        .bind(&alert.message)
+        .bind(&alert.details)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(fired)
+    }
+
+    /// Closes an open alert. Returns `None` if it does not exist or was already resolved.
+    pub async fn resolve(&self, id: Uuid) -> anyhow::Result<Option<Alert>> {
+        let resolved = sqlx::query_as::<_, Alert>(&format!(
+            "UPDATE alert_history
+             SET is_resolved = TRUE, resolved_at = NOW()
+             WHERE id = $1 AND NOT is_resolved
+             RETURNING {ALERT_COLUMNS}"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(resolved)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid) -> anyhow::Result<Option<Alert>> {
+        let alert = sqlx::query_as::<_, Alert>(&format!(
+            "SELECT {ALERT_COLUMNS} FROM alert_history WHERE id = $1"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(alert)
+    }
+
+    /// Newest first, optionally only open (`false`) or resolved (`true`) alerts.
+    pub async fn list(&self, resolved: Option<bool>, limit: i64) -> anyhow::Result<Vec<Alert>> {
+        let alerts = sqlx::query_as::<_, Alert>(&format!(
+            "SELECT {ALERT_COLUMNS} FROM alert_history
+             WHERE ($1::boolean IS NULL OR is_resolved = $1)
+             ORDER BY fired_at DESC
+             LIMIT $2"
+        ))
+        .bind(resolved)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(alerts)
+    }
+
+    pub async fn list_open(&self) -> anyhow::Result<Vec<Alert>> {
+        self.list(Some(false), i64::MAX).await
+    }
+}
@@ -1,16 +1,168 @@
+use crate::alerts::AlertRepository;
+use crate::monitoring::MonitoringRepository;
+use crate::nonces::NonceRepository;
+use crate::policies::PolicyRepository;
+use crate::rate_limits::RateLimitRepository;
+use crate::spend::{BudgetExceeded, SpendRepository, period_usage};
+use crate::tenants::TenantRepository;
+use crate::webhooks::WebhookRepository;
+use chrono::{DateTime, Utc};
 use sqlx::migrate::Migrator;
 use sqlx::postgres::PgPoolOptions;
-use sqlx::{Pool, Postgres};
+use sqlx::{FromRow, PgConnection, Pool, Postgres};
+use uuid::Uuid;
 
 // use config;
 
 static MIGRANT: Migrator = sqlx::migrate!("./migrations");
 
+/// Columns selected whenever a full `TxRequest` row is read back.
+const TX_REQUEST_COLUMNS: &str = "id, chain_id, tenant_id, from_address, to_address, value::text AS value, gas, deadline, \
+     data, signature, status, tx_hash, gas_used, effective_gas_price::text AS effective_gas_price, block_number, \
+     nonce, error, retry_count, estimated_cost_wei::text AS estimated_cost_wei, callback_url, batch_id, batch_index, \
+     created_at, updated_at";
+
+/// Columns selected whenever a full `TxAttempt` row is read back.
+const TX_ATTEMPT_COLUMNS: &str = "id, tx_request_id, tx_hash, nonce, to_address, value::text AS value, input, gas_limit, \
+     max_fee_per_gas::text AS max_fee_per_gas, max_priority_fee_per_gas::text AS max_priority_fee_per_gas, \
+     mined, created_at";
+
 #[derive(Clone, Debug)]
 pub struct DbState {
     pub pool: Pool<Postgres>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxStatus {
+    Pending,
+    Processing,
+    Submitted,
+    Confirmed,
+    Failed,
+}
+
+/// A validated forward request ready to be stored in `tx_requests`.
+/// Addresses are hex strings and `value` is a decimal string so the db crate stays chain agnostic.
+#[derive(Clone, Debug)]
+pub struct NewTxRequest {
+    pub chain_id: i64,
+    pub tenant_id: Uuid,
+    pub from_address: String,
+    pub to_address: String,
+    pub value: String,
+    pub gas: i64,
+    pub deadline: i64,
+    pub data: Vec<u8>,
+    pub signature: Vec<u8>,
+    /// Wei the relayed transaction may cost at most, held against the tenant's budget.
+    pub estimated_cost_wei: Option<String>,
+    /// Receives the request's status changes instead of the tenant's webhook URL.
+    pub callback_url: Option<String>,
+}
+
+#[derive(Clone, Debug, FromRow)]
+pub struct TxRequest {
+    pub id: Uuid,
+    pub chain_id: i64,
+    pub tenant_id: Uuid,
+    pub from_address: String,
+    pub to_address: String,
+    pub value: String,
+    pub gas: i64,
+    pub deadline: i64,
+    pub data: Vec<u8>,
+    pub signature: Vec<u8>,
+    #[sqlx(try_from = "String")]
+    pub status: TxStatus,
+    pub tx_hash: Option<String>,
+    pub gas_used: Option<i64>,
+    /// Wei per gas as a decimal string.
+    pub effective_gas_price: Option<String>,
+    pub block_number: Option<i64>,
+    pub nonce: Option<i64>,
+    pub error: Option<String>,
+    pub retry_count: i32,
+    pub estimated_cost_wei: Option<String>,
+    pub callback_url: Option<String>,
+    /// The `executeBatch` transaction the request is relayed in, if any.
+    pub batch_id: Option<Uuid>,
+    pub batch_index: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Fields written alongside a status transition. `None` leaves the stored value untouched.
+#[derive(Clone, Debug, Default)]
+pub struct StatusUpdate {
+    pub tx_hash: Option<String>,
+    pub nonce: Option<i64>,
+    pub gas_used: Option<i64>,
+    pub effective_gas_price: Option<String>,
+    pub block_number: Option<i64>,
+    pub error: Option<String>,
+}
+
+/// One broadcast of a request. Fee bumps add attempts with the same nonce and higher fees.
+/// Wei amounts are decimal strings, like `NewTxRequest::value`.
+#[derive(Clone, Debug)]
+pub struct NewTxAttempt {
+    pub tx_request_id: Uuid,
+    pub tx_hash: String,
+    pub nonce: i64,
+    pub to_address: String,
+    pub value: String,
+    pub input: Vec<u8>,
+    pub gas_limit: i64,
+    pub max_fee_per_gas: String,
+    pub max_priority_fee_per_gas: String,
+}
+
+#[derive(Clone, Debug, FromRow)]
+pub struct TxAttempt {
+    pub id: i64,
+    pub tx_request_id: Uuid,
+    pub tx_hash: String,
+    pub nonce: i64,
+    pub to_address: String,
+    pub value: String,
+    pub input: Vec<u8>,
+    pub gas_limit: i64,
+    pub max_fee_per_gas: String,
+    pub max_priority_fee_per_gas: String,
+    pub mined: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Page {
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Restricts `TxRequestRepository::list_after`; `None` fields match every request.
+#[derive(Clone, Debug, Default)]
+pub struct TxRequestFilter {
+    pub tenant_id: Option<Uuid>,
+    /// Checksummed, as stored.
+    pub from_address: Option<String>,
+    pub to_address: Option<String>,
+    pub status: Option<TxStatus>,
+    pub since: Option<DateTime<Utc>>,
+}
+
+/// The last request of a page. The next page starts right after it, so pages stay stable
+/// while new requests arrive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+#[derive(Clone, Debug)]
+pub struct TxRequestRepository {
+    pool: Pool<Postgres>,
+}
+
 impl DbState {
     pub async fn default(db_url: &str, max_connection: u32) -> anyhow::Result<Self> {
         // let db_url = config::config::load_env_var("DATABASE_URL"); // loads the database url from the environment variable
@@ -31,4 +183,691 @@ impl DbState {
         sqlx::query("SELECT 1").execute(pool).await?;
         Ok(())
     }
+
+    pub fn tx_requests(&self) -> TxRequestRepository {
+        TxRequestRepository::new(self.pool.clone())
+    }
+
+    pub fn nonces(&self) -> NonceRepository {
+        NonceRepository::new(self.pool.clone())
+    }
+
+    pub fn monitoring(&self) -> MonitoringRepository {
+        MonitoringRepository::new(self.pool.clone())
+    }
+
+    pub fn alerts(&self) -> AlertRepository {
+        AlertRepository::new(self.pool.clone())
+    }
+
+    pub fn policies(&self) -> PolicyRepository {
+        PolicyRepository::new(self.pool.clone())
+    }
+
+    pub fn rate_limits(&self) -> RateLimitRepository {
+        RateLimitRepository::new(self.pool.clone())
+    }
+
+    pub fn tenants(&self) -> TenantRepository {
+        TenantRepository::new(self.pool.clone())
+    }
+
+    pub fn spend(&self) -> SpendRepository {
+        SpendRepository::new(self.pool.clone())
+    }
+
+    pub fn webhooks(&self) -> WebhookRepository {
+        WebhookRepository::new(self.pool.clone())
+    }
+}
+
+impl TxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TxStatus::Pending => "pending",
+            TxStatus::Processing => "processing",
+            TxStatus::Submitted => "submitted",
+            TxStatus::Confirmed => "confirmed",
+            TxStatus::Failed => "failed",
+        }
+    }
+
+    /// Whether a request in this status may move to `next`.
+    pub fn can_transition_to(&self, next: TxStatus) -> bool {
+        matches!(
+            (self, next),
+            (TxStatus::Pending, TxStatus::Processing)
+                | (TxStatus::Pending, TxStatus::Submitted)
+                | (TxStatus::Pending, TxStatus::Failed)
+                | (TxStatus::Processing, TxStatus::Pending)
+                | (TxStatus::Processing, TxStatus::Submitted)
+                | (TxStatus::Processing, TxStatus::Failed)
+                | (TxStatus::Submitted, TxStatus::Confirmed)
+                | (TxStatus::Submitted, TxStatus::Failed)
+        )
+    }
+}
+
+impl TryFrom<String> for TxStatus {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "pending" => Ok(TxStatus::Pending),
+            "processing" => Ok(TxStatus::Processing),
+            "submitted" => Ok(TxStatus::Submitted),
+            "confirmed" => Ok(TxStatus::Confirmed),
+            "failed" => Ok(TxStatus::Failed),
+            _ => Err(format!("Invalid tx request status: {value}")),
+        }
+    }
+}
+
+impl TxRequestRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn insert(&self, request: &NewTxRequest) -> anyhow::Result<Uuid> {
+        let mut connection = self.pool.acquire().await?;
+        Self::insert_with(&mut connection, request).await
+    }
+
+    async fn insert_with(
+        executor: &mut PgConnection,
+        request: &NewTxRequest,
+    ) -> anyhow::Result<Uuid> {
+        let id: Uuid = sqlx::query_scalar(
+            "INSERT INTO tx_requests (chain_id, tenant_id, from_address, to_address, value, gas, deadline, data,
+                                      signature, estimated_cost_wei, callback_url)
+             VALUES ($1, $2, $3, $4, $5::numeric, $6, $7, $8, $9, $10::numeric, $11)
+             RETURNING id",
+        )
+        .bind(request.chain_id)
+        .bind(request.tenant_id)
+        .bind(&request.from_address)
+        .bind(&request.to_address)
+        .bind(&request.value)
+        .bind(request.gas)
+        .bind(request.deadline)
+        .bind(&request.data)
+        .bind(&request.signature)
+        .bind(&request.estimated_cost_wei)
+        .bind(&request.callback_url)
+        .fetch_one(&mut *executor)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Stores a request unless its estimated cost would take the tenant over its daily or
+    /// monthly budget. The tenant row is locked, so concurrent requests cannot both take
+    /// the last of a budget.
+    pub async fn insert_within_budget(
+        &self,
+        request: &NewTxRequest,
+    ) -> anyhow::Result<Result<Uuid, BudgetExceeded>> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SELECT 1 FROM tenants WHERE id = $1 FOR UPDATE")
+            .bind(request.tenant_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let estimate = request.estimated_cost_wei.as_deref().unwrap_or("0");
+        let usage = period_usage(&mut *tx, request.tenant_id, estimate).await?;
+        if let Some(exceeded) = usage.into_iter().find(|usage| usage.exceeded) {
+            return Ok(Err(BudgetExceeded {
+                period: exceeded.period,
+                budget_wei: exceeded.budget_wei.unwrap_or_default(),
+                used_wei: exceeded.used_wei,
+                estimated_cost_wei: estimate.to_string(),
+            }));
+        }
+
+        let id = Self::insert_with(&mut tx, request).await?;
+        tx.commit().await?;
+        Ok(Ok(id))
+    }
+
+    /// Stores the requests of an atomic batch in their order, unless their estimated cost
+    /// together would take the tenant over a budget. They share a tenant and a chain.
+    pub async fn insert_batch_within_budget(
+        &self,
+        requests: &[NewTxRequest],
+    ) -> anyhow::Result<Result<(Uuid, Vec<Uuid>), BudgetExceeded>> {
+        let Some(first) = requests.first() else {
+            anyhow::bail!("A batch needs at least one request");
+        };
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SELECT 1 FROM tenants WHERE id = $1 FOR UPDATE")
+            .bind(first.tenant_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let estimates: Vec<&str> = requests
+            .iter()
+            .map(|request| request.estimated_cost_wei.as_deref().unwrap_or("0"))
+            .collect();
+        let estimate: String = sqlx::query_scalar(
+            "SELECT COALESCE(SUM(e::numeric), 0)::text FROM UNNEST($1::text[]) e",
+        )
+        .bind(&estimates)
+        .fetch_one(&mut *tx)
+        .await?;
+        let usage = period_usage(&mut *tx, first.tenant_id, &estimate).await?;
+        if let Some(exceeded) = usage.into_iter().find(|usage| usage.exceeded) {
+            return Ok(Err(BudgetExceeded {
+                period: exceeded.period,
+                budget_wei: exceeded.budget_wei.unwrap_or_default(),
+                used_wei: exceeded.used_wei,
+                estimated_cost_wei: estimate,
+            }));
+        }
+
+        let batch_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO tx_batches (chain_id, tenant_id, atomic) VALUES ($1, $2, TRUE) RETURNING id",
+        )
+        .bind(first.chain_id)
+        .bind(first.tenant_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let mut ids = Vec::with_capacity(requests.len());
+        for (index, request) in requests.iter().enumerate() {
+            let id = Self::insert_with(&mut tx, request).await?;
+            sqlx::query("UPDATE tx_requests SET batch_id = $2, batch_index = $3 WHERE id = $1")
+                .bind(id)
+                .bind(batch_id)
+                .bind(index as i32)
+                .execute(&mut *tx)
+                .await?;
+            ids.push(id);
+        }
+        tx.commit().await?;
+        Ok(Ok((batch_id, ids)))
+    }
+
+    /// Records that the claimed requests went out in one non-atomic `executeBatch`, in
+    /// the order of `ids`.
+    pub async fn create_batch(
+        &self,
+        chain_id: i64,
+        refund_receiver: &str,
+        ids: &[Uuid],
+    ) -> anyhow::Result<Uuid> {
+        let mut tx = self.pool.begin().await?;
+        let batch_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO tx_batches (chain_id, atomic, refund_receiver) VALUES ($1, FALSE, $2) RETURNING id",
+        )
+        .bind(chain_id)
+        .bind(refund_receiver)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "UPDATE tx_requests r
+             SET batch_id = $1, batch_index = m.index - 1, updated_at = NOW()
+             FROM UNNEST($2::uuid[]) WITH ORDINALITY AS m(id, index)
+             WHERE r.id = m.id",
+        )
+        .bind(batch_id)
+        .bind(ids)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(batch_id)
+    }
+
+    /// Every request of a batch, in the order they were passed to `executeBatch`.
+    pub async fn batch_members(&self, batch_id: Uuid) -> anyhow::Result<Vec<TxRequest>> {
+        let requests = sqlx::query_as::<_, TxRequest>(&format!(
+            "SELECT {TX_REQUEST_COLUMNS} FROM tx_requests WHERE batch_id = $1 ORDER BY batch_index"
+        ))
+        .bind(batch_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(requests)
+    }
+
+    /// Moves a request from `from` to `to`, returning `false` if it was no longer in `from`.
+    /// Settling with a receipt adds the transaction's cost to the tenant's spend ledger, and
+    /// requests with a callback URL (their own or their tenant's) get a webhook event queued.
+    pub async fn transition_status(
+        &self,
+        id: Uuid,
+        from: TxStatus,
+        to: TxStatus,
+        update: &StatusUpdate,
+    ) -> anyhow::Result<bool> {
+        if !from.can_transition_to(to) {
+            anyhow::bail!(
+                "Invalid status transition from {} to {}",
+                from.as_str(),
+                to.as_str()
+            );
+        }
+
+        let updated: i64 = sqlx::query_scalar(
+            "WITH updated AS (
+                 UPDATE tx_requests
+                 SET status = $3,
+                     tx_hash = COALESCE($4, tx_hash),
+                     nonce = COALESCE($5, nonce),
+                     gas_used = COALESCE($6, gas_used),
+                     effective_gas_price = COALESCE($7::numeric, effective_gas_price),
+                     block_number = COALESCE($8, block_number),
+                     error = COALESCE($9, error),
+                     updated_at = NOW()
+                 WHERE id = $1 AND status = $2
+                 RETURNING id, tenant_id, chain_id, status, tx_hash, gas_used, effective_gas_price,
+                           block_number, error, callback_url
+             ), spend AS (
+                 INSERT INTO tenant_spend (tenant_id, tx_request_id, chain_id, gas_used, effective_gas_price, cost_wei)
+                 SELECT tenant_id, id, chain_id, gas_used, effective_gas_price, gas_used * effective_gas_price
+                 FROM updated
+                 WHERE $3 IN ('confirmed', 'failed') AND gas_used IS NOT NULL AND effective_gas_price IS NOT NULL
+                 ON CONFLICT (tx_request_id) DO NOTHING
+             ), webhook AS (
+                 INSERT INTO webhook_deliveries (id, tenant_id, tx_request_id, url, event_type, payload)
+                 SELECT event_id, tenant_id, id, url, 'relay.' || status,
+                        jsonb_build_object(
+                            'id', event_id,
+                            'type', 'relay.' || status,
+                            'created_at', NOW(),
+                            'data', jsonb_build_object(
+                                'id', id,
+                                'chain_id', chain_id,
+                                'status', status,
+                                'previous_status', $2::text,
+                                'tx_hash', tx_hash,
+                                'block_number', block_number,
+                                'gas_used', gas_used,
+                                'effective_gas_price', effective_gas_price::text,
+                                'error', error))
+                 FROM (
+                     SELECT gen_random_uuid() AS event_id, u.*, COALESCE(u.callback_url, t.webhook_url) AS url
+                     FROM updated u JOIN tenants t ON t.id = u.tenant_id
+                 ) events
+                 WHERE url IS NOT NULL
+             )
+             SELECT COUNT(*) FROM updated",
+        )
+        .bind(id)
+        .bind(from.as_str())
+        .bind(to.as_str())
+        .bind(&update.tx_hash)
+        .bind(update.nonce)
+        .bind(update.gas_used)
+        .bind(&update.effective_gas_price)
+        .bind(update.block_number)
+        .bind(&update.error)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(updated == 1)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid) -> anyhow::Result<Option<TxRequest>> {
+        let request = sqlx::query_as::<_, TxRequest>(&format!(
+            "SELECT {TX_REQUEST_COLUMNS} FROM tx_requests WHERE id = $1"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(request)
+    }
+
+    /// Requests whose current transaction, or any replaced attempt of it, has this hash.
+    pub async fn find_by_tx_hash(&self, tx_hash: &str) -> anyhow::Result<Vec<TxRequest>> {
+        let requests = sqlx::query_as::<_, TxRequest>(&format!(
+            "SELECT {TX_REQUEST_COLUMNS} FROM tx_requests
+             WHERE tx_hash = LOWER($1)
+                OR id IN (SELECT tx_request_id FROM tx_attempts WHERE tx_hash = LOWER($1))
+             ORDER BY created_at"
+        ))
+        .bind(tx_hash)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(requests)
+    }
+
+    /// Newest first, optionally restricted to a single status.
+    pub async fn list(
+        &self,
+        status: Option<TxStatus>,
+        page: Page,
+    ) -> anyhow::Result<Vec<TxRequest>> {
+        let requests = sqlx::query_as::<_, TxRequest>(&format!(
+            "SELECT {TX_REQUEST_COLUMNS} FROM tx_requests
+             WHERE ($1::text IS NULL OR status = $1)
+             ORDER BY created_at DESC, id DESC
+             LIMIT $2 OFFSET $3"
+        ))
+        .bind(status.map(|s| s.as_str()))
+        .bind(page.limit)
+        .bind(page.offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(requests)
+    }
+
+    /// Up to `limit` requests matching `filter`, newest first, starting after `after`.
+    pub async fn list_after(
+        &self,
+        filter: &TxRequestFilter,
+        after: Option<Cursor>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<TxRequest>> {
+        let requests = sqlx::query_as::<_, TxRequest>(&format!(
+            "SELECT {TX_REQUEST_COLUMNS} FROM tx_requests
+             WHERE ($1::uuid IS NULL OR tenant_id = $1)
+               AND ($2::text IS NULL OR from_address = $2)
+               AND ($3::text IS NULL OR to_address = $3)
+               AND ($4::text IS NULL OR status = $4)
+               AND ($5::timestamptz IS NULL OR created_at >= $5)
+               AND ($6::timestamptz IS NULL OR (created_at, id) < ($6, $7))
+             ORDER BY created_at DESC, id DESC
+             LIMIT $8"
+        ))
+        .bind(filter.tenant_id)
+        .bind(&filter.from_address)
+        .bind(&filter.to_address)
+        .bind(filter.status.map(|s| s.as_str()))
+        .bind(filter.since)
+        .bind(after.map(|cursor| cursor.created_at))
+        .bind(after.map(|cursor| cursor.id))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(requests)
+    }
+
+    /// Atomically moves up to `limit` of the oldest pending requests of a chain to `processing`.
+    /// `SKIP LOCKED` lets several workers or replicas claim disjoint batches concurrently.
+    /// Requests of atomic batches are left to `claim_pending_batch`.
+    pub async fn claim_pending(&self, chain_id: i64, limit: i64) -> anyhow::Result<Vec<TxRequest>> {
+        let requests = sqlx::query_as::<_, TxRequest>(&format!(
+            "UPDATE tx_requests
+             SET status = 'processing', updated_at = NOW()
+             WHERE id IN (
+                 SELECT id FROM tx_requests
+                 WHERE chain_id = $1 AND status = 'pending' AND batch_id IS NULL
+                 ORDER BY created_at
+                 LIMIT $2
+                 FOR UPDATE SKIP LOCKED
+             )
+             RETURNING {TX_REQUEST_COLUMNS}"
+        ))
+        .bind(chain_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(requests)
+    }
+
+    /// Moves every request of the oldest pending atomic batch of a chain to `processing`,
+    /// in batch order. The batch row is locked, so a batch is claimed whole by one worker,
+    /// and `SKIP LOCKED` moves on to the next batch while another worker holds one.
+    pub async fn claim_pending_batch(&self, chain_id: i64) -> anyhow::Result<Vec<TxRequest>> {
+        let mut requests = sqlx::query_as::<_, TxRequest>(&format!(
+            "WITH batch AS (
+                 SELECT id FROM tx_batches
+                 WHERE chain_id = $1
+                   AND EXISTS (
+                       SELECT 1 FROM tx_requests
+                       WHERE batch_id = tx_batches.id AND status = 'pending'
+                   )
+                 ORDER BY created_at
+                 LIMIT 1
+                 FOR UPDATE SKIP LOCKED
+             )
+             UPDATE tx_requests
+             SET status = 'processing', updated_at = NOW()
+             WHERE status = 'pending' AND batch_id = (SELECT id FROM batch)
+             RETURNING {TX_REQUEST_COLUMNS}"
+        ))
+        .bind(chain_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        requests.sort_by_key(|request| request.batch_index);
+        Ok(requests)
+    }
+
+    /// Puts a claimed request back in the queue after a failed broadcast.
+    pub async fn requeue(&self, id: Uuid, error: &str) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            "UPDATE tx_requests
+             SET status = 'pending', retry_count = retry_count + 1, error = $2, updated_at = NOW()
+             WHERE id = $1 AND status = 'processing'",
+        )
+        .bind(id)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Requests left in `processing` by a worker that died mid-flight.
+    pub async fn stale_processing(
+        &self,
+        chain_id: i64,
+        older_than_secs: f64,
+    ) -> anyhow::Result<Vec<TxRequest>> {
+        let requests = sqlx::query_as::<_, TxRequest>(&format!(
+            "SELECT {TX_REQUEST_COLUMNS} FROM tx_requests
+             WHERE chain_id = $1 AND status = 'processing'
+               AND updated_at < NOW() - make_interval(secs => $2)"
+        ))
+        .bind(chain_id)
+        .bind(older_than_secs)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(requests)
+    }
+
+    /// Returns requests left in `processing` by a worker that died mid-flight to the queue.
+    /// Requests with a recorded attempt are left alone, as their transaction may be mined.
+    pub async fn requeue_stale_processing(
+        &self,
+        chain_id: i64,
+        older_than_secs: f64,
+    ) -> anyhow::Result<u64> {
+        let result = sqlx::query(
+            "UPDATE tx_requests
+             SET status = 'pending', updated_at = NOW()
+             WHERE chain_id = $1 AND status = 'processing'
+               AND updated_at < NOW() - make_interval(secs => $2)
+               AND NOT EXISTS (SELECT 1 FROM tx_attempts a WHERE a.tx_request_id = tx_requests.id)",
+        )
+        .bind(chain_id)
+        .bind(older_than_secs)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Submitted requests of a chain whose receipts still need checking, least recently
+    /// checked first, and marks them checked. Requests that stay unmined thus go to the back
+    /// of the line instead of filling every page.
+    pub async fn list_submitted(
+        &self,
+        chain_id: i64,
+        limit: i64,
+    ) -> anyhow::Result<Vec<TxRequest>> {
+        let requests = sqlx::query_as::<_, TxRequest>(&format!(
+            "UPDATE tx_requests
+             SET last_checked_at = NOW()
+             WHERE id IN (
+                 SELECT id FROM tx_requests
+                 WHERE chain_id = $1 AND status = 'submitted'
+                 ORDER BY last_checked_at NULLS FIRST, updated_at
+                 LIMIT $2
+                 FOR UPDATE SKIP LOCKED
+             )
+             RETURNING {TX_REQUEST_COLUMNS}"
+        ))
+        .bind(chain_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(requests)
+    }
+
+    /// Submitted requests of a chain whose first broadcast is older than `older_than_secs`.
+    pub async fn count_stuck(&self, chain_id: i64, older_than_secs: f64) -> anyhow::Result<i64> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM tx_requests r
+             WHERE r.chain_id = $1 AND r.status = 'submitted'
+               AND EXISTS (
+                   SELECT 1 FROM tx_attempts a
+                   WHERE a.tx_request_id = r.id
+                     AND a.created_at < NOW() - make_interval(secs => $2)
+               )",
+        )
+        .bind(chain_id)
+        .bind(older_than_secs)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    pub async fn count_by_status(&self, chain_id: i64, status: TxStatus) -> anyhow::Result<i64> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM tx_requests WHERE chain_id = $1 AND status = $2",
+        )
+        .bind(chain_id)
+        .bind(status.as_str())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Points a submitted request at the hash of its latest replacement, and queues a
+    /// `relay.replaced` webhook in the same statement, as `transition_status` does.
+    /// A request already at `tx_hash` is left alone.
+    pub async fn replace_tx_hash(&self, id: Uuid, tx_hash: &str) -> anyhow::Result<bool> {
+        let updated: i64 = sqlx::query_scalar(
+            "WITH previous AS (
+                 SELECT id, tx_hash FROM tx_requests
+                 WHERE id = $1 AND status = 'submitted' AND tx_hash IS DISTINCT FROM LOWER($2)
+                 FOR UPDATE
+             ), updated AS (
+                 UPDATE tx_requests r
+                 SET tx_hash = LOWER($2), updated_at = NOW()
+                 FROM previous p
+                 WHERE r.id = p.id
+                 RETURNING r.id, r.tenant_id, r.chain_id, r.status, r.tx_hash, r.callback_url,
+                           p.tx_hash AS replaced_tx_hash
+             ), webhook AS (
+                 INSERT INTO webhook_deliveries (id, tenant_id, tx_request_id, url, event_type, payload)
+                 SELECT event_id, tenant_id, id, url, 'relay.replaced',
+                        jsonb_build_object(
+                            'id', event_id,
+                            'type', 'relay.replaced',
+                            'created_at', NOW(),
+                            'data', jsonb_build_object(
+                                'id', id,
+                                'chain_id', chain_id,
+                                'status', status,
+                                'previous_status', status,
+                                'tx_hash', tx_hash,
+                                'replaced_tx_hash', replaced_tx_hash))
+                 FROM (
+                     SELECT gen_random_uuid() AS event_id, u.*, COALESCE(u.callback_url, t.webhook_url) AS url
+                     FROM updated u JOIN tenants t ON t.id = u.tenant_id
+                 ) events
+                 WHERE url IS NOT NULL
+             )
+             SELECT COUNT(*) FROM updated",
+        )
+        .bind(id)
+        .bind(tx_hash)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(updated == 1)
+    }
+
+    pub async fn record_attempt(&self, attempt: &NewTxAttempt) -> anyhow::Result<i64> {
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO tx_attempts (tx_request_id, tx_hash, nonce, to_address, value, input, gas_limit,
+                                      max_fee_per_gas, max_priority_fee_per_gas)
+             VALUES ($1, LOWER($2), $3, $4, $5::numeric, $6, $7, $8::numeric, $9::numeric)
+             RETURNING id",
+        )
+        .bind(attempt.tx_request_id)
+        .bind(&attempt.tx_hash)
+        .bind(attempt.nonce)
+        .bind(&attempt.to_address)
+        .bind(&attempt.value)
+        .bind(&attempt.input)
+        .bind(attempt.gas_limit)
+        .bind(&attempt.max_fee_per_gas)
+        .bind(&attempt.max_priority_fee_per_gas)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Forgets the attempts of a transaction the node refused, which never reached the chain.
+    pub async fn delete_attempts(&self, tx_hash: &str) -> anyhow::Result<u64> {
+        let result = sqlx::query("DELETE FROM tx_attempts WHERE tx_hash = LOWER($1)")
+            .bind(tx_hash)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Every broadcast of a request, oldest first.
+    pub async fn attempts(&self, tx_request_id: Uuid) -> anyhow::Result<Vec<TxAttempt>> {
+        let attempts = sqlx::query_as::<_, TxAttempt>(&format!(
+            "SELECT {TX_ATTEMPT_COLUMNS} FROM tx_attempts WHERE tx_request_id = $1 ORDER BY created_at, id"
+        ))
+        .bind(tx_request_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(attempts)
+    }
+
+    /// Every broadcast of several requests, grouped by request and oldest first within each.
+    pub async fn attempts_of(&self, tx_request_ids: &[Uuid]) -> anyhow::Result<Vec<TxAttempt>> {
+        let attempts = sqlx::query_as::<_, TxAttempt>(&format!(
+            "SELECT {TX_ATTEMPT_COLUMNS} FROM tx_attempts
+             WHERE tx_request_id = ANY($1)
+             ORDER BY tx_request_id, created_at, id"
+        ))
+        .bind(tx_request_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(attempts)
+    }
+
+    /// Flags the attempt that made it on chain.
+    pub async fn mark_attempt_mined(&self, tx_hash: &str) -> anyhow::Result<()> {
+        sqlx::query("UPDATE tx_attempts SET mined = TRUE WHERE tx_hash = LOWER($1)")
+            .bind(tx_hash)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
 }
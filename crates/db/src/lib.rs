@@ -1 +1,9 @@
+pub mod alerts;
 pub mod db;
+pub mod monitoring;
+pub mod nonces;
+pub mod policies;
+pub mod rate_limits;
+pub mod spend;
+pub mod tenants;
+pub mod webhooks;
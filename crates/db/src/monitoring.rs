@@ -0,0 +1,214 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, Pool, Postgres};
+use uuid::Uuid;
+
+/// Result of one health check of one component, as returned by `/health`.
+#[derive(Clone, Debug)]
+pub struct NewHealthCheck {
+    pub component: String,
+    pub status: String,
+    pub response_time_ms: Option<i32>,
+    pub details: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct HealthCheckRecord {
+    pub id: Uuid,
+    pub component: String,
+    pub status: String,
+    pub response_time_ms: Option<i32>,
+    pub details: Option<serde_json::Value>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Value of one metric series at the time of the snapshot.
+#[derive(Clone, Debug)]
+pub struct NewMetricSnapshot {
+    pub metric_name: String,
+    pub metric_value: f64,
+    pub labels: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct MetricSnapshot {
+    pub id: Uuid,
+    pub metric_name: String,
+    pub metric_value: f64,
+    pub labels: Option<serde_json::Value>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Average duration of an operation over one snapshot interval.
+#[derive(Clone, Debug)]
+pub struct NewPerformanceMetric {
+    pub operation: String,
+    pub duration_ms: f64,
+    pub success: bool,
+    pub error_message: Option<String>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewQueueStats {
+    pub queue_name: String,
+    pub depth: i32,
+    /// Requests settled per second over the snapshot interval.
+    pub processing_rate: Option<f64>,
+    pub avg_wait_time_ms: Option<f64>,
+}
+
+/// Reads and writes the periodic monitoring tables: `health_checks`, `metrics_snapshots`,
+/// `performance_metrics` and `queue_stats`.
+#[derive(Clone, Debug)]
+pub struct MonitoringRepository {
+    pool: Pool<Postgres>,
+}
+
+impl MonitoringRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn record_health_checks(&self, checks: &[NewHealthCheck]) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for check in checks {
+            sqlx::query(
+                "INSERT INTO health_checks (component, status, response_time_ms, details)
+                 VALUES ($1, $2, $3, $4)",
+            )
+            .bind(&check.component)
+            .bind(&check.status)
+            .bind(check.response_time_ms)
+            .bind(&check.details)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn record_metric_snapshots(
+        &self,
+        snapshots: &[NewMetricSnapshot],
+    ) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for snapshot in snapshots {
+            sqlx::query(
+                "INSERT INTO metrics_snapshots (metric_name, metric_value, labels)
+                 VALUES ($1, $2, $3)",
+            )
+            .bind(&snapshot.metric_name)
+            .bind(snapshot.metric_value)
+            .bind(&snapshot.labels)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn record_performance_metrics(
+        &self,
+        metrics: &[NewPerformanceMetric],
+    ) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for metric in metrics {
+            sqlx::query(
+                "INSERT INTO performance_metrics (operation, duration_ms, success, error_message, metadata)
+                 VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(&metric.operation)
+            .bind(metric.duration_ms)
+            .bind(metric.success)
+            .bind(&metric.error_message)
+            .bind(&metric.metadata)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn record_queue_stats(&self, stats: &NewQueueStats) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO queue_stats (queue_name, depth, processing_rate, avg_wait_time_ms)
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(&stats.queue_name)
+        .bind(stats.depth)
+        .bind(stats.processing_rate)
+        .bind(stats.avg_wait_time_ms)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Newest first, optionally restricted to one component.
+    pub async fn health_history(
+        &self,
+        component: Option<&str>,
+        since: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<HealthCheckRecord>> {
+        let records = sqlx::query_as::<_, HealthCheckRecord>(
+            "SELECT id, component, status, response_time_ms, details, checked_at
+             FROM health_checks
+             WHERE ($1::text IS NULL OR component = $1)
+               AND ($2::timestamptz IS NULL OR checked_at >= $2)
+             ORDER BY checked_at DESC
+             LIMIT $3",
+        )
+        .bind(component)
+        .bind(since)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    /// Newest first.
+    pub async fn metric_history(
+        &self,
+        metric_name: &str,
+        since: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<MetricSnapshot>> {
+        let snapshots = sqlx::query_as::<_, MetricSnapshot>(
+            "SELECT id, metric_name, metric_value::float8 AS metric_value, labels, recorded_at
+             FROM metrics_snapshots
+             WHERE metric_name = $1
+               AND ($2::timestamptz IS NULL OR recorded_at >= $2)
+             ORDER BY recorded_at DESC
+             LIMIT $3",
+        )
+        .bind(metric_name)
+        .bind(since)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(snapshots)
+    }
+
+    /// Deletes monitoring rows recorded before `cutoff`. Returns the number of rows removed.
+    pub async fn prune(&self, cutoff: DateTime<Utc>) -> anyhow::Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut removed = 0;
+        for query in [
+            "DELETE FROM health_checks WHERE checked_at < $1",
+            "DELETE FROM metrics_snapshots WHERE recorded_at < $1",
+            "DELETE FROM performance_metrics WHERE recorded_at < $1",
+            "DELETE FROM queue_stats WHERE recorded_at < $1",
+        ] {
+            removed += sqlx::query(query)
+                .bind(cutoff)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(removed)
+    }
+}
@@ -0,0 +1,223 @@
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonceState {
+    /// Handed to a worker that has not broadcast a transaction with it yet.
+    Reserved,
+    /// A transaction using this nonce reached the node.
+    Broadcast,
+    /// Given back unused; the next reservation reuses it before advancing the counter.
+    Released,
+}
+
+/// Hands out relayer nonces from Postgres. Every method is scoped to one `(chain_id, address)` account.
+#[derive(Clone, Debug)]
+pub struct NonceRepository {
+    pool: Pool<Postgres>,
+}
+
+impl NonceState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NonceState::Reserved => "reserved",
+            NonceState::Broadcast => "broadcast",
+            NonceState::Released => "released",
+        }
+    }
+}
+
+impl NonceRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Aligns the stored counter with the chain's pending nonce and forgets reservations
+    /// the chain has already consumed. Returns the next nonce to hand out.
+    pub async fn sync(
+        &self,
+        chain_id: i64,
+        address: &str,
+        chain_pending: i64,
+    ) -> anyhow::Result<i64> {
+        let mut tx = self.pool.begin().await?;
+
+        let next_nonce: i64 = sqlx::query_scalar(
+            "INSERT INTO relayer_nonces (chain_id, address, next_nonce)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (chain_id, address) DO UPDATE
+             SET next_nonce = GREATEST(relayer_nonces.next_nonce, EXCLUDED.next_nonce),
+                 updated_at = NOW()
+             RETURNING next_nonce",
+        )
+        .bind(chain_id)
+        .bind(address)
+        .bind(chain_pending)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "DELETE FROM nonce_reservations WHERE chain_id = $1 AND address = $2 AND nonce < $3",
+        )
+        .bind(chain_id)
+        .bind(address)
+        .bind(chain_pending)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(next_nonce)
+    }
+
+    /// Reserves the lowest released nonce, or the next fresh one when none was released.
+    pub async fn reserve(
+        &self,
+        chain_id: i64,
+        address: &str,
+        tx_request_id: Option<Uuid>,
+    ) -> anyhow::Result<i64> {
+        let mut tx = self.pool.begin().await?;
+
+        let released: Option<i64> = sqlx::query_scalar(
+            "SELECT nonce FROM nonce_reservations
+             WHERE chain_id = $1 AND address = $2 AND state = 'released'
+             ORDER BY nonce
+             LIMIT 1
+             FOR UPDATE SKIP LOCKED",
+        )
+        .bind(chain_id)
+        .bind(address)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let nonce = match released {
+            Some(nonce) => {
+                sqlx::query(
+                    "UPDATE nonce_reservations
+                     SET state = 'reserved', tx_request_id = $4, reserved_at = NOW(), updated_at = NOW()
+                     WHERE chain_id = $1 AND address = $2 AND nonce = $3",
+                )
+                .bind(chain_id)
+                .bind(address)
+                .bind(nonce)
+                .bind(tx_request_id)
+                .execute(&mut *tx)
+                .await?;
+                nonce
+            }
+            None => {
+                let nonce: Option<i64> = sqlx::query_scalar(
+                    "UPDATE relayer_nonces
+                     SET next_nonce = next_nonce + 1, updated_at = NOW()
+                     WHERE chain_id = $1 AND address = $2
+                     RETURNING next_nonce - 1",
+                )
+                .bind(chain_id)
+                .bind(address)
+                .fetch_optional(&mut *tx)
+                .await?;
+                let nonce = nonce.ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "nonce counter for {address} on chain {chain_id} was never synced"
+                    )
+                })?;
+
+                sqlx::query(
+                    "INSERT INTO nonce_reservations (chain_id, address, nonce, state, tx_request_id)
+                     VALUES ($1, $2, $3, 'reserved', $4)",
+                )
+                .bind(chain_id)
+                .bind(address)
+                .bind(nonce)
+                .bind(tx_request_id)
+                .execute(&mut *tx)
+                .await?;
+                nonce
+            }
+        };
+
+        tx.commit().await?;
+        Ok(nonce)
+    }
+
+    pub async fn set_state(
+        &self,
+        chain_id: i64,
+        address: &str,
+        nonce: i64,
+        state: NonceState,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            "UPDATE nonce_reservations
+             SET state = $4, updated_at = NOW()
+             WHERE chain_id = $1 AND address = $2 AND nonce = $3",
+        )
+        .bind(chain_id)
+        .bind(address)
+        .bind(nonce)
+        .bind(state.as_str())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Nonces between the chain's pending nonce and the stored counter that nothing will
+    /// ever broadcast: released, missing, or reserved longer than `stale_after_secs` ago.
+    pub async fn find_gaps(
+        &self,
+        chain_id: i64,
+        address: &str,
+        chain_pending: i64,
+        stale_after_secs: f64,
+    ) -> anyhow::Result<Vec<i64>> {
+        let gaps: Vec<i64> = sqlx::query_scalar(
+            "SELECT n FROM generate_series(
+                 $3::bigint,
+                 (SELECT next_nonce - 1 FROM relayer_nonces WHERE chain_id = $1 AND address = $2)
+             ) AS n
+             WHERE NOT EXISTS (
+                 SELECT 1 FROM nonce_reservations r
+                 WHERE r.chain_id = $1 AND r.address = $2 AND r.nonce = n
+                   AND (r.state = 'broadcast'
+                        OR (r.state = 'reserved' AND r.updated_at > NOW() - make_interval(secs => $4)))
+             )
+             ORDER BY n",
+        )
+        .bind(chain_id)
+        .bind(address)
+        .bind(chain_pending)
+        .bind(stale_after_secs)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(gaps)
+    }
+
+    /// Takes ownership of a gap so only one replica fills it. Returns `false` if someone else did.
+    pub async fn claim_gap(
+        &self,
+        chain_id: i64,
+        address: &str,
+        nonce: i64,
+        stale_after_secs: f64,
+    ) -> anyhow::Result<bool> {
+        let claimed: Option<i64> = sqlx::query_scalar(
+            "INSERT INTO nonce_reservations (chain_id, address, nonce, state)
+             VALUES ($1, $2, $3, 'reserved')
+             ON CONFLICT (chain_id, address, nonce) DO UPDATE
+             SET state = 'reserved', tx_request_id = NULL, reserved_at = NOW(), updated_at = NOW()
+             WHERE nonce_reservations.state = 'released'
+                OR (nonce_reservations.state = 'reserved'
+                    AND nonce_reservations.updated_at <= NOW() - make_interval(secs => $4))
+             RETURNING nonce",
+        )
+        .bind(chain_id)
+        .bind(address)
+        .bind(nonce)
+        .bind(stale_after_secs)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(claimed.is_some())
+    }
+}
@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, Pool, Postgres};
+
+const POLICY_COLUMNS: &str = "id, document, description, created_at";
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct StoredPolicy {
+    pub id: i64,
+    /// Same shape as the policy file, as JSON.
+    pub document: serde_json::Value,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Versions of the relay policy in `relay_policies`. The newest one is active.
+#[derive(Clone, Debug)]
+pub struct PolicyRepository {
+    pool: Pool<Postgres>,
+}
+
+impl PolicyRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Stores a new version, which becomes the active policy.
+    pub async fn insert(
+        &self,
+        document: &serde_json::Value,
+        description: Option<&str>,
+    ) -> anyhow::Result<StoredPolicy> {
+        let policy = sqlx::query_as::<_, StoredPolicy>(&format!(
+            "INSERT INTO relay_policies (document, description)
+             VALUES ($1, $2)
+             RETURNING {POLICY_COLUMNS}"
+        ))
+        .bind(document)
+        .bind(description)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(policy)
+    }
+
+    pub async fn latest(&self) -> anyhow::Result<Option<StoredPolicy>> {
+        let policy = sqlx::query_as::<_, StoredPolicy>(&format!(
+            "SELECT {POLICY_COLUMNS} FROM relay_policies ORDER BY id DESC LIMIT 1"
+        ))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(policy)
+    }
+}
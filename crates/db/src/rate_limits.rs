@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, Pool, Postgres};
+
+/// Outcome of taking a token from a bucket.
+#[derive(Clone, Copy, Debug, FromRow)]
+pub struct BucketState {
+    pub allowed: bool,
+    /// Tokens left after the request.
+    pub tokens: f64,
+}
+
+/// Token buckets in `rate_limit_buckets`, shared by every replica.
+#[derive(Clone, Debug)]
+pub struct RateLimitRepository {
+    pool: Pool<Postgres>,
+}
+
+impl RateLimitRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Refills bucket `key` for the time since its last use and takes a token if one is
+    /// left. A new bucket starts with `burst` tokens. The row lock of the upsert keeps
+    /// concurrent requests from spending the same token.
+    pub async fn take(
+        &self,
+        key: &str,
+        burst: f64,
+        per_second: f64,
+    ) -> anyhow::Result<BucketState> {
+        let refilled =
+            "LEAST($2, b.tokens + EXTRACT(EPOCH FROM NOW() - b.updated_at)::DOUBLE PRECISION * $3)";
+        let state = sqlx::query_as::<_, BucketState>(&format!(
+            "INSERT INTO rate_limit_buckets AS b (key, tokens, allowed, updated_at)
+             VALUES ($1, $2 - 1, TRUE, NOW())
+             ON CONFLICT (key) DO UPDATE SET
+                 tokens = CASE WHEN {refilled} >= 1 THEN {refilled} - 1 ELSE {refilled} END,
+                 allowed = {refilled} >= 1,
+                 updated_at = NOW()
+             RETURNING allowed, tokens"
+        ))
+        .bind(key)
+        .bind(burst)
+        .bind(per_second)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(state)
+    }
+
+    /// Deletes buckets unused since `before`. Returns how many were removed.
+    pub async fn prune(&self, before: DateTime<Utc>) -> anyhow::Result<u64> {
+        let result = sqlx::query("DELETE FROM rate_limit_buckets WHERE updated_at < $1")
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
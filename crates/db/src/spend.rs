@@ -0,0 +1,186 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgExecutor, Pool, Postgres};
+use uuid::Uuid;
+
+/// A window a tenant's budget applies to, starting at UTC midnight or the first of the month.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetPeriod {
+    Daily,
+    Monthly,
+}
+
+/// Width of the buckets spend is summed over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpendBucketSize {
+    Hour,
+    Day,
+    Month,
+}
+
+/// Budget use of the current period. Wei amounts are decimal strings.
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct PeriodUsage {
+    #[sqlx(try_from = "String")]
+    pub period: BudgetPeriod,
+    pub since: DateTime<Utc>,
+    pub budget_wei: Option<String>,
+    /// Cost of the transactions settled in the period.
+    pub spent_wei: String,
+    /// Estimated cost of the requests accepted in the period and not settled yet.
+    pub reserved_wei: String,
+    /// Spent plus reserved.
+    pub used_wei: String,
+    pub remaining_wei: Option<String>,
+    /// Whether spending the estimate passed to the query would go over the budget.
+    #[serde(skip)]
+    pub exceeded: bool,
+}
+
+/// Why a request was refused: settling it could take the tenant over a budget.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct BudgetExceeded {
+    pub period: BudgetPeriod,
+    pub budget_wei: String,
+    /// Spent plus reserved.
+    pub used_wei: String,
+    pub estimated_cost_wei: String,
+}
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct SpendBucket {
+    pub start: DateTime<Utc>,
+    pub transactions: i64,
+    pub gas_used: i64,
+    pub cost_wei: String,
+}
+
+/// The spend ledger in `tenant_spend`, filled as relayed transactions settle.
+#[derive(Clone, Debug)]
+pub struct SpendRepository {
+    pool: Pool<Postgres>,
+}
+
+impl BudgetPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BudgetPeriod::Daily => "daily",
+            BudgetPeriod::Monthly => "monthly",
+        }
+    }
+}
+
+impl TryFrom<String> for BudgetPeriod {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "daily" => Ok(BudgetPeriod::Daily),
+            "monthly" => Ok(BudgetPeriod::Monthly),
+            other => Err(format!("unknown budget period: {other}")),
+        }
+    }
+}
+
+impl SpendBucketSize {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpendBucketSize::Hour => "hour",
+            SpendBucketSize::Day => "day",
+            SpendBucketSize::Month => "month",
+        }
+    }
+}
+
+impl TryFrom<String> for SpendBucketSize {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "hour" => Ok(SpendBucketSize::Hour),
+            "day" => Ok(SpendBucketSize::Day),
+            "month" => Ok(SpendBucketSize::Month),
+            other => Err(format!("unknown bucket size: {other}")),
+        }
+    }
+}
+
+impl SpendRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Daily and monthly budget use of a tenant, whether or not it has budgets.
+    pub async fn period_usage(&self, tenant_id: Uuid) -> anyhow::Result<Vec<PeriodUsage>> {
+        period_usage(&self.pool, tenant_id, "0").await
+    }
+
+    /// Settled spend between `since` and `until`, per bucket, oldest first. Empty buckets
+    /// are left out.
+    pub async fn buckets(
+        &self,
+        tenant_id: Uuid,
+        size: SpendBucketSize,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<SpendBucket>> {
+        let buckets = sqlx::query_as::<_, SpendBucket>(
+            "SELECT date_trunc($2, recorded_at, 'UTC') AS start,
+                    COUNT(*) AS transactions,
+                    SUM(gas_used)::bigint AS gas_used,
+                    SUM(cost_wei)::text AS cost_wei
+             FROM tenant_spend
+             WHERE tenant_id = $1 AND recorded_at >= $3 AND recorded_at < $4
+             GROUP BY start
+             ORDER BY start",
+        )
+        .bind(tenant_id)
+        .bind(size.as_str())
+        .bind(since)
+        .bind(until)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(buckets)
+    }
+}
+
+/// Budget use of both periods. `estimated_cost_wei` is what the caller is about to add, and
+/// sets `exceeded`.
+pub(crate) async fn period_usage<'e>(
+    executor: impl PgExecutor<'e>,
+    tenant_id: Uuid,
+    estimated_cost_wei: &str,
+) -> anyhow::Result<Vec<PeriodUsage>> {
+    let usage = sqlx::query_as::<_, PeriodUsage>(
+        "WITH periods AS (
+             SELECT 'daily' AS period, date_trunc('day', NOW(), 'UTC') AS since, daily_budget_wei AS budget
+             FROM tenants WHERE id = $1
+             UNION ALL
+             SELECT 'monthly', date_trunc('month', NOW(), 'UTC'), monthly_budget_wei
+             FROM tenants WHERE id = $1
+         ), used AS (
+             SELECT p.period, p.since, p.budget,
+                    COALESCE((SELECT SUM(cost_wei) FROM tenant_spend s
+                              WHERE s.tenant_id = $1 AND s.recorded_at >= p.since), 0) AS spent,
+                    COALESCE((SELECT SUM(estimated_cost_wei) FROM tx_requests r
+                              WHERE r.tenant_id = $1 AND r.created_at >= p.since
+                                AND r.status IN ('pending', 'processing', 'submitted')), 0) AS reserved
+             FROM periods p
+         )
+         SELECT period, since, budget::text AS budget_wei, spent::text AS spent_wei,
+                reserved::text AS reserved_wei, (spent + reserved)::text AS used_wei,
+                -- GREATEST skips NULLs, so an unlimited budget needs the CASE
+                CASE WHEN budget IS NOT NULL THEN GREATEST(budget - spent - reserved, 0)::text END AS remaining_wei,
+                COALESCE(spent + reserved + $2::numeric > budget, FALSE) AS exceeded
+         FROM used
+         ORDER BY since DESC, period",
+    )
+    .bind(tenant_id)
+    .bind(estimated_cost_wei)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(usage)
+}
@@ -0,0 +1,254 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, Pool, Postgres};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Tenant of requests accepted while authentication is disabled, created by the migration.
+pub const DEFAULT_TENANT_ID: Uuid = Uuid::nil();
+
+const TENANT_COLUMNS: &str = "id, name, daily_budget_wei::text AS daily_budget_wei, \
+     monthly_budget_wei::text AS monthly_budget_wei, webhook_url, created_at";
+
+const API_KEY_COLUMNS: &str =
+    "id, tenant_id, name, prefix, key_hash, tier, created_at, last_used_at, expires_at, revoked_at";
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct Tenant {
+    pub id: Uuid,
+    pub name: String,
+    /// Most the tenant's transactions may cost per UTC day, in wei; unlimited when `None`.
+    pub daily_budget_wei: Option<String>,
+    /// Most the tenant's transactions may cost per calendar month, in wei.
+    pub monthly_budget_wei: Option<String>,
+    /// Receives status callbacks of requests that bring no `callback_url` of their own.
+    pub webhook_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A key as handed out: the plaintext itself is only known to the caller.
+#[derive(Clone, Debug)]
+pub struct NewApiKey {
+    pub tenant_id: Uuid,
+    pub name: Option<String>,
+    pub tier: Option<String>,
+    /// First characters of the key, to tell keys apart in listings.
+    pub prefix: String,
+    /// Hex SHA-256 of the key.
+    pub key_hash: String,
+}
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub name: Option<String>,
+    pub prefix: String,
+    #[serde(skip)]
+    pub key_hash: String,
+    /// Rate limit tier; the default tier when `None`.
+    pub tier: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Tenants and their API keys in `tenants` and `api_keys`.
+#[derive(Clone, Debug)]
+pub struct TenantRepository {
+    pool: Pool<Postgres>,
+}
+
+impl TenantRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Creates a tenant, or returns `None` if the name is taken.
+    pub async fn create_tenant(&self, name: &str) -> anyhow::Result<Option<Tenant>> {
+        let tenant = sqlx::query_as::<_, Tenant>(&format!(
+            "INSERT INTO tenants (name) VALUES ($1)
+             ON CONFLICT (name) DO NOTHING
+             RETURNING {TENANT_COLUMNS}"
+        ))
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(tenant)
+    }
+
+    pub async fn list_tenants(&self) -> anyhow::Result<Vec<Tenant>> {
+        let tenants = sqlx::query_as::<_, Tenant>(&format!(
+            "SELECT {TENANT_COLUMNS} FROM tenants ORDER BY created_at"
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(tenants)
+    }
+
+    pub async fn find_tenant(&self, id: Uuid) -> anyhow::Result<Option<Tenant>> {
+        let tenant = sqlx::query_as::<_, Tenant>(&format!(
+            "SELECT {TENANT_COLUMNS} FROM tenants WHERE id = $1"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(tenant)
+    }
+
+    /// Replaces both budgets of a tenant; `None` lifts a limit. Returns `None` if the
+    /// tenant is unknown.
+    pub async fn set_budget(
+        &self,
+        id: Uuid,
+        daily_wei: Option<&str>,
+        monthly_wei: Option<&str>,
+    ) -> anyhow::Result<Option<Tenant>> {
+        let tenant = sqlx::query_as::<_, Tenant>(&format!(
+            "UPDATE tenants SET daily_budget_wei = $2::numeric, monthly_budget_wei = $3::numeric
+             WHERE id = $1
+             RETURNING {TENANT_COLUMNS}"
+        ))
+        .bind(id)
+        .bind(daily_wei)
+        .bind(monthly_wei)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(tenant)
+    }
+
+    /// Sets the tenant's default callback URL (`None` removes it) and the secret its
+    /// callbacks are signed with. Returns `None` if the tenant is unknown.
+    pub async fn set_webhook(
+        &self,
+        id: Uuid,
+        url: Option<&str>,
+        secret: &str,
+    ) -> anyhow::Result<Option<Tenant>> {
+        let tenant = sqlx::query_as::<_, Tenant>(&format!(
+            "UPDATE tenants SET webhook_url = $2, webhook_secret = $3
+             WHERE id = $1
+             RETURNING {TENANT_COLUMNS}"
+        ))
+        .bind(id)
+        .bind(url)
+        .bind(secret)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(tenant)
+    }
+
+    pub async fn create_key(&self, key: &NewApiKey) -> anyhow::Result<ApiKey> {
+        let key = sqlx::query_as::<_, ApiKey>(&format!(
+            "INSERT INTO api_keys (tenant_id, name, tier, prefix, key_hash)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING {API_KEY_COLUMNS}"
+        ))
+        .bind(key.tenant_id)
+        .bind(&key.name)
+        .bind(&key.tier)
+        .bind(&key.prefix)
+        .bind(&key.key_hash)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(key)
+    }
+
+    /// Every key of a tenant, including revoked ones, newest first.
+    pub async fn list_keys(&self, tenant_id: Uuid) -> anyhow::Result<Vec<ApiKey>> {
+        let keys = sqlx::query_as::<_, ApiKey>(&format!(
+            "SELECT {API_KEY_COLUMNS} FROM api_keys WHERE tenant_id = $1 ORDER BY created_at DESC"
+        ))
+        .bind(tenant_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(keys)
+    }
+
+    /// The usable key with this hash: not revoked and not past its rotation grace period.
+    /// `last_used_at` is refreshed at most once a minute.
+    pub async fn authenticate(&self, key_hash: &str) -> anyhow::Result<Option<ApiKey>> {
+        let key = sqlx::query_as::<_, ApiKey>(&format!(
+            "WITH found AS (
+                 SELECT {API_KEY_COLUMNS} FROM api_keys
+                 WHERE key_hash = $1
+                   AND revoked_at IS NULL
+                   AND (expires_at IS NULL OR expires_at > NOW())
+             ), touched AS (
+                 UPDATE api_keys SET last_used_at = NOW()
+                 WHERE id IN (SELECT id FROM found)
+                   AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL '1 minute')
+             )
+             SELECT {API_KEY_COLUMNS} FROM found"
+        ))
+        .bind(key_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(key)
+    }
+
+    /// Replaces a usable key with `replacement`, keeping its tenant, name and tier. The old
+    /// key keeps working for `grace`. Returns `None` if the key is unknown or revoked.
+    pub async fn rotate_key(
+        &self,
+        id: Uuid,
+        prefix: &str,
+        key_hash: &str,
+        grace: Duration,
+    ) -> anyhow::Result<Option<ApiKey>> {
+        let mut tx = self.pool.begin().await?;
+
+        let old = sqlx::query_as::<_, ApiKey>(&format!(
+            "UPDATE api_keys
+             SET expires_at = LEAST(COALESCE(expires_at, 'infinity'), NOW() + make_interval(secs => $2))
+             WHERE id = $1 AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > NOW())
+             RETURNING {API_KEY_COLUMNS}"
+        ))
+        .bind(id)
+        .bind(grace.as_secs_f64())
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(old) = old else {
+            return Ok(None);
+        };
+
+        let new = sqlx::query_as::<_, ApiKey>(&format!(
+            "INSERT INTO api_keys (tenant_id, name, tier, prefix, key_hash)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING {API_KEY_COLUMNS}"
+        ))
+        .bind(old.tenant_id)
+        .bind(&old.name)
+        .bind(&old.tier)
+        .bind(prefix)
+        .bind(key_hash)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(new))
+    }
+
+    /// Revokes a key at once. Returns `None` if it is unknown or already revoked.
+    pub async fn revoke_key(&self, id: Uuid) -> anyhow::Result<Option<ApiKey>> {
+        let key = sqlx::query_as::<_, ApiKey>(&format!(
+            "UPDATE api_keys SET revoked_at = NOW()
+             WHERE id = $1 AND revoked_at IS NULL
+             RETURNING {API_KEY_COLUMNS}"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(key)
+    }
+}
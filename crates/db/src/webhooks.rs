@@ -0,0 +1,201 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, Pool, Postgres};
+use std::time::Duration;
+use uuid::Uuid;
+
+const DELIVERY_COLUMNS: &str = "id, tenant_id, tx_request_id, url, event_type, payload, status, attempts, \
+     next_attempt_at, last_status_code, last_error, created_at, delivered_at, updated_at";
+
+/// Where a webhook event stands. `Dead` deliveries ran out of attempts and wait for a
+/// manual redelivery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Dead,
+}
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct WebhookDelivery {
+    /// Also the event id the receiver sees.
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub tx_request_id: Uuid,
+    pub url: String,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    #[sqlx(try_from = "String")]
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A delivery claimed for sending: the exact body to POST and the tenant's signing secret.
+#[derive(Clone, Debug, FromRow)]
+pub struct DueDelivery {
+    pub id: Uuid,
+    pub url: String,
+    pub body: String,
+    /// Including the one about to be made.
+    pub attempts: i32,
+    pub secret: Option<String>,
+}
+
+/// The outbox of relay status callbacks in `webhook_deliveries`. Events are added by
+/// `TxRequestRepository::transition_status`.
+#[derive(Clone, Debug)]
+pub struct WebhookRepository {
+    pool: Pool<Postgres>,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Dead => "dead",
+        }
+    }
+}
+
+impl TryFrom<String> for DeliveryStatus {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "pending" => Ok(DeliveryStatus::Pending),
+            "delivered" => Ok(DeliveryStatus::Delivered),
+            "dead" => Ok(DeliveryStatus::Dead),
+            _ => Err(format!("Invalid webhook delivery status: {value}")),
+        }
+    }
+}
+
+impl WebhookRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Claims up to `limit` due deliveries and counts the attempt. They are not due again
+    /// for `lease`, so other dispatchers leave them alone while they are sent; a dispatcher
+    /// that dies mid-send has them retried once the lease runs out.
+    pub async fn claim_due(&self, limit: i64, lease: Duration) -> anyhow::Result<Vec<DueDelivery>> {
+        let deliveries = sqlx::query_as::<_, DueDelivery>(
+            "UPDATE webhook_deliveries d
+             SET attempts = d.attempts + 1,
+                 next_attempt_at = NOW() + make_interval(secs => $2),
+                 updated_at = NOW()
+             FROM tenants t
+             WHERE t.id = d.tenant_id
+               AND d.id IN (
+                   SELECT id FROM webhook_deliveries
+                   WHERE status = 'pending' AND next_attempt_at <= NOW()
+                   ORDER BY next_attempt_at
+                   LIMIT $1
+                   FOR UPDATE SKIP LOCKED
+               )
+             RETURNING d.id, d.url, d.payload::text AS body, d.attempts, t.webhook_secret AS secret",
+        )
+        .bind(limit)
+        .bind(lease.as_secs_f64())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(deliveries)
+    }
+
+    pub async fn mark_delivered(&self, id: Uuid, status_code: i32) -> anyhow::Result<()> {
+        sqlx::query(
+            "UPDATE webhook_deliveries
+             SET status = 'delivered', last_status_code = $2, last_error = NULL,
+                 delivered_at = NOW(), updated_at = NOW()
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(status_code)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Records a failed attempt. The delivery is tried again at `retry_at`, or dead-lettered
+    /// without one.
+    pub async fn mark_failed(
+        &self,
+        id: Uuid,
+        status_code: Option<i32>,
+        error: &str,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            "UPDATE webhook_deliveries
+             SET status = CASE WHEN $4::timestamptz IS NULL THEN 'dead' ELSE 'pending' END,
+                 next_attempt_at = COALESCE($4, next_attempt_at),
+                 last_status_code = $2, last_error = $3, updated_at = NOW()
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(status_code)
+        .bind(error)
+        .bind(retry_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Deliveries of a tenant, newest first, optionally of one status or one relay request.
+    pub async fn list(
+        &self,
+        tenant_id: Uuid,
+        status: Option<DeliveryStatus>,
+        tx_request_id: Option<Uuid>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<WebhookDelivery>> {
+        let deliveries = sqlx::query_as::<_, WebhookDelivery>(&format!(
+            "SELECT {DELIVERY_COLUMNS} FROM webhook_deliveries
+             WHERE tenant_id = $1
+               AND ($2::text IS NULL OR status = $2)
+               AND ($3::uuid IS NULL OR tx_request_id = $3)
+             ORDER BY created_at DESC, id DESC
+             LIMIT $4"
+        ))
+        .bind(tenant_id)
+        .bind(status.map(|s| s.as_str()))
+        .bind(tx_request_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(deliveries)
+    }
+
+    /// Sends a delivered or dead event again, with a fresh set of attempts. Returns `None`
+    /// if the tenant has no such delivery or it is still pending.
+    pub async fn redeliver(
+        &self,
+        id: Uuid,
+        tenant_id: Uuid,
+    ) -> anyhow::Result<Option<WebhookDelivery>> {
+        let delivery = sqlx::query_as::<_, WebhookDelivery>(&format!(
+            "UPDATE webhook_deliveries
+             SET status = 'pending', attempts = 0, next_attempt_at = NOW(), updated_at = NOW()
+             WHERE id = $1 AND tenant_id = $2 AND status <> 'pending'
+             RETURNING {DELIVERY_COLUMNS}"
+        ))
+        .bind(id)
+        .bind(tenant_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(delivery)
+    }
+}
@@ -0,0 +1,52 @@
+//! Integration tests for `AlertRepository`; see `tx_request_repository.rs` for the Postgres setup.
+
+use db::alerts::{AlertRepository, AlertSeverity, NewAlert};
+use sqlx::PgPool;
+
+fn new_alert(name: &str) -> NewAlert {
+    NewAlert {
+        alert_name: name.to_string(),
+        severity: AlertSeverity::Critical,
+        message: "gas_relayer_balance_eth is 0.05, below 0.1".to_string(),
+        details: Some(serde_json::json!({ "value": 0.05 })),
+    }
+}
+
+#[sqlx::test]
+async fn deduplicates_open_alerts(pool: PgPool) -> anyhow::Result<()> {
+    let alerts = AlertRepository::new(pool);
+
+    let fired = alerts
+        .fire(&new_alert("relayer_balance_low"))
+        .await?
+        .expect("first alert fires");
+    assert_eq!(fired.severity, AlertSeverity::Critical);
+    assert!(!fired.is_resolved);
+    assert!(
+        alerts
+            .fire(&new_alert("relayer_balance_low"))
+            .await?
+            .is_none()
+    );
+    assert!(alerts.fire(&new_alert("queue_backlog")).await?.is_some());
+    assert_eq!(alerts.list_open().await?.len(), 2);
+
+    let resolved = alerts
+        .resolve(fired.id)
+        .await?
+        .expect("open alert resolves");
+    assert!(resolved.is_resolved);
+    assert!(resolved.resolved_at.is_some());
+    assert!(alerts.resolve(fired.id).await?.is_none());
+
+    // Once resolved, the same rule can fire again.
+    assert!(
+        alerts
+            .fire(&new_alert("relayer_balance_low"))
+            .await?
+            .is_some()
+    );
+    assert_eq!(alerts.list(Some(true), 10).await?.len(), 1);
+    assert_eq!(alerts.list(None, 10).await?.len(), 3);
+    Ok(())
+}
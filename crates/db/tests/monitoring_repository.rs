@@ -0,0 +1,123 @@
+//! Integration tests for `MonitoringRepository`; see `tx_request_repository.rs` for the Postgres setup.
+
+use chrono::{Duration, Utc};
+use db::monitoring::{MonitoringRepository, NewHealthCheck, NewMetricSnapshot, NewQueueStats};
+use sqlx::PgPool;
+
+fn health_check(component: &str, status: &str) -> NewHealthCheck {
+    NewHealthCheck {
+        component: component.to_string(),
+        status: status.to_string(),
+        response_time_ms: Some(12),
+        details: Some(serde_json::json!({ "pool_size": 3 })),
+    }
+}
+
+#[sqlx::test]
+async fn reads_back_health_history_by_component(pool: PgPool) -> anyhow::Result<()> {
+    let monitoring = MonitoringRepository::new(pool);
+    monitoring
+        .record_health_checks(&[
+            health_check("database", "healthy"),
+            health_check("queue", "degraded"),
+        ])
+        .await?;
+    monitoring
+        .record_health_checks(&[health_check("database", "unhealthy")])
+        .await?;
+
+    let database = monitoring
+        .health_history(Some("database"), None, 10)
+        .await?;
+    assert_eq!(database.len(), 2);
+    assert_eq!(database[0].status, "unhealthy");
+    assert_eq!(
+        database[0].details,
+        Some(serde_json::json!({ "pool_size": 3 }))
+    );
+
+    assert_eq!(monitoring.health_history(None, None, 10).await?.len(), 3);
+    assert!(
+        monitoring
+            .health_history(None, Some(Utc::now() + Duration::minutes(1)), 10)
+            .await?
+            .is_empty()
+    );
+    Ok(())
+}
+
+#[sqlx::test]
+async fn reads_back_metric_history_since(pool: PgPool) -> anyhow::Result<()> {
+    let monitoring = MonitoringRepository::new(pool.clone());
+    monitoring
+        .record_metric_snapshots(&[
+            NewMetricSnapshot {
+                metric_name: "gas_relayer_queue_depth".to_string(),
+                metric_value: 7.0,
+                labels: None,
+            },
+            NewMetricSnapshot {
+                metric_name: "http_requests_total".to_string(),
+                metric_value: 1.5,
+                labels: Some(serde_json::json!({ "route": "/relay" })),
+            },
+        ])
+        .await?;
+    sqlx::query(
+        "UPDATE metrics_snapshots SET recorded_at = NOW() - INTERVAL '2 hours'
+         WHERE metric_name = 'gas_relayer_queue_depth'",
+    )
+    .execute(&pool)
+    .await?;
+
+    let depth = monitoring
+        .metric_history("gas_relayer_queue_depth", None, 10)
+        .await?;
+    assert_eq!(depth.len(), 1);
+    assert_eq!(depth[0].metric_value, 7.0);
+
+    let since = Utc::now() - Duration::hours(1);
+    assert!(
+        monitoring
+            .metric_history("gas_relayer_queue_depth", Some(since), 10)
+            .await?
+            .is_empty()
+    );
+    let http = monitoring
+        .metric_history("http_requests_total", Some(since), 10)
+        .await?;
+    assert_eq!(
+        http[0].labels,
+        Some(serde_json::json!({ "route": "/relay" }))
+    );
+    Ok(())
+}
+
+#[sqlx::test]
+async fn prunes_rows_past_retention(pool: PgPool) -> anyhow::Result<()> {
+    let monitoring = MonitoringRepository::new(pool.clone());
+    monitoring
+        .record_health_checks(&[health_check("database", "healthy")])
+        .await?;
+    monitoring
+        .record_queue_stats(&NewQueueStats {
+            queue_name: "default".to_string(),
+            depth: 4,
+            processing_rate: Some(0.5),
+            avg_wait_time_ms: None,
+        })
+        .await?;
+    sqlx::query("UPDATE health_checks SET checked_at = NOW() - INTERVAL '8 days'")
+        .execute(&pool)
+        .await?;
+
+    let removed = monitoring.prune(Utc::now() - Duration::days(7)).await?;
+    assert_eq!(removed, 1);
+    assert!(monitoring.health_history(None, None, 10).await?.is_empty());
+
+    let queue_rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM queue_stats")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(queue_rows, 1);
+    Ok(())
+}
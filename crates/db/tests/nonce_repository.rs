@@ -0,0 +1,105 @@
+//! Integration tests for `NonceRepository`; see `tx_request_repository.rs` for the Postgres setup.
+
+use db::nonces::{NonceRepository, NonceState};
+use sqlx::PgPool;
+use std::collections::HashSet;
+
+const CHAIN_ID: i64 = 11155111;
+const RELAYER: &str = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+
+#[sqlx::test]
+async fn reserves_sequential_nonces_after_sync(pool: PgPool) -> anyhow::Result<()> {
+    let nonces = NonceRepository::new(pool);
+    assert!(nonces.reserve(CHAIN_ID, RELAYER, None).await.is_err());
+
+    assert_eq!(nonces.sync(CHAIN_ID, RELAYER, 42).await?, 42);
+    assert_eq!(nonces.reserve(CHAIN_ID, RELAYER, None).await?, 42);
+    assert_eq!(nonces.reserve(CHAIN_ID, RELAYER, None).await?, 43);
+
+    // The chain moving ahead (e.g. a manual transaction) pushes the counter forward,
+    // while a chain behind the counter never moves it back.
+    assert_eq!(nonces.sync(CHAIN_ID, RELAYER, 50).await?, 50);
+    assert_eq!(nonces.sync(CHAIN_ID, RELAYER, 45).await?, 50);
+    Ok(())
+}
+
+#[sqlx::test]
+async fn concurrent_reservations_never_collide(pool: PgPool) -> anyhow::Result<()> {
+    let nonces = NonceRepository::new(pool);
+    nonces.sync(CHAIN_ID, RELAYER, 0).await?;
+
+    let handles: Vec<_> = (0..20)
+        .map(|_| {
+            let nonces = nonces.clone();
+            tokio::spawn(async move { nonces.reserve(CHAIN_ID, RELAYER, None).await })
+        })
+        .collect();
+
+    let mut reserved = HashSet::new();
+    for handle in handles {
+        assert!(reserved.insert(handle.await??));
+    }
+    assert_eq!(reserved, (0..20).collect());
+    Ok(())
+}
+
+#[sqlx::test]
+async fn released_nonces_are_reused_first(pool: PgPool) -> anyhow::Result<()> {
+    let nonces = NonceRepository::new(pool);
+    nonces.sync(CHAIN_ID, RELAYER, 10).await?;
+    for _ in 0..3 {
+        nonces.reserve(CHAIN_ID, RELAYER, None).await?;
+    }
+
+    nonces
+        .set_state(CHAIN_ID, RELAYER, 11, NonceState::Released)
+        .await?;
+    assert_eq!(nonces.reserve(CHAIN_ID, RELAYER, None).await?, 11);
+    assert_eq!(nonces.reserve(CHAIN_ID, RELAYER, None).await?, 13);
+    Ok(())
+}
+
+#[sqlx::test]
+async fn finds_and_claims_gaps(pool: PgPool) -> anyhow::Result<()> {
+    let nonces = NonceRepository::new(pool);
+    nonces.sync(CHAIN_ID, RELAYER, 0).await?;
+    for _ in 0..4 {
+        nonces.reserve(CHAIN_ID, RELAYER, None).await?;
+    }
+    nonces
+        .set_state(CHAIN_ID, RELAYER, 0, NonceState::Broadcast)
+        .await?;
+    nonces
+        .set_state(CHAIN_ID, RELAYER, 1, NonceState::Released)
+        .await?;
+    nonces
+        .set_state(CHAIN_ID, RELAYER, 3, NonceState::Broadcast)
+        .await?;
+
+    // Nonce 2 is still freshly reserved, so only the released nonce is a gap.
+    assert_eq!(nonces.find_gaps(CHAIN_ID, RELAYER, 0, 60.0).await?, vec![1]);
+    // With a zero staleness window the in-flight reservation counts as abandoned too.
+    assert_eq!(
+        nonces.find_gaps(CHAIN_ID, RELAYER, 0, 0.0).await?,
+        vec![1, 2]
+    );
+
+    assert!(nonces.claim_gap(CHAIN_ID, RELAYER, 1, 60.0).await?);
+    assert!(!nonces.claim_gap(CHAIN_ID, RELAYER, 1, 60.0).await?);
+    assert!(
+        nonces
+            .find_gaps(CHAIN_ID, RELAYER, 0, 60.0)
+            .await?
+            .is_empty()
+    );
+
+    // Once the chain consumed everything below 4 the old reservations are dropped.
+    nonces.sync(CHAIN_ID, RELAYER, 4).await?;
+    assert!(
+        nonces
+            .find_gaps(CHAIN_ID, RELAYER, 4, 0.0)
+            .await?
+            .is_empty()
+    );
+    Ok(())
+}
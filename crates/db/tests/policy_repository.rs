@@ -0,0 +1,24 @@
+//! Integration tests for `PolicyRepository`; see `tx_request_repository.rs` for the Postgres setup.
+
+use db::policies::PolicyRepository;
+use sqlx::PgPool;
+
+#[sqlx::test]
+async fn newest_version_is_active(pool: PgPool) -> anyhow::Result<()> {
+    let policies = PolicyRepository::new(pool);
+    assert!(policies.latest().await?.is_none());
+
+    let first = policies
+        .insert(&serde_json::json!({ "max_gas": 500000 }), Some("initial"))
+        .await?;
+    let second = policies
+        .insert(&serde_json::json!({ "max_gas": 300000 }), None)
+        .await?;
+    assert!(second.id > first.id);
+
+    let active = policies.latest().await?.expect("a policy is stored");
+    assert_eq!(active.id, second.id);
+    assert_eq!(active.document["max_gas"], 300000);
+    assert_eq!(active.description, None);
+    Ok(())
+}
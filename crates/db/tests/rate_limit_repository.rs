@@ -0,0 +1,36 @@
+//! Integration tests for `RateLimitRepository`; see `tx_request_repository.rs` for the Postgres setup.
+
+use chrono::{Duration, Utc};
+use db::rate_limits::RateLimitRepository;
+use sqlx::PgPool;
+
+#[sqlx::test]
+async fn takes_tokens_until_the_bucket_is_empty(pool: PgPool) -> anyhow::Result<()> {
+    let buckets = RateLimitRepository::new(pool.clone());
+
+    // Refills one token an hour, so nothing comes back during the test
+    let per_second = 1.0 / 3600.0;
+    let first = buckets.take("ip:127.0.0.1", 2.0, per_second).await?;
+    assert!(first.allowed);
+    assert_eq!(first.tokens, 1.0);
+    let second = buckets.take("ip:127.0.0.1", 2.0, per_second).await?;
+    assert!(second.allowed);
+    assert!(second.tokens < 1.0);
+    let third = buckets.take("ip:127.0.0.1", 2.0, per_second).await?;
+    assert!(!third.allowed);
+    assert!(third.tokens >= 0.0);
+
+    // Other keys have their own bucket
+    assert!(buckets.take("ip:10.0.0.1", 2.0, per_second).await?.allowed);
+
+    // A bucket idle long enough is full again
+    sqlx::query("UPDATE rate_limit_buckets SET updated_at = NOW() - INTERVAL '2 hours' WHERE key = 'ip:127.0.0.1'")
+        .execute(&pool)
+        .await?;
+    let refilled = buckets.take("ip:127.0.0.1", 2.0, per_second).await?;
+    assert!(refilled.allowed);
+    assert!((refilled.tokens - 1.0).abs() < 1e-6);
+
+    assert_eq!(buckets.prune(Utc::now() + Duration::seconds(1)).await?, 2);
+    Ok(())
+}
@@ -0,0 +1,156 @@
+//! Integration tests for tenant budgets and `SpendRepository`; see `tx_request_repository.rs`
+//! for the Postgres setup.
+
+use chrono::{Duration, Utc};
+use db::db::{NewTxRequest, StatusUpdate, TxRequestRepository, TxStatus};
+use db::spend::{BudgetPeriod, SpendBucketSize, SpendRepository};
+use db::tenants::{DEFAULT_TENANT_ID, TenantRepository};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+fn new_request(tenant_id: Uuid, estimated_cost_wei: &str) -> NewTxRequest {
+    NewTxRequest {
+        chain_id: 11155111,
+        tenant_id,
+        from_address: "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266".to_string(),
+        to_address: "0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f".to_string(),
+        value: "0".to_string(),
+        gas: 100_000,
+        deadline: 1_900_000_000,
+        data: vec![0xd0, 0x9d, 0xe0, 0x8a],
+        signature: vec![0xab; 65],
+        estimated_cost_wei: Some(estimated_cost_wei.to_string()),
+        callback_url: None,
+    }
+}
+
+async fn settle(
+    repository: &TxRequestRepository,
+    id: Uuid,
+    gas_used: i64,
+    price: &str,
+) -> anyhow::Result<()> {
+    let submitted = StatusUpdate {
+        tx_hash: Some(format!("0x{:064x}", id.as_u128())),
+        nonce: Some(0),
+        ..Default::default()
+    };
+    assert!(
+        repository
+            .transition_status(id, TxStatus::Pending, TxStatus::Submitted, &submitted)
+            .await?
+    );
+    let confirmed = StatusUpdate {
+        gas_used: Some(gas_used),
+        effective_gas_price: Some(price.to_string()),
+        ..Default::default()
+    };
+    assert!(
+        repository
+            .transition_status(id, TxStatus::Submitted, TxStatus::Confirmed, &confirmed)
+            .await?
+    );
+    Ok(())
+}
+
+#[sqlx::test]
+async fn refuses_requests_over_budget(pool: PgPool) -> anyhow::Result<()> {
+    let tenants = TenantRepository::new(pool.clone());
+    let requests = TxRequestRepository::new(pool);
+    let tenant = tenants
+        .set_budget(DEFAULT_TENANT_ID, Some("1000"), None)
+        .await?
+        .expect("default tenant");
+    assert_eq!(tenant.daily_budget_wei.as_deref(), Some("1000"));
+    assert_eq!(tenant.monthly_budget_wei, None);
+
+    // Open requests hold their estimate against the budget
+    assert!(
+        requests
+            .insert_within_budget(&new_request(DEFAULT_TENANT_ID, "600"))
+            .await?
+            .is_ok()
+    );
+    let exceeded = requests
+        .insert_within_budget(&new_request(DEFAULT_TENANT_ID, "500"))
+        .await?
+        .expect_err("over the daily budget");
+    assert_eq!(exceeded.period, BudgetPeriod::Daily);
+    assert_eq!(exceeded.budget_wei, "1000");
+    assert_eq!(exceeded.used_wei, "600");
+    assert_eq!(exceeded.estimated_cost_wei, "500");
+    assert!(
+        requests
+            .insert_within_budget(&new_request(DEFAULT_TENANT_ID, "400"))
+            .await?
+            .is_ok()
+    );
+
+    tenants.set_budget(DEFAULT_TENANT_ID, None, None).await?;
+    assert!(
+        requests
+            .insert_within_budget(&new_request(DEFAULT_TENANT_ID, "500"))
+            .await?
+            .is_ok()
+    );
+    Ok(())
+}
+
+#[sqlx::test]
+async fn settled_transactions_are_recorded_once(pool: PgPool) -> anyhow::Result<()> {
+    let tenants = TenantRepository::new(pool.clone());
+    let requests = TxRequestRepository::new(pool.clone());
+    let spend = SpendRepository::new(pool);
+    tenants
+        .set_budget(DEFAULT_TENANT_ID, None, Some("5000000"))
+        .await?;
+
+    let id = requests
+        .insert(&new_request(DEFAULT_TENANT_ID, "3000000"))
+        .await?;
+    settle(&requests, id, 21_000, "100").await?;
+    // A second settlement of the same request is not counted again
+    assert!(
+        !requests
+            .transition_status(
+                id,
+                TxStatus::Submitted,
+                TxStatus::Confirmed,
+                &StatusUpdate::default()
+            )
+            .await?
+    );
+
+    let periods = spend.period_usage(DEFAULT_TENANT_ID).await?;
+    assert_eq!(periods.len(), 2);
+    let monthly = periods
+        .iter()
+        .find(|p| p.period == BudgetPeriod::Monthly)
+        .unwrap();
+    assert_eq!(monthly.spent_wei, "2100000");
+    assert_eq!(monthly.reserved_wei, "0");
+    assert_eq!(monthly.remaining_wei.as_deref(), Some("2900000"));
+    let daily = periods
+        .iter()
+        .find(|p| p.period == BudgetPeriod::Daily)
+        .unwrap();
+    assert_eq!(daily.budget_wei, None);
+    assert_eq!(daily.remaining_wei, None);
+
+    let now = Utc::now();
+    let buckets = spend
+        .buckets(
+            DEFAULT_TENANT_ID,
+            SpendBucketSize::Hour,
+            now - Duration::days(1),
+            now + Duration::hours(1),
+        )
+        .await?;
+    assert_eq!(buckets.len(), 1);
+    assert_eq!(buckets[0].transactions, 1);
+    assert_eq!(buckets[0].gas_used, 21_000);
+    assert_eq!(buckets[0].cost_wei, "2100000");
+
+    assert!(spend.period_usage(Uuid::from_u128(7)).await?.is_empty());
+    Ok(())
+}
@@ -0,0 +1,103 @@
+//! Integration tests for `TenantRepository`; see `tx_request_repository.rs` for the Postgres setup.
+
+use db::tenants::{DEFAULT_TENANT_ID, NewApiKey, TenantRepository};
+use sqlx::PgPool;
+use std::time::Duration;
+
+fn new_key(tenant_id: uuid::Uuid, key_hash: &str) -> NewApiKey {
+    NewApiKey {
+        tenant_id,
+        name: Some("ci".to_string()),
+        tier: Some("partner".to_string()),
+        prefix: key_hash[..8].to_string(),
+        key_hash: key_hash.to_string(),
+    }
+}
+
+#[sqlx::test]
+async fn creates_tenants_with_unique_names(pool: PgPool) -> anyhow::Result<()> {
+    let tenants = TenantRepository::new(pool);
+    let payments = tenants
+        .create_tenant("payments")
+        .await?
+        .expect("new tenant");
+    assert!(tenants.create_tenant("payments").await?.is_none());
+
+    let names: Vec<_> = tenants
+        .list_tenants()
+        .await?
+        .into_iter()
+        .map(|tenant| tenant.name)
+        .collect();
+    assert_eq!(names, vec!["default", "payments"]);
+    assert_eq!(
+        tenants.find_tenant(payments.id).await?.unwrap().name,
+        "payments"
+    );
+    assert!(tenants.find_tenant(DEFAULT_TENANT_ID).await?.is_some());
+    Ok(())
+}
+
+#[sqlx::test]
+async fn authenticates_until_revoked(pool: PgPool) -> anyhow::Result<()> {
+    let tenants = TenantRepository::new(pool);
+    let tenant = tenants.create_tenant("games").await?.unwrap();
+    let key = tenants
+        .create_key(&new_key(tenant.id, "aaaaaaaaaaaa"))
+        .await?;
+
+    let found = tenants
+        .authenticate("aaaaaaaaaaaa")
+        .await?
+        .expect("key is usable");
+    assert_eq!(found.id, key.id);
+    assert_eq!(found.tenant_id, tenant.id);
+    assert_eq!(found.tier.as_deref(), Some("partner"));
+    assert!(tenants.authenticate("bbbbbbbbbbbb").await?.is_none());
+
+    assert!(tenants.revoke_key(key.id).await?.is_some());
+    assert!(tenants.revoke_key(key.id).await?.is_none());
+    assert!(tenants.authenticate("aaaaaaaaaaaa").await?.is_none());
+    Ok(())
+}
+
+#[sqlx::test]
+async fn rotation_keeps_the_old_key_for_the_grace_period(pool: PgPool) -> anyhow::Result<()> {
+    let tenants = TenantRepository::new(pool);
+    let tenant = tenants.create_tenant("games").await?.unwrap();
+    let old = tenants
+        .create_key(&new_key(tenant.id, "aaaaaaaaaaaa"))
+        .await?;
+
+    let new = tenants
+        .rotate_key(
+            old.id,
+            "bbbbbbbb",
+            "bbbbbbbbbbbb",
+            Duration::from_secs(3600),
+        )
+        .await?
+        .expect("key is usable");
+    assert_eq!(new.tenant_id, tenant.id);
+    assert_eq!(new.name.as_deref(), Some("ci"));
+    assert_eq!(new.tier.as_deref(), Some("partner"));
+    assert!(tenants.authenticate("aaaaaaaaaaaa").await?.is_some());
+    assert!(tenants.authenticate("bbbbbbbbbbbb").await?.is_some());
+
+    // Without a grace period the old key stops working at once, and cannot be rotated again
+    tenants
+        .rotate_key(new.id, "cccccccc", "cccccccccccc", Duration::ZERO)
+        .await?
+        .expect("key is usable");
+    assert!(tenants.authenticate("bbbbbbbbbbbb").await?.is_none());
+    assert!(
+        tenants
+            .rotate_key(new.id, "dddddddd", "dddddddddddd", Duration::ZERO)
+            .await?
+            .is_none()
+    );
+
+    let keys = tenants.list_keys(tenant.id).await?;
+    assert_eq!(keys.len(), 3);
+    Ok(())
+}
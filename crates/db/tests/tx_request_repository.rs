@@ -0,0 +1,578 @@
+//! Integration tests for `TxRequestRepository`.
+//! They need a local Postgres reachable through `DATABASE_URL`; `sqlx::test`
+//! creates a throwaway database per test and applies `./migrations` to it.
+
+use db::db::{
+    Cursor, NewTxAttempt, NewTxRequest, Page, StatusUpdate, TxRequestFilter, TxRequestRepository,
+    TxStatus,
+};
+use db::tenants::DEFAULT_TENANT_ID;
+use sqlx::PgPool;
+
+fn new_request(from_address: &str) -> NewTxRequest {
+    NewTxRequest {
+        chain_id: 11155111,
+        tenant_id: DEFAULT_TENANT_ID,
+        from_address: from_address.to_string(),
+        to_address: "0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f".to_string(),
+        value: "1000000000000000000".to_string(),
+        gas: 100_000,
+        deadline: 1_900_000_000,
+        data: vec![0xd0, 0x9d, 0xe0, 0x8a],
+        signature: vec![0xab; 65],
+        estimated_cost_wei: None,
+        callback_url: None,
+    }
+}
+
+#[sqlx::test]
+async fn insert_and_find_by_id(pool: PgPool) -> anyhow::Result<()> {
+    let repository = TxRequestRepository::new(pool);
+    let id = repository
+        .insert(&new_request("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"))
+        .await?;
+
+    let stored = repository.find_by_id(id).await?.expect("request exists");
+    assert_eq!(stored.status, TxStatus::Pending);
+    assert_eq!(stored.chain_id, 11155111);
+    assert_eq!(stored.tenant_id, DEFAULT_TENANT_ID);
+    assert_eq!(stored.value, "1000000000000000000");
+    assert_eq!(stored.data, vec![0xd0, 0x9d, 0xe0, 0x8a]);
+    assert!(stored.tx_hash.is_none());
+
+    assert!(repository.find_by_id(uuid::Uuid::nil()).await?.is_none());
+    Ok(())
+}
+
+#[sqlx::test]
+async fn transitions_status_and_finds_by_hash(pool: PgPool) -> anyhow::Result<()> {
+    let repository = TxRequestRepository::new(pool);
+    let id = repository
+        .insert(&new_request("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"))
+        .await?;
+    let tx_hash = "0x15d8ff9b27d7cc1f2acbe5f65197456a9dd37d4ed4198ddbd138e828d1cd10a0";
+
+    let submitted = StatusUpdate {
+        tx_hash: Some(tx_hash.to_string()),
+        nonce: Some(7),
+        ..Default::default()
+    };
+    assert!(
+        repository
+            .transition_status(id, TxStatus::Pending, TxStatus::Submitted, &submitted)
+            .await?
+    );
+
+    // A second worker racing on the same row loses the compare-and-set.
+    assert!(
+        !repository
+            .transition_status(id, TxStatus::Pending, TxStatus::Submitted, &submitted)
+            .await?
+    );
+
+    let confirmed = StatusUpdate {
+        gas_used: Some(43_512),
+        ..Default::default()
+    };
+    assert!(
+        repository
+            .transition_status(id, TxStatus::Submitted, TxStatus::Confirmed, &confirmed)
+            .await?
+    );
+
+    let by_hash = repository
+        .find_by_tx_hash(&tx_hash.to_uppercase().replace("0X", "0x"))
+        .await?;
+    assert_eq!(by_hash.len(), 1);
+    assert_eq!(by_hash[0].id, id);
+    assert_eq!(by_hash[0].status, TxStatus::Confirmed);
+    assert_eq!(by_hash[0].nonce, Some(7));
+    assert_eq!(by_hash[0].gas_used, Some(43_512));
+    Ok(())
+}
+
+#[sqlx::test]
+async fn rejects_invalid_transition(pool: PgPool) -> anyhow::Result<()> {
+    let repository = TxRequestRepository::new(pool);
+    let id = repository
+        .insert(&new_request("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"))
+        .await?;
+
+    let result = repository
+        .transition_status(
+            id,
+            TxStatus::Pending,
+            TxStatus::Confirmed,
+            &StatusUpdate::default(),
+        )
+        .await;
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[sqlx::test]
+async fn lists_pages_newest_first(pool: PgPool) -> anyhow::Result<()> {
+    let repository = TxRequestRepository::new(pool);
+    let mut ids = Vec::new();
+    for _ in 0..5 {
+        ids.push(
+            repository
+                .insert(&new_request("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"))
+                .await?,
+        );
+    }
+    repository
+        .transition_status(
+            ids[0],
+            TxStatus::Pending,
+            TxStatus::Failed,
+            &StatusUpdate {
+                error: Some("deadline expired".to_string()),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    let first = repository
+        .list(
+            None,
+            Page {
+                limit: 2,
+                offset: 0,
+            },
+        )
+        .await?;
+    let second = repository
+        .list(
+            None,
+            Page {
+                limit: 2,
+                offset: 2,
+            },
+        )
+        .await?;
+    assert_eq!(first.len(), 2);
+    assert_eq!(second.len(), 2);
+    assert!(first[1].created_at >= second[0].created_at);
+    assert!(first.iter().all(|a| second.iter().all(|b| a.id != b.id)));
+
+    let failed = repository
+        .list(
+            Some(TxStatus::Failed),
+            Page {
+                limit: 10,
+                offset: 0,
+            },
+        )
+        .await?;
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].id, ids[0]);
+    assert_eq!(failed[0].error.as_deref(), Some("deadline expired"));
+    Ok(())
+}
+
+#[sqlx::test]
+async fn concurrent_claims_are_disjoint(pool: PgPool) -> anyhow::Result<()> {
+    let repository = TxRequestRepository::new(pool);
+    for _ in 0..6 {
+        repository
+            .insert(&new_request("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"))
+            .await?;
+    }
+
+    let (first, second) = tokio::join!(
+        repository.claim_pending(11155111, 4),
+        repository.claim_pending(11155111, 4)
+    );
+    let (first, second) = (first?, second?);
+    assert_eq!(first.len() + second.len(), 6);
+    assert!(first.iter().all(|a| second.iter().all(|b| a.id != b.id)));
+    assert!(first.iter().all(|r| r.status == TxStatus::Processing));
+    assert!(repository.claim_pending(11155111, 4).await?.is_empty());
+    assert!(repository.claim_pending(1, 4).await?.is_empty());
+    Ok(())
+}
+
+#[sqlx::test]
+async fn requeues_claimed_requests(pool: PgPool) -> anyhow::Result<()> {
+    let repository = TxRequestRepository::new(pool);
+    let id = repository
+        .insert(&new_request("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"))
+        .await?;
+    repository.claim_pending(11155111, 1).await?;
+
+    assert!(repository.requeue(id, "connection reset").await?);
+    let stored = repository.find_by_id(id).await?.expect("request exists");
+    assert_eq!(stored.status, TxStatus::Pending);
+    assert_eq!(stored.retry_count, 1);
+    assert_eq!(stored.error.as_deref(), Some("connection reset"));
+    assert_eq!(
+        repository
+            .count_by_status(11155111, TxStatus::Pending)
+            .await?,
+        1
+    );
+
+    // A worker that died mid-flight leaves the row in processing until it goes stale.
+    repository.claim_pending(11155111, 1).await?;
+    assert_eq!(
+        repository.requeue_stale_processing(11155111, 60.0).await?,
+        0
+    );
+    assert_eq!(repository.requeue_stale_processing(11155111, 0.0).await?, 1);
+    Ok(())
+}
+
+#[sqlx::test]
+async fn keeps_broadcast_stale_requests_out_of_the_queue(pool: PgPool) -> anyhow::Result<()> {
+    let repository = TxRequestRepository::new(pool);
+    let broadcast = repository
+        .insert(&new_request("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"))
+        .await?;
+    let unsent = repository
+        .insert(&new_request("0x70997970C51812dc3A010C7d01b50e0d17dc79C8"))
+        .await?;
+    repository.claim_pending(11155111, 2).await?;
+    let attempt = |id, tx_hash: &str| NewTxAttempt {
+        tx_request_id: id,
+        tx_hash: tx_hash.to_string(),
+        nonce: 7,
+        to_address: "0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f".to_string(),
+        value: "0".to_string(),
+        input: vec![0xdf, 0x90, 0x5c, 0xaf],
+        gas_limit: 201_587,
+        max_fee_per_gas: "30000000000".to_string(),
+        max_priority_fee_per_gas: "1000000000".to_string(),
+    };
+    repository
+        .record_attempt(&attempt(broadcast, "0x0a"))
+        .await?;
+    // A transaction the node refused leaves no attempt behind
+    repository.record_attempt(&attempt(unsent, "0x0B")).await?;
+    assert_eq!(repository.delete_attempts("0x0b").await?, 1);
+
+    let stale = repository.stale_processing(11155111, 0.0).await?;
+    assert_eq!(stale.len(), 2);
+    assert!(
+        repository
+            .stale_processing(11155111, 60.0)
+            .await?
+            .is_empty()
+    );
+
+    // Only the request that never went out is queued again
+    assert_eq!(repository.requeue_stale_processing(11155111, 0.0).await?, 1);
+    let stored = repository
+        .find_by_id(broadcast)
+        .await?
+        .expect("request exists");
+    assert_eq!(stored.status, TxStatus::Processing);
+    let stored = repository
+        .find_by_id(unsent)
+        .await?
+        .expect("request exists");
+    assert_eq!(stored.status, TxStatus::Pending);
+    Ok(())
+}
+
+#[sqlx::test]
+async fn claims_batches_whole_and_apart(pool: PgPool) -> anyhow::Result<()> {
+    let repository = TxRequestRepository::new(pool);
+    let single = repository
+        .insert(&new_request("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"))
+        .await?;
+    let (batch_id, ids) = repository
+        .insert_batch_within_budget(&[
+            new_request("0x70997970C51812dc3A010C7d01b50e0d17dc79C8"),
+            new_request("0x3C44CdDdB6a900fa2b585dd299e03d12FA4293BC"),
+        ])
+        .await?
+        .expect("no budget is set");
+    assert_eq!(ids.len(), 2);
+
+    // Batched requests are only ever claimed together, in batch order
+    let claimed = repository.claim_pending(11155111, 10).await?;
+    assert_eq!(claimed.iter().map(|r| r.id).collect::<Vec<_>>(), [single]);
+    let claimed = repository.claim_pending_batch(11155111).await?;
+    assert_eq!(claimed.iter().map(|r| r.id).collect::<Vec<_>>(), ids);
+    assert!(
+        claimed
+            .iter()
+            .all(|r| r.status == TxStatus::Processing && r.batch_id == Some(batch_id))
+    );
+    assert_eq!(claimed[1].batch_index, Some(1));
+    assert!(repository.claim_pending_batch(11155111).await?.is_empty());
+
+    // Batches the worker forms are recorded once they are sent
+    let formed = repository
+        .create_batch(
+            11155111,
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+            &[single],
+        )
+        .await?;
+    let members = repository.batch_members(formed).await?;
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].batch_index, Some(0));
+    assert_eq!(repository.batch_members(batch_id).await?.len(), 2);
+    Ok(())
+}
+
+#[sqlx::test]
+async fn claims_the_next_batch_while_one_is_locked(pool: PgPool) -> anyhow::Result<()> {
+    let repository = TxRequestRepository::new(pool.clone());
+    let mut batches = Vec::new();
+    for _ in 0..2 {
+        let (batch_id, _) = repository
+            .insert_batch_within_budget(&[
+                new_request("0x70997970C51812dc3A010C7d01b50e0d17dc79C8"),
+                new_request("0x3C44CdDdB6a900fa2b585dd299e03d12FA4293BC"),
+            ])
+            .await?
+            .expect("no budget is set");
+        batches.push(batch_id);
+    }
+
+    // Another worker holds the oldest batch
+    let mut other = pool.begin().await?;
+    sqlx::query("SELECT id FROM tx_batches WHERE id = $1 FOR UPDATE")
+        .bind(batches[0])
+        .execute(&mut *other)
+        .await?;
+
+    let claimed = repository.claim_pending_batch(11155111).await?;
+    assert_eq!(claimed.len(), 2);
+    assert!(claimed.iter().all(|r| r.batch_id == Some(batches[1])));
+    assert!(repository.claim_pending_batch(11155111).await?.is_empty());
+
+    other.rollback().await?;
+    let claimed = repository.claim_pending_batch(11155111).await?;
+    assert!(claimed.iter().all(|r| r.batch_id == Some(batches[0])));
+    Ok(())
+}
+
+#[sqlx::test]
+async fn records_receipts_of_submitted_requests(pool: PgPool) -> anyhow::Result<()> {
+    let repository = TxRequestRepository::new(pool);
+    let id = repository
+        .insert(&new_request("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"))
+        .await?;
+    repository
+        .insert(&new_request("0x70997970C51812dc3A010C7d01b50e0d17dc79C8"))
+        .await?;
+    repository
+        .transition_status(
+            id,
+            TxStatus::Pending,
+            TxStatus::Submitted,
+            &StatusUpdate {
+                tx_hash: Some("0x01".to_string()),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    let submitted = repository.list_submitted(11155111, 10).await?;
+    assert_eq!(submitted.len(), 1);
+    assert_eq!(submitted[0].id, id);
+
+    let receipt = StatusUpdate {
+        gas_used: Some(52_000),
+        effective_gas_price: Some("340282366920938463463374607431768211455".to_string()),
+        block_number: Some(6_000_000),
+        ..Default::default()
+    };
+    repository
+        .transition_status(id, TxStatus::Submitted, TxStatus::Confirmed, &receipt)
+        .await?;
+
+    let stored = repository.find_by_id(id).await?.expect("request exists");
+    assert_eq!(stored.gas_used, Some(52_000));
+    assert_eq!(
+        stored.effective_gas_price.as_deref(),
+        Some("340282366920938463463374607431768211455")
+    );
+    assert_eq!(stored.block_number, Some(6_000_000));
+    assert!(repository.list_submitted(11155111, 10).await?.is_empty());
+    Ok(())
+}
+
+#[sqlx::test]
+async fn rotates_through_submitted_requests(pool: PgPool) -> anyhow::Result<()> {
+    let repository = TxRequestRepository::new(pool);
+    let mut ids = Vec::new();
+    for hash in ["0x01", "0x02", "0x03"] {
+        let id = repository
+            .insert(&new_request("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"))
+            .await?;
+        repository
+            .transition_status(
+                id,
+                TxStatus::Pending,
+                TxStatus::Submitted,
+                &StatusUpdate {
+                    tx_hash: Some(hash.to_string()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        ids.push(id);
+    }
+
+    // Whatever stays unmined goes to the back, so every request gets its turn
+    let first = repository.list_submitted(11155111, 2).await?;
+    let second = repository.list_submitted(11155111, 2).await?;
+    assert_eq!(first.len(), 2);
+    let missed = ids
+        .iter()
+        .find(|id| first.iter().all(|request| request.id != **id))
+        .unwrap();
+    assert!(second.iter().any(|request| request.id == *missed));
+    Ok(())
+}
+
+#[sqlx::test]
+async fn counts_requests_stuck_since_their_first_broadcast(pool: PgPool) -> anyhow::Result<()> {
+    let repository = TxRequestRepository::new(pool.clone());
+    let id = repository
+        .insert(&new_request("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"))
+        .await?;
+    repository
+        .transition_status(
+            id,
+            TxStatus::Pending,
+            TxStatus::Submitted,
+            &StatusUpdate {
+                tx_hash: Some("0x0a".to_string()),
+                nonce: Some(7),
+                ..Default::default()
+            },
+        )
+        .await?;
+    repository
+        .record_attempt(&NewTxAttempt {
+            tx_request_id: id,
+            tx_hash: "0x0a".to_string(),
+            nonce: 7,
+            to_address: "0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f".to_string(),
+            value: "0".to_string(),
+            input: vec![0xdf, 0x90, 0x5c, 0xaf],
+            gas_limit: 201_587,
+            max_fee_per_gas: "30000000000".to_string(),
+            max_priority_fee_per_gas: "1000000000".to_string(),
+        })
+        .await?;
+    assert_eq!(repository.count_stuck(11155111, 3600.0).await?, 0);
+
+    sqlx::query("UPDATE tx_attempts SET created_at = NOW() - INTERVAL '2 hours'")
+        .execute(&pool)
+        .await?;
+    assert_eq!(repository.count_stuck(11155111, 3600.0).await?, 1);
+    assert_eq!(repository.count_stuck(10, 3600.0).await?, 0);
+    Ok(())
+}
+
+#[sqlx::test]
+async fn records_fee_bumped_attempts(pool: PgPool) -> anyhow::Result<()> {
+    let repository = TxRequestRepository::new(pool);
+    let id = repository
+        .insert(&new_request("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"))
+        .await?;
+    let attempt = |tx_hash: &str, max_fee: &str, priority_fee: &str| NewTxAttempt {
+        tx_request_id: id,
+        tx_hash: tx_hash.to_string(),
+        nonce: 7,
+        to_address: "0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f".to_string(),
+        value: "0".to_string(),
+        input: vec![0xdf, 0x90, 0x5c, 0xaf],
+        gas_limit: 201_587,
+        max_fee_per_gas: max_fee.to_string(),
+        max_priority_fee_per_gas: priority_fee.to_string(),
+    };
+
+    repository
+        .transition_status(
+            id,
+            TxStatus::Pending,
+            TxStatus::Submitted,
+            &StatusUpdate {
+                tx_hash: Some("0x0a".to_string()),
+                nonce: Some(7),
+                ..Default::default()
+            },
+        )
+        .await?;
+    repository
+        .record_attempt(&attempt("0x0A", "30000000000", "1000000000"))
+        .await?;
+    repository
+        .record_attempt(&attempt("0x0b", "33000000000", "1100000000"))
+        .await?;
+    assert!(repository.replace_tx_hash(id, "0x0B").await?);
+    repository.mark_attempt_mined("0x0a").await?;
+
+    let attempts = repository.attempts(id).await?;
+    assert_eq!(attempts.len(), 2);
+    assert_eq!(attempts[0].tx_hash, "0x0a");
+    assert!(attempts[0].mined);
+    assert_eq!(attempts[1].max_fee_per_gas, "33000000000");
+    assert!(!attempts[1].mined);
+
+    let stored = repository.find_by_id(id).await?.expect("request exists");
+    assert_eq!(stored.tx_hash.as_deref(), Some("0x0b"));
+
+    // The replaced hash still leads to the request
+    let by_old_hash = repository.find_by_tx_hash("0x0A").await?;
+    assert_eq!(by_old_hash.len(), 1);
+    assert_eq!(by_old_hash[0].id, id);
+    assert_eq!(repository.attempts_of(&[id]).await?.len(), 2);
+    Ok(())
+}
+
+#[sqlx::test]
+async fn pages_through_filtered_requests(pool: PgPool) -> anyhow::Result<()> {
+    let repository = TxRequestRepository::new(pool);
+    let sender = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+    for _ in 0..5 {
+        repository.insert(&new_request(sender)).await?;
+    }
+    repository
+        .insert(&new_request("0x70997970C51812dc3A010C7d01b50e0d17dc79C8"))
+        .await?;
+
+    let filter = TxRequestFilter {
+        tenant_id: Some(DEFAULT_TENANT_ID),
+        from_address: Some(sender.to_string()),
+        ..Default::default()
+    };
+    let mut seen = Vec::new();
+    let mut after = None;
+    loop {
+        let page = repository.list_after(&filter, after, 2).await?;
+        let Some(last) = page.last() else { break };
+        after = Some(Cursor {
+            created_at: last.created_at,
+            id: last.id,
+        });
+        seen.extend(page.iter().map(|request| (request.created_at, request.id)));
+    }
+    assert_eq!(seen.len(), 5);
+    assert!(seen.windows(2).all(|pair| pair[0] > pair[1]));
+
+    let other_tenant = TxRequestFilter {
+        tenant_id: Some(uuid::Uuid::from_u128(1)),
+        ..Default::default()
+    };
+    assert!(
+        repository
+            .list_after(&other_tenant, None, 10)
+            .await?
+            .is_empty()
+    );
+    let failed = TxRequestFilter {
+        status: Some(TxStatus::Failed),
+        ..Default::default()
+    };
+    assert!(repository.list_after(&failed, None, 10).await?.is_empty());
+    Ok(())
+}
@@ -0,0 +1,211 @@
+//! Integration tests for the webhook outbox; see `tx_request_repository.rs` for the Postgres setup.
+
+use chrono::{Duration, Utc};
+use db::db::{NewTxRequest, StatusUpdate, TxRequestRepository, TxStatus};
+use db::tenants::{DEFAULT_TENANT_ID, TenantRepository};
+use db::webhooks::{DeliveryStatus, WebhookRepository};
+use sqlx::PgPool;
+
+fn new_request(callback_url: Option<&str>) -> NewTxRequest {
+    NewTxRequest {
+        chain_id: 11155111,
+        tenant_id: DEFAULT_TENANT_ID,
+        from_address: "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266".to_string(),
+        to_address: "0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f".to_string(),
+        value: "0".to_string(),
+        gas: 100_000,
+        deadline: 1_900_000_000,
+        data: vec![0xd0, 0x9d, 0xe0, 0x8a],
+        signature: vec![0xab; 65],
+        estimated_cost_wei: None,
+        callback_url: callback_url.map(str::to_string),
+    }
+}
+
+fn submitted() -> StatusUpdate {
+    StatusUpdate {
+        tx_hash: Some("0x0a".to_string()),
+        nonce: Some(3),
+        ..Default::default()
+    }
+}
+
+#[sqlx::test]
+async fn transitions_queue_events(pool: PgPool) -> anyhow::Result<()> {
+    let requests = TxRequestRepository::new(pool.clone());
+    let tenants = TenantRepository::new(pool.clone());
+    let webhooks = WebhookRepository::new(pool);
+
+    // Without a URL of the request or the tenant nothing is queued
+    let silent = requests.insert(&new_request(None)).await?;
+    requests
+        .transition_status(silent, TxStatus::Pending, TxStatus::Submitted, &submitted())
+        .await?;
+    assert!(
+        webhooks
+            .list(DEFAULT_TENANT_ID, None, None, 10)
+            .await?
+            .is_empty()
+    );
+
+    tenants
+        .set_webhook(
+            DEFAULT_TENANT_ID,
+            Some("https://tenant.example/hooks"),
+            "whsec_test",
+        )
+        .await?;
+    let own = requests
+        .insert(&new_request(Some("https://own.example/hook")))
+        .await?;
+    requests
+        .transition_status(own, TxStatus::Pending, TxStatus::Submitted, &submitted())
+        .await?;
+    requests
+        .transition_status(
+            silent,
+            TxStatus::Submitted,
+            TxStatus::Confirmed,
+            &StatusUpdate {
+                gas_used: Some(21_000),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    let deliveries = webhooks.list(DEFAULT_TENANT_ID, None, None, 10).await?;
+    assert_eq!(deliveries.len(), 2);
+    let confirmed = deliveries
+        .iter()
+        .find(|d| d.tx_request_id == silent)
+        .unwrap();
+    assert_eq!(confirmed.url, "https://tenant.example/hooks");
+    assert_eq!(confirmed.event_type, "relay.confirmed");
+    assert_eq!(confirmed.status, DeliveryStatus::Pending);
+    assert_eq!(confirmed.payload["id"], confirmed.id.to_string());
+    assert_eq!(confirmed.payload["data"]["previous_status"], "submitted");
+    assert_eq!(confirmed.payload["data"]["gas_used"], 21_000);
+    let own_delivery = deliveries.iter().find(|d| d.tx_request_id == own).unwrap();
+    assert_eq!(own_delivery.url, "https://own.example/hook");
+    assert_eq!(own_delivery.payload["data"]["tx_hash"], "0x0a");
+    assert_eq!(
+        webhooks
+            .list(DEFAULT_TENANT_ID, None, Some(own), 10)
+            .await?
+            .len(),
+        1
+    );
+    Ok(())
+}
+
+#[sqlx::test]
+async fn fee_bumps_queue_replaced_events(pool: PgPool) -> anyhow::Result<()> {
+    let requests = TxRequestRepository::new(pool.clone());
+    let webhooks = WebhookRepository::new(pool);
+    let id = requests
+        .insert(&new_request(Some("https://own.example/hook")))
+        .await?;
+    requests
+        .transition_status(id, TxStatus::Pending, TxStatus::Submitted, &submitted())
+        .await?;
+
+    assert!(requests.replace_tx_hash(id, "0x0B").await?);
+    let deliveries = webhooks.list(DEFAULT_TENANT_ID, None, Some(id), 10).await?;
+    assert_eq!(deliveries.len(), 2);
+    let replaced = deliveries
+        .iter()
+        .find(|d| d.event_type == "relay.replaced")
+        .unwrap();
+    assert_eq!(replaced.url, "https://own.example/hook");
+    assert_eq!(replaced.payload["type"], "relay.replaced");
+    assert_eq!(replaced.payload["data"]["status"], "submitted");
+    assert_eq!(replaced.payload["data"]["tx_hash"], "0x0b");
+    assert_eq!(replaced.payload["data"]["replaced_tx_hash"], "0x0a");
+
+    // Settled requests are not replaced
+    requests
+        .transition_status(
+            id,
+            TxStatus::Submitted,
+            TxStatus::Confirmed,
+            &StatusUpdate::default(),
+        )
+        .await?;
+    assert!(!requests.replace_tx_hash(id, "0x0c").await?);
+    assert_eq!(
+        webhooks
+            .list(DEFAULT_TENANT_ID, None, Some(id), 10)
+            .await?
+            .len(),
+        3
+    );
+    Ok(())
+}
+
+#[sqlx::test]
+async fn retries_then_dead_letters(pool: PgPool) -> anyhow::Result<()> {
+    let requests = TxRequestRepository::new(pool.clone());
+    let webhooks = WebhookRepository::new(pool);
+    let id = requests
+        .insert(&new_request(Some("https://own.example/hook")))
+        .await?;
+    requests
+        .transition_status(id, TxStatus::Pending, TxStatus::Submitted, &submitted())
+        .await?;
+
+    let lease = std::time::Duration::from_secs(60);
+    let claimed = webhooks.claim_due(10, lease).await?;
+    assert_eq!(claimed.len(), 1);
+    assert_eq!(claimed[0].attempts, 1);
+    assert_eq!(claimed[0].secret, None);
+    // The body is the stored payload, byte for byte what gets signed
+    let payload: serde_json::Value = serde_json::from_str(&claimed[0].body)?;
+    assert_eq!(payload["type"], "relay.submitted");
+    // Leased deliveries are not handed out twice
+    assert!(webhooks.claim_due(10, lease).await?.is_empty());
+
+    let delivery = claimed[0].id;
+    webhooks
+        .mark_failed(
+            delivery,
+            Some(502),
+            "HTTP 502",
+            Some(Utc::now() - Duration::seconds(1)),
+        )
+        .await?;
+    let retried = webhooks.claim_due(10, lease).await?;
+    assert_eq!(retried.len(), 1);
+    assert_eq!(retried[0].attempts, 2);
+
+    webhooks
+        .mark_failed(delivery, None, "connection refused", None)
+        .await?;
+    let dead = webhooks
+        .list(DEFAULT_TENANT_ID, Some(DeliveryStatus::Dead), None, 10)
+        .await?;
+    assert_eq!(dead.len(), 1);
+    assert_eq!(dead[0].last_error.as_deref(), Some("connection refused"));
+    assert!(webhooks.claim_due(10, lease).await?.is_empty());
+
+    let redelivered = webhooks
+        .redeliver(delivery, DEFAULT_TENANT_ID)
+        .await?
+        .expect("dead delivery");
+    assert_eq!(redelivered.status, DeliveryStatus::Pending);
+    assert_eq!(redelivered.attempts, 0);
+    assert!(
+        webhooks
+            .redeliver(delivery, DEFAULT_TENANT_ID)
+            .await?
+            .is_none()
+    );
+
+    let again = webhooks.claim_due(10, lease).await?;
+    webhooks.mark_delivered(again[0].id, 204).await?;
+    let delivered = webhooks
+        .list(DEFAULT_TENANT_ID, Some(DeliveryStatus::Delivered), None, 10)
+        .await?;
+    assert_eq!(delivered[0].last_status_code, Some(204));
+    assert!(delivered[0].delivered_at.is_some());
+    Ok(())
+}
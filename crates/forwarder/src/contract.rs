@@ -0,0 +1,35 @@
+use alloy::sol;
+
+sol! {
+    /// The subset of OpenZeppelin's `ERC2771Forwarder` ABI the relayer calls.
+    #[derive(Debug)]
+    interface TrustedForwarder {
+        struct ForwardRequestData {
+            address from;
+            address to;
+            uint256 value;
+            uint256 gas;
+            uint48 deadline;
+            bytes data;
+            bytes signature;
+        }
+
+        function execute(ForwardRequestData calldata request) external payable;
+
+        /// Atomic when `refundReceiver` is the zero address: one invalid request reverts the
+        /// batch. Otherwise invalid requests are skipped and their value sent to `refundReceiver`.
+        function executeBatch(ForwardRequestData[] calldata requests, address payable refundReceiver) external payable;
+
+        function nonces(address owner) external view returns (uint256);
+
+        /// One per request that was executed, whether or not its call succeeded.
+        event ExecutedForwardRequest(address indexed signer, uint256 nonce, bool success);
+
+        error ERC2771ForwarderInvalidSigner(address signer, address from);
+        error ERC2771ForwarderMismatchedValue(uint256 requestedValue, uint256 msgValue);
+        error ERC2771ForwarderExpiredRequest(uint48 deadline);
+        error ERC2771UntrustfulTarget(address target, address forwarder);
+        error FailedCall();
+        error FailedInnerCall();
+    }
+}
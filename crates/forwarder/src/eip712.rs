@@ -0,0 +1,150 @@
+use crate::error::SignatureError;
+use crate::forward_request::ForwardRequestData;
+use alloy::primitives::{Address, Signature, B256, U256};
+use alloy::sol_types::{Eip712Domain, SolStruct};
+use std::borrow::Cow;
+
+/// EIP-712 version used by `ERC2771Forwarder`'s constructor.
+pub const FORWARDER_EIP712_VERSION: &str = "1";
+
+/// The EIP-712 domain of a deployed `TrustedForwarder`.
+#[derive(Debug, Clone)]
+pub struct ForwarderDomain {
+    domain: Eip712Domain,
+}
+
+impl ForwarderDomain {
+    pub fn new(name: &str, chain_id: u64, verifying_contract: Address) -> Self {
+        let domain = Eip712Domain::new(
+            Some(Cow::Owned(name.to_string())),
+            Some(Cow::Borrowed(FORWARDER_EIP712_VERSION)),
+            Some(U256::from(chain_id)),
+            Some(verifying_contract),
+            None,
+        );
+        Self { domain }
+    }
+
+    pub fn domain(&self) -> &Eip712Domain {
+        &self.domain
+    }
+
+    pub fn signing_hash(&self, request: &ForwardRequestData) -> B256 {
+        request.forward_request().eip712_signing_hash(&self.domain)
+    }
+
+    /// Recovers the signer of `request` and checks it against `request.from`.
+    pub fn recover_signer(&self, request: &ForwardRequestData) -> Result<Address, SignatureError> {
+        let signature = Signature::try_from(request.signature.as_ref())
+            .map_err(|e| SignatureError::Malformed(e.to_string()))?;
+
+        let recovered = signature
+            .recover_address_from_prehash(&self.signing_hash(request))
+            .map_err(|e| SignatureError::Unrecoverable(e.to_string()))?;
+
+        if recovered != request.from {
+            return Err(SignatureError::SignerMismatch {
+                expected: request.from,
+                recovered,
+            });
+        }
+
+        Ok(recovered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{address, b256, bytes, Bytes};
+
+    // Signed with the well-known Hardhat/Anvil account #0 private key
+    // 0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80.
+    const SIGNER: Address = address!("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+    const SIGNATURE: Bytes = bytes!("ab19b0e286b219e0f9461ceb1a37dc2f58b1b71bccd59897c3d9db4c5571934174d556ca3e431cb70b4064c47bbe18b6dce11b40b204419d521230b095d7831f1c");
+
+    fn sepolia_domain() -> ForwarderDomain {
+        ForwarderDomain::new(
+            "TrustedForwarder",
+            11155111,
+            address!("0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f"),
+        )
+    }
+
+    fn signed_request() -> ForwardRequestData {
+        ForwardRequestData {
+            from: SIGNER,
+            to: address!("0x1111111111111111111111111111111111111111"),
+            value: U256::ZERO,
+            gas: U256::from(100_000),
+            nonce: U256::ZERO,
+            deadline: 1_900_000_000,
+            data: bytes!("d09de08a"),
+            signature: SIGNATURE,
+        }
+    }
+
+    #[test]
+    fn type_hash_matches_erc2771_forwarder() {
+        let request = signed_request().forward_request();
+        assert_eq!(
+            request.eip712_type_hash(),
+            b256!("0x7f96328b83274ebc7c1cf4f7a3abda602b51a78b7fa1d86a2ce353d75e587cac")
+        );
+    }
+
+    #[test]
+    fn signing_hash_is_stable() {
+        assert_eq!(
+            sepolia_domain().signing_hash(&signed_request()),
+            b256!("0x15d8ff9b27d7cc1f2acbe5f65197456a9dd37d4ed4198ddbd138e828d1cd10a0")
+        );
+    }
+
+    #[test]
+    fn recovers_signer_of_valid_request() {
+        assert_eq!(
+            sepolia_domain().recover_signer(&signed_request()),
+            Ok(SIGNER)
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_request() {
+        let mut request = signed_request();
+        request.nonce = U256::from(1);
+
+        assert!(matches!(
+            sepolia_domain().recover_signer(&request),
+            Err(SignatureError::SignerMismatch {
+                expected: SIGNER,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_signature_for_another_domain() {
+        let other_chain = ForwarderDomain::new(
+            "TrustedForwarder",
+            10,
+            address!("0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f"),
+        );
+
+        assert!(matches!(
+            other_chain.recover_signer(&signed_request()),
+            Err(SignatureError::SignerMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_signature() {
+        let mut request = signed_request();
+        request.signature = bytes!("0102");
+
+        assert!(matches!(
+            sepolia_domain().recover_signer(&request),
+            Err(SignatureError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,33 @@
+use alloy::primitives::Address;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    /// The signature bytes could not be parsed as an ECDSA signature.
+    Malformed(String),
+    /// No address could be recovered from the signature and signing hash.
+    Unrecoverable(String),
+    /// The recovered signer is not the `from` address of the request.
+    SignerMismatch {
+        expected: Address,
+        recovered: Address,
+    },
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::Malformed(e) => write!(f, "malformed signature: {e}"),
+            SignatureError::Unrecoverable(e) => write!(f, "unable to recover signer: {e}"),
+            SignatureError::SignerMismatch {
+                expected,
+                recovered,
+            } => write!(
+                f,
+                "signature was produced by {recovered}, expected {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
@@ -0,0 +1,183 @@
+use crate::contract::TrustedForwarder;
+use alloy::primitives::aliases::U48;
+use alloy::primitives::{Address, Bytes, U256};
+use alloy::sol;
+use serde::Deserialize;
+
+/// Largest value representable by the `uint48 deadline` field of a ForwardRequest.
+pub const MAX_DEADLINE: u64 = (1 << 48) - 1;
+
+sol! {
+    /// The EIP-712 struct signed by users, matching `ERC2771Forwarder._FORWARD_REQUEST_TYPEHASH`.
+    #[derive(Debug)]
+    struct ForwardRequest {
+        address from;
+        address to;
+        uint256 value;
+        uint256 gas;
+        uint256 nonce;
+        uint48 deadline;
+        bytes data;
+    }
+}
+
+/// Mirrors OpenZeppelin's `ERC2771Forwarder.ForwardRequestData` struct.
+/// The forwarder reads the nonce from chain, but clients must send the nonce
+/// they signed over so the relayer can verify the signature before queuing.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ForwardRequestData {
+    pub from: Address,
+    pub to: Address,
+    pub value: U256,
+    pub gas: U256,
+    pub nonce: U256,
+    pub deadline: u64,
+    pub data: Bytes,
+    pub signature: Bytes,
+}
+
+impl ForwardRequestData {
+    pub fn validate(&self, now: u64) -> Result<(), &'static str> {
+        if self.from == Address::ZERO {
+            return Err("from must not be the zero address");
+        }
+        if self.to == Address::ZERO {
+            return Err("to must not be the zero address");
+        }
+        if self.gas.is_zero() {
+            return Err("gas must be greater than zero");
+        }
+        if self.gas > U256::from(i64::MAX) {
+            return Err("gas is too large");
+        }
+        if self.deadline > MAX_DEADLINE {
+            return Err("deadline does not fit in uint48");
+        }
+        if self.deadline <= now {
+            return Err("deadline has already passed");
+        }
+        if self.signature.len() != 65 {
+            return Err("signature must be 65 bytes");
+        }
+        Ok(())
+    }
+
+    /// The typed struct whose EIP-712 hash the signature commits to.
+    pub fn forward_request(&self) -> ForwardRequest {
+        ForwardRequest {
+            from: self.from,
+            to: self.to,
+            value: self.value,
+            gas: self.gas,
+            nonce: self.nonce,
+            deadline: U48::saturating_from(self.deadline),
+            data: self.data.clone(),
+        }
+    }
+
+    /// The struct `TrustedForwarder.execute` takes, which leaves the nonce to the contract.
+    pub fn execute_data(&self) -> TrustedForwarder::ForwardRequestData {
+        TrustedForwarder::ForwardRequestData {
+            from: self.from,
+            to: self.to,
+            value: self.value,
+            gas: self.gas,
+            deadline: U48::saturating_from(self.deadline),
+            data: self.data.clone(),
+            signature: self.signature.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{address, bytes};
+
+    const NOW: u64 = 1_800_000_000;
+
+    fn request() -> ForwardRequestData {
+        ForwardRequestData {
+            from: address!("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"),
+            to: address!("0x1111111111111111111111111111111111111111"),
+            value: U256::ZERO,
+            gas: U256::from(100_000),
+            nonce: U256::ZERO,
+            deadline: NOW + 60,
+            data: bytes!("d09de08a"),
+            signature: Bytes::from(vec![0; 65]),
+        }
+    }
+
+    #[test]
+    fn accepts_well_formed_requests() {
+        assert_eq!(request().validate(NOW), Ok(()));
+    }
+
+    #[test]
+    fn rejects_expired_deadlines() {
+        let mut expired = request();
+        expired.deadline = NOW;
+        assert_eq!(expired.validate(NOW), Err("deadline has already passed"));
+        expired.deadline = NOW - 1;
+        assert_eq!(expired.validate(NOW), Err("deadline has already passed"));
+
+        let mut too_far = request();
+        too_far.deadline = MAX_DEADLINE + 1;
+        assert_eq!(
+            too_far.validate(NOW),
+            Err("deadline does not fit in uint48")
+        );
+    }
+
+    #[test]
+    fn rejects_zero_addresses() {
+        let mut from = request();
+        from.from = Address::ZERO;
+        assert_eq!(from.validate(NOW), Err("from must not be the zero address"));
+
+        let mut to = request();
+        to.to = Address::ZERO;
+        assert_eq!(to.validate(NOW), Err("to must not be the zero address"));
+    }
+
+    #[test]
+    fn rejects_malformed_addresses() {
+        let body = |from: &str, to: &str| {
+            format!(
+                r#"{{"from":"{from}","to":"{to}","value":"0x0","gas":"0x186a0","nonce":"0x0",
+                    "deadline":1900000000,"data":"0x","signature":"0x00"}}"#
+            )
+        };
+        let valid = "0x1111111111111111111111111111111111111111";
+        assert!(serde_json::from_str::<ForwardRequestData>(&body(valid, valid)).is_ok());
+        for invalid in [
+            "0x1234",
+            "0xzz11111111111111111111111111111111111111",
+            "alice",
+        ] {
+            assert!(serde_json::from_str::<ForwardRequestData>(&body(invalid, valid)).is_err());
+            assert!(serde_json::from_str::<ForwardRequestData>(&body(valid, invalid)).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_gas_out_of_range() {
+        let mut zero = request();
+        zero.gas = U256::ZERO;
+        assert_eq!(zero.validate(NOW), Err("gas must be greater than zero"));
+
+        let mut largest = request();
+        largest.gas = U256::from(i64::MAX);
+        assert_eq!(largest.validate(NOW), Ok(()));
+        largest.gas += U256::from(1);
+        assert_eq!(largest.validate(NOW), Err("gas is too large"));
+    }
+
+    #[test]
+    fn rejects_signatures_of_the_wrong_length() {
+        let mut short = request();
+        short.signature = Bytes::from(vec![0; 64]);
+        assert_eq!(short.validate(NOW), Err("signature must be 65 bytes"));
+    }
+}
@@ -0,0 +1,11 @@
+pub mod contract;
+pub mod eip712;
+pub mod error;
+pub mod forward_request;
+pub mod revert;
+
+pub use contract::*;
+pub use eip712::*;
+pub use error::*;
+pub use forward_request::*;
+pub use revert::*;
@@ -0,0 +1,218 @@
+use crate::contract::TrustedForwarder::TrustedForwarderErrors;
+use alloy::dyn_abi::{DynSolValue, JsonAbiExt};
+use alloy::json_abi::Error;
+use alloy::primitives::hex;
+use alloy::sol_types::SolInterface;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A custom error of a target contract, declared by its signature such as
+/// `InsufficientBalance(uint256,uint256)`, so reverts carrying it can be named.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CustomError(Error);
+
+/// Turns the return data of a reverted call into a human readable reason.
+/// Forwarder errors are named, `Error(string)` and `Panic(uint256)` are decoded,
+/// anything else is reported by its raw selector and data.
+pub fn decode_revert_reason(data: &[u8]) -> String {
+    if data.is_empty() {
+        return "execution reverted".to_string();
+    }
+
+    if let Ok(error) = TrustedForwarderErrors::abi_decode(data) {
+        return describe_forwarder_error(&error);
+    }
+
+    match alloy::sol_types::decode_revert_reason(data) {
+        Some(reason) => reason,
+        None => format!("execution reverted: 0x{}", hex::encode(data)),
+    }
+}
+
+/// Reason of a reverted call of a target contract: one of its declared `errors` with the
+/// arguments it was raised with, else `Error(string)` and `Panic(uint256)`, else the raw data.
+pub fn decode_target_revert(data: &[u8], errors: &[CustomError]) -> String {
+    if data.is_empty() {
+        return "execution reverted".to_string();
+    }
+    let declared = data.get(..4).and_then(|selector| {
+        errors
+            .iter()
+            .find(|error| error.0.selector().as_slice() == selector)
+    });
+    if let Some(error) = declared {
+        if let Ok(values) = error.0.abi_decode_input(&data[4..]) {
+            return format!("{}({})", error.0.name, format_values(&values));
+        }
+    }
+
+    match alloy::sol_types::decode_revert_reason(data) {
+        Some(reason) => reason,
+        None => format!("execution reverted: 0x{}", hex::encode(data)),
+    }
+}
+
+fn format_values(values: &[DynSolValue]) -> String {
+    values
+        .iter()
+        .map(format_value)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_value(value: &DynSolValue) -> String {
+    match value {
+        DynSolValue::Address(address) => address.to_checksum(None),
+        DynSolValue::Bool(value) => value.to_string(),
+        DynSolValue::Int(value, _) => value.to_string(),
+        DynSolValue::Uint(value, _) => value.to_string(),
+        DynSolValue::String(value) => format!("{value:?}"),
+        DynSolValue::Bytes(bytes) => format!("0x{}", hex::encode(bytes)),
+        DynSolValue::FixedBytes(word, size) => format!("0x{}", hex::encode(&word[..*size])),
+        DynSolValue::Array(values) | DynSolValue::FixedArray(values) => {
+            format!("[{}]", format_values(values))
+        }
+        DynSolValue::Tuple(values) => format!("({})", format_values(values)),
+        other => format!("{other:?}"),
+    }
+}
+
+impl FromStr for CustomError {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let error =
+            Error::parse(value).map_err(|e| format!("invalid error signature {value}: {e}"))?;
+        Ok(Self(error))
+    }
+}
+
+impl fmt::Display for CustomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.signature())
+    }
+}
+
+impl Serialize for CustomError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for CustomError {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+fn describe_forwarder_error(error: &TrustedForwarderErrors) -> String {
+    match error {
+        TrustedForwarderErrors::ERC2771ForwarderInvalidSigner(e) => format!(
+            "ERC2771ForwarderInvalidSigner: signed by {} instead of {}",
+            e.signer, e.from
+        ),
+        TrustedForwarderErrors::ERC2771ForwarderMismatchedValue(e) => format!(
+            "ERC2771ForwarderMismatchedValue: request value {} but sent {}",
+            e.requestedValue, e.msgValue
+        ),
+        TrustedForwarderErrors::ERC2771ForwarderExpiredRequest(e) => format!(
+            "ERC2771ForwarderExpiredRequest: deadline {} has passed",
+            e.deadline
+        ),
+        TrustedForwarderErrors::ERC2771UntrustfulTarget(e) => format!(
+            "ERC2771UntrustfulTarget: {} does not trust forwarder {}",
+            e.target, e.forwarder
+        ),
+        TrustedForwarderErrors::FailedCall(_) | TrustedForwarderErrors::FailedInnerCall(_) => {
+            "target call reverted".to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::TrustedForwarder;
+    use alloy::primitives::aliases::U48;
+    use alloy::primitives::{address, U256};
+    use alloy::sol_types::{Revert, SolError};
+
+    #[test]
+    fn decodes_forwarder_errors() {
+        let data = TrustedForwarder::ERC2771UntrustfulTarget {
+            target: address!("0x1111111111111111111111111111111111111111"),
+            forwarder: address!("0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f"),
+        }
+        .abi_encode();
+        assert_eq!(
+            decode_revert_reason(&data),
+            "ERC2771UntrustfulTarget: 0x1111111111111111111111111111111111111111 does not trust forwarder 0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f"
+        );
+
+        let data = TrustedForwarder::ERC2771ForwarderExpiredRequest {
+            deadline: U48::from(1_700_000_000u64),
+        }
+        .abi_encode();
+        assert_eq!(
+            decode_revert_reason(&data),
+            "ERC2771ForwarderExpiredRequest: deadline 1700000000 has passed"
+        );
+    }
+
+    #[test]
+    fn decodes_revert_strings() {
+        let data = Revert::from("Ownable: caller is not the owner").abi_encode();
+        assert_eq!(
+            decode_revert_reason(&data),
+            "revert: Ownable: caller is not the owner"
+        );
+    }
+
+    #[test]
+    fn decodes_declared_target_errors() {
+        let errors: Vec<CustomError> = ["InsufficientBalance(address,uint256,uint256)", "Paused()"]
+            .iter()
+            .map(|signature| signature.parse().unwrap())
+            .collect();
+        assert_eq!(
+            errors[0].to_string(),
+            "InsufficientBalance(address,uint256,uint256)"
+        );
+
+        let mut data = alloy::primitives::keccak256("InsufficientBalance(address,uint256,uint256)")
+            [..4]
+            .to_vec();
+        data.extend_from_slice(&[0u8; 12]);
+        data.extend_from_slice(address!("0x70997970C51812dc3A010C7d01b50e0d17dc79C8").as_slice());
+        data.extend_from_slice(&U256::from(5).to_be_bytes::<32>());
+        data.extend_from_slice(&U256::from(10).to_be_bytes::<32>());
+        assert_eq!(
+            decode_target_revert(&data, &errors),
+            "InsufficientBalance(0x70997970C51812dc3A010C7d01b50e0d17dc79C8, 5, 10)"
+        );
+
+        // Undeclared errors and revert strings fall back to the generic decoding
+        assert_eq!(
+            decode_target_revert(&data, &errors[1..]),
+            format!("execution reverted: 0x{}", hex::encode(&data))
+        );
+        let data = Revert::from("not allowed").abi_encode();
+        assert_eq!(decode_target_revert(&data, &errors), "revert: not allowed");
+        assert_eq!(decode_target_revert(&[], &errors), "execution reverted");
+        assert!("transfer(".parse::<CustomError>().is_err());
+    }
+
+    #[test]
+    fn falls_back_to_raw_data() {
+        assert_eq!(decode_revert_reason(&[]), "execution reverted");
+
+        let mut data = vec![0xde, 0xad, 0xbe, 0xef];
+        data.extend_from_slice(&U256::from(7).to_be_bytes::<32>());
+        assert_eq!(
+            decode_revert_reason(&data),
+            format!("execution reverted: 0xdeadbeef{:064x}", 7)
+        );
+    }
+}
@@ -1,8 +1,12 @@
+use chrono::{DateTime, Utc};
+use futures::future::{join_all, BoxFuture};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::Instant;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Ordered from best to worst so the overall status is simply the maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum HealthStatus {
     Healthy,
     Degraded,
@@ -26,22 +30,52 @@ pub struct SystemHealth {
     pub uptime_seconds: u64,
 }
 
+/// One component of the system that can report its own health.
+pub trait HealthCheck: Send + Sync {
+    /// Key of the component in `SystemHealth::components`.
+    fn name(&self) -> String;
+
+    fn check(&self) -> BoxFuture<'_, ComponentHealth>;
+}
+
+impl HealthStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HealthStatus::Healthy => "healthy",
+            HealthStatus::Degraded => "degraded",
+            HealthStatus::Unhealthy => "unhealthy",
+        }
+    }
+}
+
 impl ComponentHealth {
-    pub fn healthy(message: &str) -> Self {
+    pub fn new(status: HealthStatus, message: &str) -> Self {
         Self {
-            status: HealthStatus::Healthy,
+            status,
             message: message.to_string(),
             last_checked: Utc::now(),
             response_time_ms: None,
             details: HashMap::new(),
         }
     }
-    
+
+    pub fn healthy(message: &str) -> Self {
+        Self::new(HealthStatus::Healthy, message)
+    }
+
+    pub fn degraded(message: &str) -> Self {
+        Self::new(HealthStatus::Degraded, message)
+    }
+
+    pub fn unhealthy(message: &str) -> Self {
+        Self::new(HealthStatus::Unhealthy, message)
+    }
+
     pub fn with_response_time(mut self, response_time_ms: u64) -> Self {
         self.response_time_ms = Some(response_time_ms);
         self
     }
-    
+
     pub fn with_detail(mut self, key: &str, value: serde_json::Value) -> Self {
         self.details.insert(key.to_string(), value);
         self
@@ -57,28 +91,95 @@ impl SystemHealth {
             uptime_seconds,
         }
     }
-    
+
+    /// Adds a component; the overall status becomes the worst one seen.
     pub fn add_component(&mut self, name: &str, health: ComponentHealth) {
+        self.overall_status = self.overall_status.max(health.status);
         self.components.insert(name.to_string(), health);
     }
 }
 
-pub struct HealthChecker;
+/// Registry of health checks. Uptime counts from when the checker was created at startup.
+#[derive(Clone)]
+pub struct HealthChecker {
+    checks: Vec<Arc<dyn HealthCheck>>,
+    started_at: Instant,
+}
+
+impl Default for HealthChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl HealthChecker {
     pub fn new() -> Self {
-        Self
+        Self {
+            checks: Vec::new(),
+            started_at: Instant::now(),
+        }
+    }
+
+    pub fn register(&mut self, check: impl HealthCheck + 'static) {
+        self.checks.push(Arc::new(check));
     }
-    
+
+    pub fn uptime_seconds(&self) -> u64 {
+        self.started_at.elapsed().as_secs()
+    }
+
+    /// Runs every registered check concurrently.
     pub async fn check_system_health(&self) -> SystemHealth {
-        let mut system_health = SystemHealth::new(3600);
-        
-        system_health.add_component("database", 
-            ComponentHealth::healthy("Database connection active")
-                .with_response_time(5)
-                .with_detail("active_connections", serde_json::json!(10))
-        );
-        
+        let results = join_all(
+            self.checks
+                .iter()
+                .map(|check| async move { (check.name(), check.check().await) }),
+        )
+        .await;
+
+        let mut system_health = SystemHealth::new(self.uptime_seconds());
+        for (name, health) in results {
+            system_health.add_component(&name, health);
+        }
+
         system_health
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(&'static str, HealthStatus);
+
+    impl HealthCheck for Fixed {
+        fn name(&self) -> String {
+            self.0.to_string()
+        }
+
+        fn check(&self) -> BoxFuture<'_, ComponentHealth> {
+            Box::pin(async move { ComponentHealth::new(self.1, "fixed") })
+        }
+    }
+
+    #[tokio::test]
+    async fn overall_status_is_the_worst_component() {
+        let mut checker = HealthChecker::new();
+        assert_eq!(
+            checker.check_system_health().await.overall_status,
+            HealthStatus::Healthy
+        );
+
+        checker.register(Fixed("database", HealthStatus::Healthy));
+        checker.register(Fixed("rpc", HealthStatus::Degraded));
+        let health = checker.check_system_health().await;
+        assert_eq!(health.overall_status, HealthStatus::Degraded);
+        assert_eq!(health.components.len(), 2);
+
+        checker.register(Fixed("balance", HealthStatus::Unhealthy));
+        assert_eq!(
+            checker.check_system_health().await.overall_status,
+            HealthStatus::Unhealthy
+        );
+    }
+}
@@ -1,7 +1,7 @@
-pub mod metrics_collector;
 pub mod health_checks;
+pub mod metrics_collector;
 pub mod middleware;
 
-pub use metrics_collector::*;
 pub use health_checks::*;
-pub use middleware::*;
\ No newline at end of file
+pub use metrics_collector::*;
+pub use middleware::*;
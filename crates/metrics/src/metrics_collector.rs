@@ -1,15 +1,40 @@
+use prometheus::proto::MetricType;
 use prometheus::{
-    Counter, Gauge, Histogram, IntCounter, IntGauge, Registry, Encoder, TextEncoder,
-    HistogramOpts, Opts,};
+    Counter, CounterVec, Encoder, Gauge, GaugeVec, Histogram, HistogramOpts, HistogramVec,
+    IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
-#[derive(Clone)]
+/// Label of the series that are tracked per chain.
+const CHAIN_LABEL: [&str; 1] = ["chain_id"];
+
+/// Current value of one metric series, as read from the registry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSample {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub value: MetricValue,
+}
 
-/// This struct holds all Prometheus metric objects and provides methods that update and export them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetricValue {
+    Counter(f64),
+    Gauge(f64),
+    /// Number of observations and their sum, since process start.
+    Histogram {
+        count: u64,
+        sum: f64,
+    },
+}
 
+/// This struct holds all Prometheus metric objects and provides methods that update and export them.
+/// Transaction, gas price, queue, RPC and relayer series are labelled with `chain_id`;
+/// [`MetricsCollector::chain`] resolves them for one chain.
+#[derive(Clone)]
 pub struct MetricsCollector {
     pub registry: Arc<Registry>,
-    
+
     // Transaction metrics
     /*
     These track:
@@ -18,12 +43,12 @@ pub struct MetricsCollector {
     How many are still pending.
     Time taken per transaction.
     */
-    pub transactions_total: IntCounter,
-    pub transactions_success: IntCounter,
-    pub transactions_failed: IntCounter,
-    pub transactions_pending: IntGauge,
-    pub transaction_processing_duration: Histogram,
-    
+    pub transactions_total: IntCounterVec,
+    pub transactions_success: IntCounterVec,
+    pub transactions_failed: IntCounterVec,
+    pub transactions_pending: IntGaugeVec,
+    pub transactions_stuck: IntGaugeVec,
+    pub transaction_processing_duration: HistogramVec,
 
     // Gas metrics
     /*
@@ -32,10 +57,11 @@ pub struct MetricsCollector {
     Current gas price (could be updated periodically).
     Violations (when transactions exceed allowed gas limits).
     */
-    pub gas_used_total: Counter,
-    pub gas_price_current: Gauge,
+    pub gas_used_total: CounterVec,
+    pub gas_price_current: GaugeVec,
     pub gas_limit_violations: IntCounter,
-    
+    pub budget_rejections: IntCounter,
+    pub simulation_reverts: IntCounterVec,
 
     // Queue metrics
     /*
@@ -44,11 +70,10 @@ pub struct MetricsCollector {
     How long they wait in the queue.
     How many times retries occurred.
     */
-    pub queue_depth: IntGauge,
-    pub queue_processing_time: Histogram,
-    pub queue_retries_total: IntCounter,
-    
-
+    pub queue_depth: IntGaugeVec,
+    pub queue_processing_time: HistogramVec,
+    pub queue_retries_total: IntCounterVec,
+    pub batch_size: HistogramVec,
 
     // Database metrics
     /*
@@ -60,7 +85,6 @@ pub struct MetricsCollector {
     pub db_connections_active: IntGauge,
     pub db_query_duration: Histogram,
     pub db_errors_total: IntCounter,
-    
 
     // RPC metrics
     /*
@@ -69,12 +93,18 @@ pub struct MetricsCollector {
     How many failed.
     Latency for each RPC call.
     */
-    pub rpc_requests_total: IntCounter,
-    pub rpc_errors_total: IntCounter,
-    pub rpc_latency: Histogram,
+    pub rpc_requests_total: IntCounterVec,
+    pub rpc_errors_total: IntCounterVec,
+    pub rpc_latency: HistogramVec,
 
+    // HTTP metrics
+    /*
+    Track inbound API traffic, labelled by method, matched route
+    template (e.g. `/relay/{id}`) and status class (`2xx`, `4xx`, ...).
+    */
+    pub http_requests_total: IntCounterVec,
+    pub http_request_duration: HistogramVec,
 
-    
     // Relayer metrics
     /*
     Monitor:
@@ -82,10 +112,9 @@ pub struct MetricsCollector {
     Current nonce (to detect stuck txs or misalignment).
     Total sent transactions.
     */
-    pub relayer_balance: Gauge,
-    pub relayer_nonce_current: IntGauge,
-    pub relayer_tx_sent: IntCounter,
-    
+    pub relayer_balance: GaugeVec,
+    pub relayer_nonce_current: IntGaugeVec,
+    pub relayer_tx_sent: IntCounterVec,
 
     // Security metrics
     /*
@@ -97,168 +126,320 @@ pub struct MetricsCollector {
     pub invalid_signatures: IntCounter,
     pub replay_attacks: IntCounter,
     pub rate_limit_hits: IntCounter,
+
+    // Webhook metrics
+    /*
+    Count callback deliveries by outcome: delivered, retried or dead-lettered.
+    */
+    pub webhook_deliveries: IntCounterVec,
+
+    // Event stream metrics
+    /*
+    Open SSE and WebSocket connections receiving relay events, by transport.
+    */
+    pub event_stream_connections: IntGaugeVec,
 }
 
 impl MetricsCollector {
     pub fn new() -> anyhow::Result<Self> {
         let registry = Arc::new(Registry::new());
-        
+
         // Transaction metrics
-        let transactions_total = IntCounter::with_opts(
-            Opts::new("gas_relayer_transactions_total", "Total number of transactions processed")
+        let transactions_total = IntCounterVec::new(
+            Opts::new(
+                "gas_relayer_transactions_total",
+                "Total number of transactions processed",
+            ),
+            &CHAIN_LABEL,
+        )?;
+
+        let transactions_success = IntCounterVec::new(
+            Opts::new(
+                "gas_relayer_transactions_success_total",
+                "Total number of successful transactions",
+            ),
+            &CHAIN_LABEL,
         )?;
-        
-        let transactions_success = IntCounter::with_opts(
-            Opts::new("gas_relayer_transactions_success_total", "Total number of successful transactions")
+
+        let transactions_failed = IntCounterVec::new(
+            Opts::new(
+                "gas_relayer_transactions_failed_total",
+                "Total number of failed transactions",
+            ),
+            &CHAIN_LABEL,
         )?;
-        
-        let transactions_failed = IntCounter::with_opts(
-            Opts::new("gas_relayer_transactions_failed_total", "Total number of failed transactions")
+
+        let transactions_pending = IntGaugeVec::new(
+            Opts::new(
+                "gas_relayer_transactions_pending",
+                "Number of transactions currently pending",
+            ),
+            &CHAIN_LABEL,
         )?;
-        
-        let transactions_pending = IntGauge::with_opts(
-            Opts::new("gas_relayer_transactions_pending", "Number of transactions currently pending")
+
+        let transactions_stuck = IntGaugeVec::new(
+            Opts::new(
+                "gas_relayer_transactions_stuck",
+                "Submitted requests without a receipt for longer than tracker.max_pending_secs",
+            ),
+            &CHAIN_LABEL,
         )?;
-        
-        let transaction_processing_duration = Histogram::with_opts(
-            HistogramOpts::new("gas_relayer_transaction_processing_duration_seconds", "Time spent processing transactions")
-                .buckets(vec![0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0])
+
+        let transaction_processing_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "gas_relayer_transaction_processing_duration_seconds",
+                "Time spent processing transactions",
+            )
+            .buckets(vec![0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0]),
+            &CHAIN_LABEL,
         )?;
-        
+
         // Gas metrics
-        let gas_used_total = Counter::with_opts(
-            Opts::new("gas_relayer_gas_used_total", "Total gas used by relayed transactions")
+        let gas_used_total = CounterVec::new(
+            Opts::new(
+                "gas_relayer_gas_used_total",
+                "Total gas used by relayed transactions",
+            ),
+            &CHAIN_LABEL,
         )?;
-        
-        let gas_price_current = Gauge::with_opts(
-            Opts::new("gas_relayer_gas_price_gwei", "Current gas price in Gwei")
+
+        let gas_price_current = GaugeVec::new(
+            Opts::new("gas_relayer_gas_price_gwei", "Current gas price in Gwei"),
+            &CHAIN_LABEL,
         )?;
-        
-        let gas_limit_violations = IntCounter::with_opts(
-            Opts::new("gas_relayer_gas_limit_violations_total", "Number of transactions exceeding gas limits")
+
+        let gas_limit_violations = IntCounter::with_opts(Opts::new(
+            "gas_relayer_gas_limit_violations_total",
+            "Number of transactions exceeding gas limits",
+        ))?;
+
+        let budget_rejections = IntCounter::with_opts(Opts::new(
+            "gas_relayer_budget_rejections_total",
+            "Number of relay requests refused for exceeding a tenant gas budget",
+        ))?;
+
+        let simulation_reverts = IntCounterVec::new(
+            Opts::new(
+                "gas_relayer_simulation_reverts_total",
+                "Relay requests refused because their simulated execution reverted",
+            ),
+            &CHAIN_LABEL,
         )?;
-        
+
         // Queue metrics
-        let queue_depth = IntGauge::with_opts(
-            Opts::new("gas_relayer_queue_depth", "Number of transactions in processing queue")
+        let queue_depth = IntGaugeVec::new(
+            Opts::new(
+                "gas_relayer_queue_depth",
+                "Number of transactions in processing queue",
+            ),
+            &CHAIN_LABEL,
         )?;
-        
-        let queue_processing_time = Histogram::with_opts(
-            HistogramOpts::new("gas_relayer_queue_processing_time_seconds", "Time transactions spend in queue")
-                .buckets(vec![1.0, 5.0, 10.0, 30.0, 60.0, 300.0, 600.0])
+
+        let queue_processing_time = HistogramVec::new(
+            HistogramOpts::new(
+                "gas_relayer_queue_processing_time_seconds",
+                "Time transactions spend in queue",
+            )
+            .buckets(vec![1.0, 5.0, 10.0, 30.0, 60.0, 300.0, 600.0]),
+            &CHAIN_LABEL,
         )?;
-        
-        let queue_retries_total = IntCounter::with_opts(
-            Opts::new("gas_relayer_queue_retries_total", "Total number of transaction retries")
+
+        let queue_retries_total = IntCounterVec::new(
+            Opts::new(
+                "gas_relayer_queue_retries_total",
+                "Total number of transaction retries",
+            ),
+            &CHAIN_LABEL,
         )?;
-        
-        // Database metrics
-        let db_connections_active = IntGauge::with_opts(
-            Opts::new("gas_relayer_db_connections_active", "Number of active database connections")
+
+        let batch_size = HistogramVec::new(
+            HistogramOpts::new(
+                "gas_relayer_batch_size",
+                "Relay requests per executeBatch transaction",
+            )
+            .buckets(vec![2.0, 5.0, 10.0, 20.0, 50.0, 100.0]),
+            &CHAIN_LABEL,
         )?;
-        
+
+        // Database metrics
+        let db_connections_active = IntGauge::with_opts(Opts::new(
+            "gas_relayer_db_connections_active",
+            "Number of active database connections",
+        ))?;
+
         let db_query_duration = Histogram::with_opts(
-            HistogramOpts::new("gas_relayer_db_query_duration_seconds", "Database query execution time")
-                .buckets(vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0])
+            HistogramOpts::new(
+                "gas_relayer_db_query_duration_seconds",
+                "Database query execution time",
+            )
+            .buckets(vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0]),
         )?;
-        
-        let db_errors_total = IntCounter::with_opts(
-            Opts::new("gas_relayer_db_errors_total", "Total number of database errors")
-        )?;
-        
+
+        let db_errors_total = IntCounter::with_opts(Opts::new(
+            "gas_relayer_db_errors_total",
+            "Total number of database errors",
+        ))?;
+
         // RPC metrics
-        let rpc_requests_total = IntCounter::with_opts(
-            Opts::new("gas_relayer_rpc_requests_total", "Total number of RPC requests")
+        let rpc_requests_total = IntCounterVec::new(
+            Opts::new(
+                "gas_relayer_rpc_requests_total",
+                "Total number of RPC requests",
+            ),
+            &CHAIN_LABEL,
         )?;
-        
-        let rpc_errors_total = IntCounter::with_opts(
-            Opts::new("gas_relayer_rpc_errors_total", "Total number of RPC errors")
+
+        let rpc_errors_total = IntCounterVec::new(
+            Opts::new("gas_relayer_rpc_errors_total", "Total number of RPC errors"),
+            &CHAIN_LABEL,
         )?;
-        
-        let rpc_latency = Histogram::with_opts(
+
+        let rpc_latency = HistogramVec::new(
             HistogramOpts::new("gas_relayer_rpc_latency_seconds", "RPC request latency")
-                .buckets(vec![0.1, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0])
+                .buckets(vec![0.1, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0]),
+            &CHAIN_LABEL,
+        )?;
+
+        // HTTP metrics
+        let http_requests_total = IntCounterVec::new(
+            Opts::new(
+                "http_requests_total",
+                "Total number of HTTP requests served",
+            ),
+            &["method", "route", "status"],
         )?;
-        
+
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency")
+                .buckets(vec![0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0]),
+            &["method", "route", "status"],
+        )?;
+
         // Relayer metrics
-        let relayer_balance = Gauge::with_opts(
-            Opts::new("gas_relayer_balance_eth", "Relayer wallet balance in ETH")
+        let relayer_balance = GaugeVec::new(
+            Opts::new("gas_relayer_balance_eth", "Relayer wallet balance in ETH"),
+            &CHAIN_LABEL,
         )?;
-        
-        let relayer_nonce_current = IntGauge::with_opts(
-            Opts::new("gas_relayer_nonce_current", "Current nonce of relayer wallet")
+
+        let relayer_nonce_current = IntGaugeVec::new(
+            Opts::new(
+                "gas_relayer_nonce_current",
+                "Current nonce of relayer wallet",
+            ),
+            &CHAIN_LABEL,
         )?;
-        
-        let relayer_tx_sent = IntCounter::with_opts(
-            Opts::new("gas_relayer_tx_sent_total", "Total transactions sent by relayer")
+
+        let relayer_tx_sent = IntCounterVec::new(
+            Opts::new(
+                "gas_relayer_tx_sent_total",
+                "Total transactions sent by relayer",
+            ),
+            &CHAIN_LABEL,
         )?;
-        
+
         // Security metrics
-        let invalid_signatures = IntCounter::with_opts(
-            Opts::new("gas_relayer_invalid_signatures_total", "Total number of invalid signatures detected")
-        )?;
-        
-        let replay_attacks = IntCounter::with_opts(
-            Opts::new("gas_relayer_replay_attacks_total", "Total number of replay attacks detected")
+        let invalid_signatures = IntCounter::with_opts(Opts::new(
+            "gas_relayer_invalid_signatures_total",
+            "Total number of invalid signatures detected",
+        ))?;
+
+        let replay_attacks = IntCounter::with_opts(Opts::new(
+            "gas_relayer_replay_attacks_total",
+            "Total number of replay attacks detected",
+        ))?;
+
+        let rate_limit_hits = IntCounter::with_opts(Opts::new(
+            "gas_relayer_rate_limit_hits_total",
+            "Total number of rate limit violations",
+        ))?;
+
+        // Webhook metrics
+        let webhook_deliveries = IntCounterVec::new(
+            Opts::new(
+                "gas_relayer_webhook_deliveries_total",
+                "Webhook delivery attempts by outcome",
+            ),
+            &["outcome"],
         )?;
-        
-        let rate_limit_hits = IntCounter::with_opts(
-            Opts::new("gas_relayer_rate_limit_hits_total", "Total number of rate limit violations")
+
+        // Event stream metrics
+        let event_stream_connections = IntGaugeVec::new(
+            Opts::new(
+                "gas_relayer_event_stream_connections",
+                "Open relay event streams by transport",
+            ),
+            &["transport"],
         )?;
-        
+
         // Register all metrics
         registry.register(Box::new(transactions_total.clone()))?;
         registry.register(Box::new(transactions_success.clone()))?;
         registry.register(Box::new(transactions_failed.clone()))?;
         registry.register(Box::new(transactions_pending.clone()))?;
+        registry.register(Box::new(transactions_stuck.clone()))?;
         registry.register(Box::new(transaction_processing_duration.clone()))?;
         registry.register(Box::new(gas_used_total.clone()))?;
         registry.register(Box::new(gas_price_current.clone()))?;
         registry.register(Box::new(gas_limit_violations.clone()))?;
+        registry.register(Box::new(budget_rejections.clone()))?;
+        registry.register(Box::new(simulation_reverts.clone()))?;
         registry.register(Box::new(queue_depth.clone()))?;
         registry.register(Box::new(queue_processing_time.clone()))?;
         registry.register(Box::new(queue_retries_total.clone()))?;
+        registry.register(Box::new(batch_size.clone()))?;
         registry.register(Box::new(db_connections_active.clone()))?;
         registry.register(Box::new(db_query_duration.clone()))?;
         registry.register(Box::new(db_errors_total.clone()))?;
         registry.register(Box::new(rpc_requests_total.clone()))?;
         registry.register(Box::new(rpc_errors_total.clone()))?;
         registry.register(Box::new(rpc_latency.clone()))?;
+        registry.register(Box::new(http_requests_total.clone()))?;
+        registry.register(Box::new(http_request_duration.clone()))?;
         registry.register(Box::new(relayer_balance.clone()))?;
         registry.register(Box::new(relayer_nonce_current.clone()))?;
         registry.register(Box::new(relayer_tx_sent.clone()))?;
         registry.register(Box::new(invalid_signatures.clone()))?;
         registry.register(Box::new(replay_attacks.clone()))?;
         registry.register(Box::new(rate_limit_hits.clone()))?;
-        
+        registry.register(Box::new(webhook_deliveries.clone()))?;
+        registry.register(Box::new(event_stream_connections.clone()))?;
+
         Ok(Self {
             registry,
             transactions_total,
             transactions_success,
             transactions_failed,
             transactions_pending,
+            transactions_stuck,
             transaction_processing_duration,
             gas_used_total,
             gas_price_current,
             gas_limit_violations,
+            budget_rejections,
+            simulation_reverts,
             queue_depth,
             queue_processing_time,
             queue_retries_total,
+            batch_size,
             db_connections_active,
             db_query_duration,
             db_errors_total,
             rpc_requests_total,
             rpc_errors_total,
             rpc_latency,
+            http_requests_total,
+            http_request_duration,
             relayer_balance,
             relayer_nonce_current,
             relayer_tx_sent,
             invalid_signatures,
             replay_attacks,
             rate_limit_hits,
+            webhook_deliveries,
+            event_stream_connections,
         })
     }
-    
+
     pub fn export_metrics(&self) -> anyhow::Result<String> {
         let encoder = TextEncoder::new();
         let metric_families = self.registry.gather();
@@ -266,21 +447,130 @@ impl MetricsCollector {
         encoder.encode(&metric_families, &mut buffer)?;
         Ok(String::from_utf8(buffer)?)
     }
-    
+
+    /// Every series of every registered metric, for persisting outside of Prometheus.
+    pub fn snapshot(&self) -> Vec<MetricSample> {
+        let mut samples = Vec::new();
+        for family in self.registry.gather() {
+            for metric in family.get_metric() {
+                let value = match family.get_field_type() {
+                    MetricType::COUNTER => MetricValue::Counter(metric.get_counter().get_value()),
+                    MetricType::GAUGE => MetricValue::Gauge(metric.get_gauge().get_value()),
+                    MetricType::HISTOGRAM => MetricValue::Histogram {
+                        count: metric.get_histogram().get_sample_count(),
+                        sum: metric.get_histogram().get_sample_sum(),
+                    },
+                    _ => continue,
+                };
+                let labels = metric
+                    .get_label()
+                    .iter()
+                    .map(|label| (label.get_name().to_string(), label.get_value().to_string()))
+                    .collect();
+                samples.push(MetricSample {
+                    name: family.get_name().to_string(),
+                    labels,
+                    value,
+                });
+            }
+        }
+        samples
+    }
+
+    /// The per-chain series of `chain_id`, created at zero if they do not exist yet.
+    pub fn chain(&self, chain_id: u64) -> ChainMetrics {
+        let label = chain_id.to_string();
+        let labels = [label.as_str()];
+        ChainMetrics {
+            chain_id,
+            transactions_total: self.transactions_total.with_label_values(&labels),
+            transactions_success: self.transactions_success.with_label_values(&labels),
+            transactions_failed: self.transactions_failed.with_label_values(&labels),
+            transactions_pending: self.transactions_pending.with_label_values(&labels),
+            transactions_stuck: self.transactions_stuck.with_label_values(&labels),
+            transaction_processing_duration: self
+                .transaction_processing_duration
+                .with_label_values(&labels),
+            gas_used_total: self.gas_used_total.with_label_values(&labels),
+            gas_price_current: self.gas_price_current.with_label_values(&labels),
+            simulation_reverts: self.simulation_reverts.with_label_values(&labels),
+            queue_depth: self.queue_depth.with_label_values(&labels),
+            queue_processing_time: self.queue_processing_time.with_label_values(&labels),
+            queue_retries_total: self.queue_retries_total.with_label_values(&labels),
+            batch_size: self.batch_size.with_label_values(&labels),
+            rpc_requests_total: self.rpc_requests_total.with_label_values(&labels),
+            rpc_errors_total: self.rpc_errors_total.with_label_values(&labels),
+            rpc_latency: self.rpc_latency.with_label_values(&labels),
+            relayer_balance: self.relayer_balance.with_label_values(&labels),
+            relayer_nonce_current: self.relayer_nonce_current.with_label_values(&labels),
+            relayer_tx_sent: self.relayer_tx_sent.with_label_values(&labels),
+        }
+    }
+
     // Helper methods for common metric operations
+    pub fn record_http_request(
+        &self,
+        method: &str,
+        route: &str,
+        status_class: &str,
+        duration: f64,
+    ) {
+        let labels = [method, route, status_class];
+        self.http_requests_total.with_label_values(&labels).inc();
+        self.http_request_duration
+            .with_label_values(&labels)
+            .observe(duration);
+    }
+
+    pub fn record_db_query(&self, duration: f64, success: bool) {
+        self.db_query_duration.observe(duration);
+        if !success {
+            self.db_errors_total.inc();
+        }
+    }
+}
+
+/// The `chain_id` labelled series of one chain, handed to that chain's worker, tracker and RPC pool.
+#[derive(Clone)]
+pub struct ChainMetrics {
+    pub chain_id: u64,
+    pub transactions_total: IntCounter,
+    pub transactions_success: IntCounter,
+    pub transactions_failed: IntCounter,
+    pub transactions_pending: IntGauge,
+    pub transactions_stuck: IntGauge,
+    pub transaction_processing_duration: Histogram,
+    pub gas_used_total: Counter,
+    pub gas_price_current: Gauge,
+    pub simulation_reverts: IntCounter,
+    pub queue_depth: IntGauge,
+    pub queue_processing_time: Histogram,
+    pub queue_retries_total: IntCounter,
+    pub batch_size: Histogram,
+    pub rpc_requests_total: IntCounter,
+    pub rpc_errors_total: IntCounter,
+    pub rpc_latency: Histogram,
+    pub relayer_balance: Gauge,
+    pub relayer_nonce_current: IntGauge,
+    pub relayer_tx_sent: IntCounter,
+}
+
+impl ChainMetrics {
     pub fn record_transaction_success(&self, processing_time: f64, gas_used: f64) {
         self.transactions_total.inc();
         self.transactions_success.inc();
-        self.transaction_processing_duration.observe(processing_time);
+        self.transaction_processing_duration
+            .observe(processing_time);
         self.gas_used_total.inc_by(gas_used);
     }
-    
+
     pub fn record_transaction_failure(&self, processing_time: f64) {
         self.transactions_total.inc();
         self.transactions_failed.inc();
-        self.transaction_processing_duration.observe(processing_time);
+        self.transaction_processing_duration
+            .observe(processing_time);
     }
-    
+
     pub fn record_rpc_call(&self, latency: f64, success: bool) {
         self.rpc_requests_total.inc();
         self.rpc_latency.observe(latency);
@@ -288,11 +578,57 @@ impl MetricsCollector {
             self.rpc_errors_total.inc();
         }
     }
-    
-    pub fn record_db_query(&self, duration: f64, success: bool) {
-        self.db_query_duration.observe(duration);
-        if !success {
-            self.db_errors_total.inc();
-        }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_reads_every_series() {
+        let metrics = MetricsCollector::new().unwrap();
+        let sepolia = metrics.chain(11155111);
+        sepolia.queue_depth.set(3);
+        sepolia.record_rpc_call(0.25, true);
+        sepolia.record_rpc_call(0.75, false);
+        metrics.chain(10).record_rpc_call(0.5, true);
+        metrics.record_http_request("POST", "/relay", "2xx", 0.1);
+
+        let samples = metrics.snapshot();
+        let find = |name: &str| {
+            samples
+                .iter()
+                .find(|s| {
+                    s.name == name && s.labels.get("chain_id").is_none_or(|id| id == "11155111")
+                })
+                .unwrap()
+        };
+
+        assert_eq!(
+            find("gas_relayer_queue_depth").value,
+            MetricValue::Gauge(3.0)
+        );
+        assert_eq!(
+            find("gas_relayer_rpc_errors_total").value,
+            MetricValue::Counter(1.0)
+        );
+        assert_eq!(
+            find("gas_relayer_rpc_latency_seconds").value,
+            MetricValue::Histogram { count: 2, sum: 1.0 }
+        );
+
+        assert_eq!(
+            find("gas_relayer_queue_depth").labels["chain_id"],
+            "11155111"
+        );
+        let rpc_series = samples
+            .iter()
+            .filter(|s| s.name == "gas_relayer_rpc_requests_total")
+            .count();
+        assert_eq!(rpc_series, 2);
+
+        let http = find("http_requests_total");
+        assert_eq!(http.labels["route"], "/relay");
+        assert_eq!(http.labels["status"], "2xx");
     }
-}
\ No newline at end of file
+}
@@ -1,11 +1,14 @@
+use crate::MetricsCollector;
 use axum::{
-    extract::{Request, State},
+    extract::{MatchedPath, Request, State},
     http::StatusCode,
     middleware::Next,
     response::Response,
 };
 use std::time::Instant;
-use crate::MetricsCollector;
+
+/// Route label for requests that matched no route, so unknown paths share one series.
+const UNMATCHED_ROUTE: &str = "unmatched";
 
 pub async fn metrics_middleware(
     State(metrics): State<MetricsCollector>,
@@ -14,41 +17,95 @@ pub async fn metrics_middleware(
 ) -> Response {
     let start = Instant::now();
     let method = request.method().clone();
-    let path = request.uri().path().to_string();
-    
+    // Label with the route template rather than the raw path to keep cardinality bounded
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+
     let response = next.run(request).await;
-    
+
     let duration = start.elapsed().as_secs_f64();
     let status = response.status();
-    
+
     // Record HTTP metrics
-    record_http_request_metrics(&metrics, &method.to_string(), &path, status, duration);
-    
+    record_http_request_metrics(&metrics, method.as_str(), &route, status, duration);
+
     response
 }
 
 fn record_http_request_metrics(
     metrics: &MetricsCollector,
     method: &str,
-    path: &str,
+    route: &str,
     status: StatusCode,
     duration: f64,
 ) {
-    // For now, we'll use the existing RPC metrics as a placeholder
-    // In a full implementation, you'd want dedicated HTTP request metrics
-    metrics.rpc_requests_total.inc();
-    metrics.rpc_latency.observe(duration);
-    
-    if status.is_server_error() || status.is_client_error() {
-        metrics.rpc_errors_total.inc();
-    }
-    
+    metrics.record_http_request(method, route, status_class(status), duration);
+
     // Log the request for debugging
     tracing::info!(
         method = method,
-        path = path,
+        route = route,
         status = status.as_u16(),
         duration_ms = duration * 1000.0,
         "HTTP request processed"
     );
-}
\ No newline at end of file
+}
+
+fn status_class(status: StatusCode) -> &'static str {
+    match status.as_u16() {
+        100..=199 => "1xx",
+        200..=299 => "2xx",
+        300..=399 => "3xx",
+        400..=499 => "4xx",
+        _ => "5xx",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn app(metrics: MetricsCollector) -> Router {
+        Router::new()
+            .route("/relay/{id}", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(metrics, metrics_middleware))
+    }
+
+    async fn get_path(app: Router, path: &str) -> StatusCode {
+        let request = Request::builder().uri(path).body(Body::empty()).unwrap();
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn labels_requests_by_route_template() {
+        let metrics = MetricsCollector::new().unwrap();
+
+        assert_eq!(
+            get_path(app(metrics.clone()), "/relay/1").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            get_path(app(metrics.clone()), "/relay/2").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            get_path(app(metrics.clone()), "/nope").await,
+            StatusCode::NOT_FOUND
+        );
+
+        let served = |route: &str, status: &str| {
+            metrics
+                .http_requests_total
+                .with_label_values(&["GET", route, status])
+                .get()
+        };
+        assert_eq!(served("/relay/{id}", "2xx"), 2);
+        assert_eq!(served(UNMATCHED_ROUTE, "4xx"), 1);
+        assert_eq!(metrics.chain(11155111).rpc_requests_total.get(), 0);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::rules::{Policy, PolicyViolation};
+use config::config::{PolicyConfig, PolicySource};
+use db::policies::PolicyRepository;
+use forwarder::ForwardRequestData;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Holds the active relay policy and re-reads its source on an interval, so edits to the
+/// file or new `relay_policies` rows apply without a restart.
+#[derive(Clone)]
+pub struct PolicyEngine {
+    source: PolicySource,
+    interval: Duration,
+    policies: PolicyRepository,
+    active: Arc<RwLock<Arc<Policy>>>,
+}
+
+impl PolicyEngine {
+    /// Reads the configured source. A policy that does not parse fails startup.
+    pub async fn load(config: &PolicyConfig, policies: PolicyRepository) -> anyhow::Result<Self> {
+        let engine = Self {
+            source: config.source.clone(),
+            interval: config.reload_interval,
+            policies,
+            active: Arc::new(RwLock::new(Arc::new(Policy::default()))),
+        };
+
+        let policy = engine.read().await?;
+        if engine.source == PolicySource::Database && policy == Policy::default() {
+            tracing::warn!("relay_policies holds no policy, every signed request is relayed");
+        }
+        *engine.active.write().expect("policy lock poisoned") = Arc::new(policy);
+        Ok(engine)
+    }
+
+    pub fn current(&self) -> Arc<Policy> {
+        self.active.read().expect("policy lock poisoned").clone()
+    }
+
+    pub fn evaluate(
+        &self,
+        chain_id: u64,
+        request: &ForwardRequestData,
+    ) -> Result<(), PolicyViolation> {
+        self.current().evaluate(chain_id, request)
+    }
+
+    /// Re-reads the source and swaps in the policy if it changed. Returns whether it did.
+    /// On error the active policy stays in place.
+    pub async fn reload(&self) -> anyhow::Result<bool> {
+        let policy = self.read().await?;
+        let mut active = self.active.write().expect("policy lock poisoned");
+        if **active == policy {
+            return Ok(false);
+        }
+        *active = Arc::new(policy);
+        tracing::info!(source = ?self.source, "Relay policy reloaded");
+        Ok(true)
+    }
+
+    pub fn spawn(self, shutdown: watch::Receiver<bool>) -> JoinHandle<()> {
+        tokio::spawn(self.run(shutdown))
+    }
+
+    pub async fn run(self, mut shutdown: watch::Receiver<bool>) {
+        if self.source == PolicySource::None {
+            return;
+        }
+
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(self.interval) => {}
+                _ = shutdown.changed() => {}
+            }
+            if *shutdown.borrow() {
+                break;
+            }
+
+            if let Err(e) = self.reload().await {
+                tracing::error!(
+                    "Failed to reload relay policy, keeping the active one: {}",
+                    e
+                );
+            }
+        }
+    }
+
+    async fn read(&self) -> anyhow::Result<Policy> {
+        match &self.source {
+            PolicySource::None => Ok(Policy::default()),
+            PolicySource::File(path) => Policy::from_file(path),
+            PolicySource::Database => match self.policies.latest().await? {
+                Some(stored) => Policy::from_json(stored.document).map_err(|e| {
+                    anyhow::anyhow!("relay policy version {} is invalid: {e}", stored.id)
+                }),
+                None => Ok(Policy::default()),
+            },
+        }
+    }
+}
@@ -0,0 +1,460 @@
+use alloy::primitives::{keccak256, Address, U256};
+use forwarder::{CustomError, ForwardRequestData};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Contents of the policy file, or the `document` of a `relay_policies` row.
+///
+/// ```toml
+/// max_gas = 500000
+///
+/// [senders]
+/// deny = ["0x0000000000000000000000000000000000000bad"]
+///
+/// [[contracts]]
+/// address = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+/// selectors = ["setMessage(string)", "incrementCounter()"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    /// Gas limit of any request; a contract rule may set a lower one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_gas: Option<u64>,
+    /// Wei any request may forward, as a decimal string.
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "wei")]
+    pub max_value: Option<U256>,
+    #[serde(default)]
+    pub senders: SenderRules,
+    /// Allowed targets. Without any, every target is allowed.
+    #[serde(default)]
+    pub contracts: Vec<ContractRule>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SenderRules {
+    /// If not empty, only these senders are relayed.
+    #[serde(default)]
+    pub allow: Vec<Address>,
+    /// Never relayed, even when also allowed.
+    #[serde(default)]
+    pub deny: Vec<Address>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContractRule {
+    pub address: Address,
+    /// Limits the rule to one chain; it applies on every chain without.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    /// Callable functions. Without any, every function is allowed.
+    #[serde(default)]
+    pub selectors: Vec<Selector>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_gas: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "wei")]
+    pub max_value: Option<U256>,
+    /// Custom errors of the contract by signature, named in simulated reverts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<CustomError>,
+}
+
+/// A 4-byte function selector, written as hex (`0xd09de08a`) or as the signature
+/// it is derived from (`incrementCounter()`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Selector(pub [u8; 4]);
+
+/// Why a request was refused. Serialized with a `code` tag for API clients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum PolicyViolation {
+    SenderDenied {
+        from: Address,
+    },
+    SenderNotAllowed {
+        from: Address,
+    },
+    TargetNotAllowed {
+        to: Address,
+    },
+    SelectorNotAllowed {
+        to: Address,
+        /// `None` when the call data is too short to hold one.
+        selector: Option<Selector>,
+    },
+    ValueTooHigh {
+        value: String,
+        max_value: String,
+    },
+    GasTooHigh {
+        gas: u64,
+        max_gas: u64,
+    },
+}
+
+impl Policy {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("failed to read {}: {e}", path.display()))?;
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> anyhow::Result<Self> {
+        let policy: Policy = toml::from_str(contents)?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    pub fn from_json(document: serde_json::Value) -> anyhow::Result<Self> {
+        let policy: Policy = serde_json::from_value(document)?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        let mut targets = HashSet::new();
+        for contract in &self.contracts {
+            anyhow::ensure!(
+                targets.insert((contract.address, contract.chain_id)),
+                "duplicate contract rule for {}",
+                contract.address
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks a signed request bound for `chain_id`: senders first, then the target and
+    /// its function, then value and gas.
+    pub fn evaluate(
+        &self,
+        chain_id: u64,
+        request: &ForwardRequestData,
+    ) -> Result<(), PolicyViolation> {
+        let from = request.from;
+        if self.senders.deny.contains(&from) {
+            return Err(PolicyViolation::SenderDenied { from });
+        }
+        if !self.senders.allow.is_empty() && !self.senders.allow.contains(&from) {
+            return Err(PolicyViolation::SenderNotAllowed { from });
+        }
+
+        let contract = self.contract(chain_id, request.to);
+        if !self.contracts.is_empty() && contract.is_none() {
+            return Err(PolicyViolation::TargetNotAllowed { to: request.to });
+        }
+
+        if let Some(contract) = contract {
+            if !contract.selectors.is_empty() {
+                let selector = Selector::of(&request.data);
+                if !selector.is_some_and(|s| contract.selectors.contains(&s)) {
+                    return Err(PolicyViolation::SelectorNotAllowed {
+                        to: request.to,
+                        selector,
+                    });
+                }
+            }
+        }
+
+        let max_value = lowest(self.max_value, contract.and_then(|c| c.max_value));
+        if let Some(max_value) = max_value.filter(|max| request.value > *max) {
+            return Err(PolicyViolation::ValueTooHigh {
+                value: request.value.to_string(),
+                max_value: max_value.to_string(),
+            });
+        }
+
+        let max_gas = lowest(self.max_gas, contract.and_then(|c| c.max_gas));
+        if let Some(max_gas) = max_gas.filter(|max| request.gas > U256::from(*max)) {
+            return Err(PolicyViolation::GasTooHigh {
+                gas: request.gas.saturating_to(),
+                max_gas,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Custom errors declared for the target `to` on `chain_id`.
+    pub fn target_errors(&self, chain_id: u64, to: Address) -> &[CustomError] {
+        self.contract(chain_id, to)
+            .map(|contract| contract.errors.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The rule for `to`, preferring one limited to `chain_id` over one for every chain.
+    fn contract(&self, chain_id: u64, to: Address) -> Option<&ContractRule> {
+        let mut matching = self.contracts.iter().filter(|c| c.address == to);
+        matching
+            .clone()
+            .find(|c| c.chain_id == Some(chain_id))
+            .or_else(|| matching.find(|c| c.chain_id.is_none()))
+    }
+}
+
+impl Selector {
+    /// The first four bytes of call data, if there are that many.
+    pub fn of(data: &[u8]) -> Option<Self> {
+        data.get(..4)
+            .map(|bytes| Self(bytes.try_into().expect("four bytes")))
+    }
+}
+
+impl FromStr for Selector {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Some(hex) = value.strip_prefix("0x") {
+            let bytes =
+                alloy::hex::decode(hex).map_err(|e| format!("invalid selector {value}: {e}"))?;
+            return <[u8; 4]>::try_from(bytes)
+                .map(Self)
+                .map_err(|_| format!("selector {value} must be 4 bytes"));
+        }
+
+        let is_signature = value
+            .split_once('(')
+            .is_some_and(|(name, _)| !name.is_empty() && value.ends_with(')'))
+            && !value.contains(char::is_whitespace);
+        if !is_signature {
+            return Err(format!(
+                "selector {value} must be 0x-prefixed hex or a signature like transfer(address,uint256)"
+            ));
+        }
+        Ok(Self::of(keccak256(value.as_bytes()).as_slice()).expect("hash is 32 bytes"))
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", alloy::hex::encode(self.0))
+    }
+}
+
+impl Serialize for Selector {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Selector {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyViolation::SenderDenied { from } => write!(f, "sender {from} is denied"),
+            PolicyViolation::SenderNotAllowed { from } => {
+                write!(f, "sender {from} is not on the allowlist")
+            }
+            PolicyViolation::TargetNotAllowed { to } => {
+                write!(f, "contract {to} is not sponsored")
+            }
+            PolicyViolation::SelectorNotAllowed {
+                to,
+                selector: Some(selector),
+            } => {
+                write!(f, "function {selector} of {to} is not sponsored")
+            }
+            PolicyViolation::SelectorNotAllowed { to, selector: None } => {
+                write!(f, "call to {to} has no function selector")
+            }
+            PolicyViolation::ValueTooHigh { value, max_value } => {
+                write!(f, "value {value} exceeds the maximum of {max_value} wei")
+            }
+            PolicyViolation::GasTooHigh { gas, max_gas } => {
+                write!(f, "gas {gas} exceeds the maximum of {max_gas}")
+            }
+        }
+    }
+}
+
+fn lowest<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Wei amounts as decimal strings, which TOML and JSON both hold without losing precision.
+mod wei {
+    use alloy::primitives::U256;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<U256>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.collect_str(value),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<U256>, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        U256::from_str_radix(&value, 10)
+            .map(Some)
+            .map_err(|e| serde::de::Error::custom(format!("invalid wei amount {value}: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{address, Bytes};
+
+    const COUNTER: Address = address!("0x5FbDB2315678afecb367f032d93F642f64180aa3");
+    const USER: Address = address!("0x70997970C51812dc3A010C7d01b50e0d17dc79C8");
+
+    fn request(to: Address, data: &[u8], gas: u64) -> ForwardRequestData {
+        ForwardRequestData {
+            from: USER,
+            to,
+            value: U256::ZERO,
+            gas: U256::from(gas),
+            nonce: U256::ZERO,
+            deadline: 1893456000,
+            data: Bytes::copy_from_slice(data),
+            signature: Bytes::new(),
+        }
+    }
+
+    fn policy() -> Policy {
+        Policy::parse(
+            r#"
+            max_gas = 500000
+            max_value = "0"
+
+            [senders]
+            deny = ["0x0000000000000000000000000000000000000bad"]
+
+            [[contracts]]
+            address = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+            selectors = ["setMessage(string)", "0xd09de08a"]
+            max_gas = 200000
+            errors = ["MessageTooLong(uint256)"]
+
+            [[contracts]]
+            address = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+            chain_id = 10
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_signatures_and_hex_selectors() {
+        let increment: Selector = "incrementCounter()".parse().unwrap();
+        assert_eq!(increment.to_string(), "0x5b34b966");
+        assert_eq!("0x5b34b966".parse::<Selector>().unwrap(), increment);
+        assert!("0x5b34".parse::<Selector>().is_err());
+        assert!("incrementCounter".parse::<Selector>().is_err());
+    }
+
+    #[test]
+    fn finds_the_errors_of_a_target() {
+        let policy = policy();
+        let errors = policy.target_errors(11155111, COUNTER);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].to_string(), "MessageTooLong(uint256)");
+        // The chain specific rule declares none
+        assert!(policy.target_errors(10, COUNTER).is_empty());
+        assert!(Policy::parse("[[contracts]]\naddress = \"0x5FbDB2315678afecb367f032d93F642f64180aa3\"\nerrors = [\"Bad(\"]").is_err());
+    }
+
+    #[test]
+    fn allows_listed_functions_within_limits() {
+        let policy = policy();
+        let set_message: Selector = "setMessage(string)".parse().unwrap();
+        assert_eq!(
+            policy.evaluate(11155111, &request(COUNTER, &set_message.0, 100_000)),
+            Ok(())
+        );
+
+        assert_eq!(
+            policy.evaluate(
+                11155111,
+                &request(COUNTER, &[0xde, 0xad, 0xbe, 0xef], 100_000)
+            ),
+            Err(PolicyViolation::SelectorNotAllowed {
+                to: COUNTER,
+                selector: Some(Selector([0xde, 0xad, 0xbe, 0xef])),
+            })
+        );
+        assert_eq!(
+            policy.evaluate(11155111, &request(COUNTER, &set_message.0, 300_000)),
+            Err(PolicyViolation::GasTooHigh {
+                gas: 300_000,
+                max_gas: 200_000
+            })
+        );
+        // The chain specific rule allows any function, up to the global gas limit.
+        assert_eq!(
+            policy.evaluate(10, &request(COUNTER, &[0xde, 0xad, 0xbe, 0xef], 300_000)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn rejects_senders_targets_and_value() {
+        let policy = policy();
+        let other = address!("0x9fE46736679d2D9a65F0992F2272dE9f3c7fa6e0");
+        assert_eq!(
+            policy.evaluate(1, &request(other, &[], 21_000)),
+            Err(PolicyViolation::TargetNotAllowed { to: other })
+        );
+
+        let mut paying = request(COUNTER, &[0xd0, 0x9d, 0xe0, 0x8a], 21_000);
+        paying.value = U256::from(1);
+        assert_eq!(
+            policy.evaluate(1, &paying),
+            Err(PolicyViolation::ValueTooHigh {
+                value: "1".to_string(),
+                max_value: "0".to_string(),
+            })
+        );
+
+        let mut denied = request(COUNTER, &[0xd0, 0x9d, 0xe0, 0x8a], 21_000);
+        denied.from = address!("0x0000000000000000000000000000000000000bad");
+        let violation = policy.evaluate(1, &denied).unwrap_err();
+        assert_eq!(
+            serde_json::to_value(&violation).unwrap(),
+            serde_json::json!({
+                "code": "sender_denied",
+                "from": "0x0000000000000000000000000000000000000bad",
+            })
+        );
+    }
+
+    #[test]
+    fn an_empty_policy_allows_everything() {
+        let policy = Policy::default();
+        assert_eq!(policy.evaluate(1, &request(USER, &[], 10_000_000)), Ok(()));
+        assert!(Policy::parse("unknown = 1").is_err());
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let policy = policy();
+        let document = serde_json::to_value(&policy).unwrap();
+        assert_eq!(document["contracts"][0]["selectors"][1], "0xd09de08a");
+        assert_eq!(Policy::from_json(document).unwrap(), policy);
+    }
+
+    #[test]
+    fn parses_the_example_file() {
+        let policy = Policy::parse(include_str!("../../../policy.example.toml")).unwrap();
+        assert!(!policy.contracts.is_empty());
+    }
+}
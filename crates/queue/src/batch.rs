@@ -0,0 +1,541 @@
+use alloy::primitives::{Address, Bytes, U64};
+use alloy::rpc::types::TransactionReceipt;
+use alloy::sol_types::SolCall;
+use config::config::BatchingConfig;
+use db::db::TxRequest;
+use forwarder::TrustedForwarder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// Gas of an `executeBatch` transaction besides its requests: intrinsic cost, loop and value check.
+const BATCH_GAS_OVERHEAD: u64 = 50_000;
+
+/// Gas the forwarder spends on each request of a batch: calldata, signature check, nonce
+/// bump, call and event.
+const BATCH_REQUEST_OVERHEAD: u64 = 60_000;
+
+/// Gas limit of the `executeBatch` transaction relaying requests with these `gas` values.
+/// As with `execute_gas_limit`, each call keeps 1/63 back for the forwarder (EIP-150).
+/// Saturates at `u64::MAX`, which no gas ceiling lets through.
+pub fn batch_gas_limit(gases: impl IntoIterator<Item = u64>) -> u64 {
+    gases.into_iter().fold(BATCH_GAS_OVERHEAD, |limit, gas| {
+        limit.saturating_add(
+            gas.saturating_add(gas / 63)
+                .saturating_add(BATCH_REQUEST_OVERHEAD),
+        )
+    })
+}
+
+/// Splits `gas_used` in proportion to `weights`. The shares add up to `gas_used` exactly,
+/// so the tenants' ledger adds up to what the transaction cost.
+pub fn attribute_gas(gas_used: u64, weights: &[u64]) -> Vec<u64> {
+    let total: u128 = weights.iter().map(|&weight| weight as u128).sum();
+    if total == 0 {
+        return vec![0; weights.len()];
+    }
+
+    let mut shares: Vec<u64> = weights
+        .iter()
+        .map(|&weight| (gas_used as u128 * weight as u128 / total) as u64)
+        .collect();
+    // Less than one gas per request is lost to rounding; it goes to the first ones
+    let rest = gas_used - shares.iter().sum::<u64>();
+    for share in shares.iter_mut().take(rest as usize) {
+        *share += 1;
+    }
+    shares
+}
+
+/// Collects claimed requests into one batch until it is full or its window ran out.
+pub struct BatchBuilder {
+    config: BatchingConfig,
+    requests: Vec<TxRequest>,
+    signers: HashSet<String>,
+    opened_at: Option<Instant>,
+}
+
+impl BatchBuilder {
+    pub fn new(config: BatchingConfig) -> Self {
+        Self {
+            config,
+            requests: Vec::new(),
+            signers: HashSet::new(),
+            opened_at: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// Adds a request, first closing the open batch if the request does not fit in it:
+    /// it would pass the gas ceiling, or its signer already has a request in it. With one
+    /// request per signer the receipt tells which request the forwarder skipped.
+    pub fn push(&mut self, request: TxRequest, now: Instant) -> Option<Vec<TxRequest>> {
+        let fits = self.requests.is_empty()
+            || (!self.signers.contains(&request.from_address)
+                && batch_gas_limit(
+                    self.requests
+                        .iter()
+                        .chain([&request])
+                        .map(|request| request.gas as u64),
+                ) <= self.config.max_gas);
+        let closed = if fits { None } else { Some(self.take()) };
+
+        if self.requests.is_empty() {
+            self.opened_at = Some(now);
+        }
+        self.signers.insert(request.from_address.clone());
+        self.requests.push(request);
+        closed
+    }
+
+    /// The open batch, once it holds `max_requests` or its first request waited the window out.
+    pub fn ready(&mut self, now: Instant) -> Option<Vec<TxRequest>> {
+        let due = self
+            .opened_at
+            .is_some_and(|opened_at| now >= opened_at + self.config.window);
+        if due || self.requests.len() >= self.config.max_requests {
+            Some(self.take())
+        } else {
+            None
+        }
+    }
+
+    /// How long until the open batch is due, if there is one.
+    pub fn time_left(&self, now: Instant) -> Option<Duration> {
+        self.opened_at
+            .map(|opened_at| (opened_at + self.config.window).saturating_duration_since(now))
+    }
+
+    pub fn take(&mut self) -> Vec<TxRequest> {
+        self.signers.clear();
+        self.opened_at = None;
+        std::mem::take(&mut self.requests)
+    }
+}
+
+/// Options of `debug_traceTransaction` asking for geth's call tracer.
+#[derive(Debug, Clone, Serialize)]
+pub struct CallTracer {
+    tracer: &'static str,
+}
+
+impl Default for CallTracer {
+    fn default() -> Self {
+        Self {
+            tracer: "callTracer",
+        }
+    }
+}
+
+/// A call of a transaction traced with `CallTracer`, with the calls it made.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+    #[serde(default)]
+    pub to: Option<Address>,
+    #[serde(default)]
+    pub input: Bytes,
+    pub gas_used: U64,
+    #[serde(default)]
+    pub calls: Vec<CallFrame>,
+}
+
+/// What became of one request of a mined `executeBatch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchedOutcome {
+    /// Share of the transaction's gas: see `MinedBatch::attribute`.
+    pub gas_used: u64,
+    /// Whether the target call succeeded; `None` if the forwarder skipped the request as
+    /// invalid or the transaction reverted.
+    pub executed: Option<bool>,
+}
+
+/// The requests of a mined `executeBatch` transaction and how each fared, read from its
+/// calldata and the forwarder's `ExecutedForwardRequest` events.
+///
+/// Until `attribute` is given a trace, the gas is split by the gas limit of each request,
+/// as nothing else in the receipt tells the requests' execution apart.
+#[derive(Debug)]
+pub struct MinedBatch {
+    requests: Vec<TrustedForwarder::ForwardRequestData>,
+    gas_used: u64,
+    outcomes: Vec<BatchedOutcome>,
+}
+
+impl MinedBatch {
+    /// `None` unless `input` is an `executeBatch` call.
+    pub fn new(input: &[u8], receipt: &TransactionReceipt) -> Option<Self> {
+        let requests = TrustedForwarder::executeBatchCall::abi_decode(input)
+            .ok()?
+            .requests;
+        let weights: Vec<u64> = requests
+            .iter()
+            .map(|request| request.gas.saturating_to::<u64>() + BATCH_REQUEST_OVERHEAD)
+            .collect();
+        let shares = attribute_gas(receipt.gas_used, &weights);
+
+        // Events come in request order, and skipped requests emit none
+        let mut executed = receipt
+            .inner
+            .logs()
+            .iter()
+            .filter(|log| Some(log.address()) == receipt.to)
+            .filter_map(|log| {
+                log.log_decode::<TrustedForwarder::ExecutedForwardRequest>()
+                    .ok()
+            })
+            .map(|log| log.inner.data)
+            .peekable();
+        let outcomes = requests
+            .iter()
+            .zip(shares)
+            .map(|(request, gas_used)| BatchedOutcome {
+                gas_used,
+                executed: executed
+                    .next_if(|event| event.signer == request.from)
+                    .map(|event| event.success),
+            })
+            .collect();
+
+        Some(Self {
+            requests,
+            gas_used: receipt.gas_used,
+            outcomes,
+        })
+    }
+
+    /// Splits the gas by what each request actually used, read from the transaction's call
+    /// trace: each pays for its own call to the target, and the rest of the transaction
+    /// (intrinsic cost, calldata, signature checks) is shared equally. A trace that does
+    /// not add up leaves the split by gas limit in place.
+    pub fn attribute(&mut self, trace: &CallFrame) {
+        // Targets are called in request order with the signer appended (ERC-2771)
+        let mut calls = trace.calls.iter();
+        let execution: Vec<u64> = self
+            .requests
+            .iter()
+            .zip(&self.outcomes)
+            .map(|(request, outcome)| {
+                if outcome.executed.is_none() {
+                    return 0;
+                }
+                let input = [request.data.as_ref(), request.from.as_slice()].concat();
+                calls
+                    .by_ref()
+                    .find(|call| call.to == Some(request.to) && call.input[..] == input[..])
+                    .map_or(0, |call| call.gas_used.saturating_to())
+            })
+            .collect();
+
+        let executed = execution
+            .iter()
+            .fold(0u64, |total, &gas| total.saturating_add(gas));
+        if executed > self.gas_used {
+            return;
+        }
+        let shared = attribute_gas(self.gas_used - executed, &vec![1; execution.len()]);
+        for ((outcome, own), shared) in self.outcomes.iter_mut().zip(execution).zip(shared) {
+            outcome.gas_used = own + shared;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outcomes.is_empty()
+    }
+
+    /// The outcome of the request with this signature, which is unique to it.
+    pub fn outcome_of(&self, signature: &[u8]) -> Option<BatchedOutcome> {
+        self.requests
+            .iter()
+            .position(|request| request.signature.as_ref() == signature)
+            .map(|index| self.outcomes[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::aliases::U48;
+    use alloy::primitives::{address, Address, U256};
+    use alloy::sol_types::SolEvent;
+    use uuid::Uuid;
+
+    const FORWARDER: Address = address!("0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f");
+    const ALICE: Address = address!("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+    const BOB: Address = address!("0x70997970C51812dc3A010C7d01b50e0d17dc79C8");
+    const CAROL: Address = address!("0x3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+
+    fn config() -> BatchingConfig {
+        BatchingConfig {
+            enabled: true,
+            max_gas: batch_gas_limit([100_000, 100_000, 100_000]),
+            window: Duration::from_secs(2),
+            max_requests: 3,
+            refund_receiver: None,
+        }
+    }
+
+    fn request(from: Address, gas: i64) -> TxRequest {
+        let now = chrono::Utc::now();
+        TxRequest {
+            id: Uuid::nil(),
+            chain_id: 11155111,
+            tenant_id: Uuid::nil(),
+            from_address: from.to_checksum(None),
+            to_address: FORWARDER.to_checksum(None),
+            value: "0".to_string(),
+            gas,
+            deadline: 1_900_000_000,
+            data: vec![],
+            signature: vec![0xab; 65],
+            status: db::db::TxStatus::Processing,
+            tx_hash: None,
+            gas_used: None,
+            effective_gas_price: None,
+            block_number: None,
+            nonce: None,
+            error: None,
+            retry_count: 0,
+            estimated_cost_wei: None,
+            callback_url: None,
+            batch_id: None,
+            batch_index: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn closes_batches_at_the_gas_ceiling_and_on_repeated_signers() {
+        let now = Instant::now();
+        let mut builder = BatchBuilder::new(config());
+        assert!(builder.push(request(ALICE, 100_000), now).is_none());
+        assert!(builder.push(request(BOB, 100_000), now).is_none());
+
+        // Alice's second request waits for the next batch
+        let closed = builder.push(request(ALICE, 100_000), now).unwrap();
+        assert_eq!(closed.len(), 2);
+
+        // So does a request that would take the batch over the ceiling
+        let closed = builder.push(request(BOB, 300_000), now).unwrap();
+        assert_eq!(closed.len(), 1);
+        assert_eq!(builder.take().len(), 1);
+        assert!(builder.is_empty());
+
+        // A request over the ceiling on its own still goes out, alone
+        assert!(builder.push(request(ALICE, 5_000_000), now).is_none());
+        assert_eq!(builder.take().len(), 1);
+    }
+
+    #[test]
+    fn gas_limits_near_the_maximum_do_not_overflow() {
+        assert_eq!(
+            batch_gas_limit([i64::MAX as u64, i64::MAX as u64]),
+            u64::MAX
+        );
+        assert_eq!(batch_gas_limit([u64::MAX]), u64::MAX);
+
+        let now = Instant::now();
+        let mut builder = BatchBuilder::new(config());
+        assert!(builder.push(request(ALICE, i64::MAX), now).is_none());
+        let closed = builder.push(request(BOB, i64::MAX), now).unwrap();
+        assert_eq!(closed.len(), 1);
+    }
+
+    #[test]
+    fn releases_full_and_due_batches() {
+        let now = Instant::now();
+        let mut builder = BatchBuilder::new(config());
+        assert!(builder.ready(now).is_none());
+        assert_eq!(builder.time_left(now), None);
+
+        builder.push(request(ALICE, 10_000), now);
+        builder.push(request(BOB, 10_000), now + Duration::from_secs(1));
+        assert!(builder.ready(now + Duration::from_secs(1)).is_none());
+        assert_eq!(
+            builder.time_left(now + Duration::from_secs(1)),
+            Some(Duration::from_secs(1))
+        );
+        // The window starts with the first request
+        assert_eq!(
+            builder
+                .ready(now + Duration::from_secs(2))
+                .map(|batch| batch.len()),
+            Some(2)
+        );
+
+        for from in [ALICE, BOB, CAROL] {
+            builder.push(request(from, 10_000), now);
+        }
+        assert_eq!(builder.ready(now).map(|batch| batch.len()), Some(3));
+    }
+
+    #[test]
+    fn attributes_all_of_the_gas() {
+        assert_eq!(attribute_gas(300_000, &[1, 1, 1]), vec![100_000; 3]);
+        assert_eq!(attribute_gas(10, &[1, 1, 1]), vec![4, 3, 3]);
+        assert_eq!(attribute_gas(1_000, &[3, 1]), vec![750, 250]);
+        assert!(attribute_gas(1_000, &[]).is_empty());
+    }
+
+    fn forward_request(
+        from: Address,
+        gas: u64,
+        signature: u8,
+    ) -> TrustedForwarder::ForwardRequestData {
+        TrustedForwarder::ForwardRequestData {
+            from,
+            to: FORWARDER,
+            value: U256::ZERO,
+            gas: U256::from(gas),
+            deadline: U48::from(1_900_000_000u64),
+            data: Bytes::new(),
+            signature: Bytes::from(vec![signature; 65]),
+        }
+    }
+
+    fn executed_log(signer: Address, success: bool) -> serde_json::Value {
+        let event = TrustedForwarder::ExecutedForwardRequest {
+            signer,
+            nonce: U256::from(1),
+            success,
+        };
+        let log = event.encode_log_data();
+        serde_json::json!({
+            "address": FORWARDER,
+            "topics": log.topics(),
+            "data": log.data,
+            "blockHash": format!("0x{}", "0b".repeat(32)),
+            "blockNumber": "0x10",
+            "blockTimestamp": null,
+            "transactionHash": format!("0x{}", "0a".repeat(32)),
+            "transactionIndex": "0x0",
+            "logIndex": "0x0",
+            "removed": false,
+        })
+    }
+
+    fn receipt(gas_used: u64, logs: Vec<serde_json::Value>) -> TransactionReceipt {
+        serde_json::from_value(serde_json::json!({
+            "type": "0x2",
+            "status": "0x1",
+            "cumulativeGasUsed": format!("{gas_used:#x}"),
+            "logs": logs,
+            "logsBloom": format!("0x{}", "0".repeat(512)),
+            "transactionHash": format!("0x{}", "0a".repeat(32)),
+            "transactionIndex": "0x0",
+            "blockHash": format!("0x{}", "0b".repeat(32)),
+            "blockNumber": "0x10",
+            "gasUsed": format!("{gas_used:#x}"),
+            "effectiveGasPrice": "0x3b9aca00",
+            "from": ALICE,
+            "to": FORWARDER,
+            "contractAddress": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn reads_each_outcome_from_the_receipt() {
+        let input = TrustedForwarder::executeBatchCall {
+            requests: vec![
+                forward_request(ALICE, 40_000, 1),
+                forward_request(BOB, 100_000, 2),
+                forward_request(CAROL, 40_000, 3),
+            ],
+            refundReceiver: ALICE,
+        }
+        .abi_encode();
+        // Bob's request was skipped, Carol's call reverted. Gas is split by each request's
+        // gas plus the forwarder's overhead for it
+        let receipt = receipt(
+            360_000,
+            vec![executed_log(ALICE, true), executed_log(CAROL, false)],
+        );
+
+        let batch = MinedBatch::new(&input, &receipt).unwrap();
+        assert_eq!(batch.len(), 3);
+        assert_eq!(
+            batch.outcome_of(&[1; 65]),
+            Some(BatchedOutcome {
+                gas_used: 100_000,
+                executed: Some(true)
+            })
+        );
+        assert_eq!(
+            batch.outcome_of(&[2; 65]),
+            Some(BatchedOutcome {
+                gas_used: 160_000,
+                executed: None
+            })
+        );
+        assert_eq!(batch.outcome_of(&[3; 65]).unwrap().executed, Some(false));
+        assert_eq!(batch.outcome_of(&[4; 65]), None);
+
+        let execute = TrustedForwarder::executeCall {
+            request: forward_request(ALICE, 40_000, 1),
+        }
+        .abi_encode();
+        assert!(MinedBatch::new(&execute, &receipt).is_none());
+    }
+
+    fn call(to: Address, input: Vec<u8>, gas_used: u64) -> serde_json::Value {
+        serde_json::json!({
+            "type": "CALL",
+            "from": FORWARDER,
+            "to": to,
+            "input": Bytes::from(input),
+            "gas": "0x0",
+            "gasUsed": format!("{gas_used:#x}"),
+        })
+    }
+
+    #[test]
+    fn bills_traced_execution_over_gas_limits() {
+        let target = address!("0x5FbDB2315678afecb367f032d93F642f64180aa3");
+        let mut cheap = forward_request(ALICE, 1_000_000, 1);
+        cheap.to = target;
+        cheap.data = Bytes::from(vec![0xaa]);
+        let mut costly = forward_request(BOB, 100_000, 2);
+        costly.to = target;
+        costly.data = Bytes::from(vec![0xbb]);
+        let input = TrustedForwarder::executeBatchCall {
+            requests: vec![cheap, costly],
+            refundReceiver: ALICE,
+        }
+        .abi_encode();
+        let receipt = receipt(
+            200_000,
+            vec![executed_log(ALICE, true), executed_log(BOB, true)],
+        );
+
+        // By gas limit alone, Alice's generous limit would pay for most of Bob's call
+        let mut batch = MinedBatch::new(&input, &receipt).unwrap();
+        assert!(batch.outcome_of(&[1; 65]).unwrap().gas_used > 150_000);
+
+        let trace: CallFrame = serde_json::from_value(serde_json::json!({
+            "type": "CALL",
+            "to": FORWARDER,
+            "input": "0x",
+            "gasUsed": "0x30d40",
+            "calls": [
+                call(Address::with_last_byte(1), vec![], 3_000),
+                call(target, [&[0xaa][..], ALICE.as_slice()].concat(), 10_000),
+                call(Address::with_last_byte(1), vec![], 3_000),
+                call(target, [&[0xbb][..], BOB.as_slice()].concat(), 90_000),
+            ],
+        }))
+        .unwrap();
+        batch.attribute(&trace);
+        // Each pays its own call and half of the remaining 100_000
+        assert_eq!(batch.outcome_of(&[1; 65]).unwrap().gas_used, 60_000);
+        assert_eq!(batch.outcome_of(&[2; 65]).unwrap().gas_used, 140_000);
+    }
+}
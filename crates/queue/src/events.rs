@@ -0,0 +1,221 @@
+use chrono::{DateTime, Utc};
+use db::db::{NewTxRequest, StatusUpdate, TxRequest, TxStatus};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// What happened to a relay request; the names match its status `lifecycle`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RelayEventKind {
+    Queued,
+    Submitted,
+    /// A stuck transaction was replaced with higher fees.
+    Replaced,
+    Confirmed,
+    Failed,
+}
+
+/// A change of one relay request, as pushed to event stream subscribers.
+#[derive(Debug, Clone, Serialize)]
+pub struct RelayEvent {
+    pub event: RelayEventKind,
+    /// Id of the relay request.
+    pub id: Uuid,
+    #[serde(skip)]
+    pub tenant_id: Uuid,
+    pub chain_id: i64,
+    /// Status of the request after the event.
+    pub status: &'static str,
+    pub from: String,
+    pub to: String,
+    pub tx_hash: Option<String>,
+    pub block_number: Option<i64>,
+    pub gas_used: Option<i64>,
+    pub effective_gas_price: Option<String>,
+    pub error: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+/// In-process fan-out of relay events from the API, queue workers and receipt trackers
+/// to the event streams of this replica.
+#[derive(Clone)]
+pub struct RelayEventBus {
+    sender: broadcast::Sender<Arc<RelayEvent>>,
+    shutdown: watch::Receiver<bool>,
+}
+
+/// One subscriber's view of the bus.
+pub struct RelayEventSubscription {
+    events: broadcast::Receiver<Arc<RelayEvent>>,
+    shutdown: watch::Receiver<bool>,
+}
+
+pub enum Received {
+    Event(Arc<RelayEvent>),
+    /// The subscriber fell behind and missed this many events.
+    Lagged(u64),
+    /// The relayer is shutting down; streams should end.
+    Closed,
+}
+
+impl RelayEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RelayEventKind::Queued => "queued",
+            RelayEventKind::Submitted => "submitted",
+            RelayEventKind::Replaced => "replaced",
+            RelayEventKind::Confirmed => "confirmed",
+            RelayEventKind::Failed => "failed",
+        }
+    }
+
+    /// No further events follow these.
+    pub fn is_final(&self) -> bool {
+        matches!(self, RelayEventKind::Confirmed | RelayEventKind::Failed)
+    }
+}
+
+impl RelayEvent {
+    /// A request that was just accepted.
+    pub fn queued(id: Uuid, request: &NewTxRequest) -> Self {
+        Self {
+            event: RelayEventKind::Queued,
+            id,
+            tenant_id: request.tenant_id,
+            chain_id: request.chain_id,
+            status: TxStatus::Pending.as_str(),
+            from: request.from_address.clone(),
+            to: request.to_address.clone(),
+            tx_hash: None,
+            block_number: None,
+            gas_used: None,
+            effective_gas_price: None,
+            error: None,
+            at: Utc::now(),
+        }
+    }
+
+    /// `request` as it was before the change, moved to `status` with `update` applied.
+    pub fn new(
+        event: RelayEventKind,
+        request: &TxRequest,
+        status: TxStatus,
+        update: &StatusUpdate,
+    ) -> Self {
+        Self {
+            event,
+            id: request.id,
+            tenant_id: request.tenant_id,
+            chain_id: request.chain_id,
+            status: status.as_str(),
+            from: request.from_address.clone(),
+            to: request.to_address.clone(),
+            tx_hash: update.tx_hash.clone().or_else(|| request.tx_hash.clone()),
+            block_number: update.block_number,
+            gas_used: update.gas_used,
+            effective_gas_price: update.effective_gas_price.clone(),
+            error: update.error.clone(),
+            at: Utc::now(),
+        }
+    }
+}
+
+impl RelayEventBus {
+    /// Subscriptions end once `shutdown` flips to `true`, so open streams do not hold up
+    /// a graceful shutdown.
+    pub fn new(capacity: usize, shutdown: watch::Receiver<bool>) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender, shutdown }
+    }
+
+    /// Hands the event to every current subscriber. Nobody listening is fine.
+    pub fn publish(&self, event: RelayEvent) {
+        let _ = self.sender.send(Arc::new(event));
+    }
+
+    /// Events published from now on.
+    pub fn subscribe(&self) -> RelayEventSubscription {
+        RelayEventSubscription {
+            events: self.sender.subscribe(),
+            shutdown: self.shutdown.clone(),
+        }
+    }
+}
+
+impl RelayEventSubscription {
+    pub async fn recv(&mut self) -> Received {
+        if *self.shutdown.borrow() {
+            return Received::Closed;
+        }
+        tokio::select! {
+            received = self.events.recv() => match received {
+                Ok(event) => Received::Event(event),
+                Err(RecvError::Lagged(missed)) => Received::Lagged(missed),
+                Err(RecvError::Closed) => Received::Closed,
+            },
+            _ = self.shutdown.changed() => Received::Closed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queued(tenant: u128) -> RelayEvent {
+        let request = NewTxRequest {
+            chain_id: 11155111,
+            tenant_id: Uuid::from_u128(tenant),
+            from_address: "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266".to_string(),
+            to_address: "0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f".to_string(),
+            value: "0".to_string(),
+            gas: 100_000,
+            deadline: 1_900_000_000,
+            data: vec![],
+            signature: vec![],
+            estimated_cost_wei: None,
+            callback_url: None,
+        };
+        RelayEvent::queued(Uuid::from_u128(7), &request)
+    }
+
+    #[tokio::test]
+    async fn fans_out_until_shutdown() {
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let bus = RelayEventBus::new(2, shutdown_rx);
+        bus.publish(queued(1));
+
+        // Subscribers only see what is published after they subscribed
+        let mut first = bus.subscribe();
+        let mut second = bus.subscribe();
+        bus.publish(queued(2));
+        for subscription in [&mut first, &mut second] {
+            match subscription.recv().await {
+                Received::Event(event) => assert_eq!(event.tenant_id, Uuid::from_u128(2)),
+                _ => panic!("expected an event"),
+            }
+        }
+
+        for tenant in 3..6 {
+            bus.publish(queued(tenant));
+        }
+        assert!(matches!(first.recv().await, Received::Lagged(1)));
+        assert!(matches!(first.recv().await, Received::Event(_)));
+
+        shutdown_tx.send(true).unwrap();
+        assert!(matches!(second.recv().await, Received::Closed));
+        assert!(matches!(bus.subscribe().recv().await, Received::Closed));
+    }
+
+    #[test]
+    fn serializes_without_the_tenant() {
+        let event = serde_json::to_value(queued(1)).unwrap();
+        assert_eq!(event["event"], "queued");
+        assert_eq!(event["status"], "pending");
+        assert_eq!(event["id"], Uuid::from_u128(7).to_string());
+        assert!(event.get("tenant_id").is_none());
+    }
+}
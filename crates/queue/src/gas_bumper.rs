@@ -0,0 +1,82 @@
+use alloy::eips::eip1559::Eip1559Estimation;
+
+/// Nodes only accept a replacement that raises both fee fields by at least 10%.
+const REPLACEMENT_BUMP_PERCENT: u128 = 10;
+
+/// Fees for a replacement of a transaction sent with `previous`: at least 10% above it
+/// and never below what the network currently asks. `None` once that exceeds `max_fee_cap`.
+pub fn bump_fees(
+    previous: &Eip1559Estimation,
+    network: &Eip1559Estimation,
+    max_fee_cap: u128,
+) -> Option<Eip1559Estimation> {
+    let max_fee_per_gas = min_replacement(previous.max_fee_per_gas).max(network.max_fee_per_gas);
+    let max_priority_fee_per_gas = min_replacement(previous.max_priority_fee_per_gas)
+        .max(network.max_priority_fee_per_gas)
+        .min(max_fee_per_gas);
+
+    if max_fee_per_gas > max_fee_cap {
+        return None;
+    }
+
+    Some(Eip1559Estimation {
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+    })
+}
+
+/// Rounds up so small fees still grow by the full percentage.
+fn min_replacement(fee: u128) -> u128 {
+    fee + (fee * REPLACEMENT_BUMP_PERCENT).div_ceil(100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GWEI: u128 = 1_000_000_000;
+
+    fn fees(max_fee_per_gas: u128, max_priority_fee_per_gas: u128) -> Eip1559Estimation {
+        Eip1559Estimation {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        }
+    }
+
+    #[test]
+    fn bumps_both_fees_by_ten_percent() {
+        let bumped = bump_fees(
+            &fees(30 * GWEI, 2 * GWEI),
+            &fees(20 * GWEI, GWEI),
+            500 * GWEI,
+        );
+        assert_eq!(bumped, Some(fees(33 * GWEI, 2_200_000_000)));
+    }
+
+    #[test]
+    fn follows_the_network_when_it_moved_further() {
+        let bumped = bump_fees(
+            &fees(30 * GWEI, 2 * GWEI),
+            &fees(50 * GWEI, 3 * GWEI),
+            500 * GWEI,
+        );
+        assert_eq!(bumped, Some(fees(50 * GWEI, 3 * GWEI)));
+    }
+
+    #[test]
+    fn rounds_small_fees_up() {
+        assert_eq!(bump_fees(&fees(9, 1), &fees(0, 0), 500), Some(fees(10, 2)));
+    }
+
+    #[test]
+    fn stops_at_the_fee_cap() {
+        assert_eq!(
+            bump_fees(
+                &fees(460 * GWEI, 2 * GWEI),
+                &fees(20 * GWEI, GWEI),
+                500 * GWEI
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use alloy::eips::eip1559::Eip1559Estimation;
+use alloy::eips::BlockNumberOrTag;
+use alloy::providers::{DynProvider, Provider};
+use alloy::rpc::types::FeeHistory;
+use config::config::GasOracleConfig;
+use metrics::ChainMetrics;
+use serde::{Serialize, Serializer};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Mutex;
+
+/// Floor for priority fee suggestions when the sampled blocks paid no tips at all.
+const MIN_PRIORITY_FEE: u128 = 1;
+
+/// Wei per gas is serialized as a decimal string; it does not always fit a JSON number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FeeSuggestion {
+    #[serde(serialize_with = "as_decimal")]
+    pub max_fee_per_gas: u128,
+    #[serde(serialize_with = "as_decimal")]
+    pub max_priority_fee_per_gas: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GasEstimates {
+    /// Block the samples end at.
+    pub block_number: u64,
+    /// Base fee of the next block.
+    #[serde(serialize_with = "as_decimal")]
+    pub base_fee_per_gas: u128,
+    pub slow: FeeSuggestion,
+    pub normal: FeeSuggestion,
+    pub fast: FeeSuggestion,
+}
+
+/// Suggests EIP-1559 fees from recent blocks and caches them for `cache_ttl`.
+#[derive(Clone)]
+pub struct GasOracle {
+    config: GasOracleConfig,
+    provider: DynProvider,
+    metrics: ChainMetrics,
+    cache: Arc<Mutex<Option<(Instant, GasEstimates)>>>,
+}
+
+impl GasOracle {
+    pub fn new(config: GasOracleConfig, provider: DynProvider, metrics: ChainMetrics) -> Self {
+        Self {
+            config,
+            provider,
+            metrics,
+            cache: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Cached estimates, refreshed from `eth_feeHistory` once they are older than the TTL.
+    /// Concurrent callers wait for a single refresh instead of each hitting the node.
+    pub async fn estimates(&self) -> anyhow::Result<GasEstimates> {
+        let mut cache = self.cache.lock().await;
+        if let Some((fetched_at, estimates)) = cache.as_ref() {
+            if fetched_at.elapsed() < self.config.cache_ttl {
+                return Ok(estimates.clone());
+            }
+        }
+
+        let history = self
+            .provider
+            .get_fee_history(
+                self.config.block_count,
+                BlockNumberOrTag::Latest,
+                &self.config.percentiles,
+            )
+            .await?;
+        let estimates = estimates_from_history(&history)
+            .ok_or_else(|| anyhow::anyhow!("eth_feeHistory returned no base fees"))?;
+
+        self.metrics
+            .gas_price_current
+            .set(estimates.normal.max_fee_per_gas as f64 / 1e9);
+        *cache = Some((Instant::now(), estimates.clone()));
+
+        Ok(estimates)
+    }
+
+    /// Fees for a transaction sent now, at the normal percentile.
+    pub async fn suggest(&self) -> anyhow::Result<Eip1559Estimation> {
+        let normal = self.estimates().await?.normal;
+        Ok(Eip1559Estimation {
+            max_fee_per_gas: normal.max_fee_per_gas,
+            max_priority_fee_per_gas: normal.max_priority_fee_per_gas,
+        })
+    }
+}
+
+/// Priority fees are the median reward of each percentile over the sampled blocks, ignoring
+/// empty blocks. The max fee leaves room for the base fee to double before inclusion.
+pub fn estimates_from_history(history: &FeeHistory) -> Option<GasEstimates> {
+    let base_fee_per_gas = *history.base_fee_per_gas.last()?;
+    let rewards = history.reward.as_deref().unwrap_or_default();
+
+    let suggestion = |percentile: usize| {
+        let mut tips: Vec<u128> = rewards
+            .iter()
+            .filter_map(|block| block.get(percentile).copied())
+            .filter(|tip| *tip > 0)
+            .collect();
+        tips.sort_unstable();
+
+        let max_priority_fee_per_gas = tips
+            .get(tips.len() / 2)
+            .copied()
+            .unwrap_or(MIN_PRIORITY_FEE);
+        FeeSuggestion {
+            max_fee_per_gas: base_fee_per_gas * 2 + max_priority_fee_per_gas,
+            max_priority_fee_per_gas,
+        }
+    };
+
+    Some(GasEstimates {
+        block_number: (history.oldest_block + history.base_fee_per_gas.len() as u64)
+            .saturating_sub(2),
+        base_fee_per_gas,
+        slow: suggestion(0),
+        normal: suggestion(1),
+        fast: suggestion(2),
+    })
+}
+
+fn as_decimal<S: Serializer>(value: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GWEI: u128 = 1_000_000_000;
+
+    fn history(base_fees: Vec<u128>, rewards: Vec<Vec<u128>>) -> FeeHistory {
+        FeeHistory {
+            oldest_block: 100,
+            base_fee_per_gas: base_fees,
+            reward: Some(rewards),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn takes_the_median_reward_per_percentile() {
+        let history = history(
+            vec![10 * GWEI, 11 * GWEI, 12 * GWEI, 13 * GWEI],
+            vec![
+                vec![GWEI, 2 * GWEI, 5 * GWEI],
+                vec![GWEI, 3 * GWEI, 4 * GWEI],
+                vec![2 * GWEI, 2 * GWEI, 9 * GWEI],
+            ],
+        );
+
+        let estimates = estimates_from_history(&history).unwrap();
+        assert_eq!(estimates.block_number, 102);
+        assert_eq!(estimates.base_fee_per_gas, 13 * GWEI);
+        assert_eq!(estimates.slow.max_priority_fee_per_gas, GWEI);
+        assert_eq!(estimates.normal.max_priority_fee_per_gas, 2 * GWEI);
+        assert_eq!(estimates.fast.max_priority_fee_per_gas, 5 * GWEI);
+        assert_eq!(estimates.fast.max_fee_per_gas, 31 * GWEI);
+    }
+
+    #[test]
+    fn ignores_empty_blocks() {
+        let history = history(
+            vec![10 * GWEI, 10 * GWEI, 10 * GWEI],
+            vec![vec![0, 0, 0], vec![GWEI, 2 * GWEI, 3 * GWEI]],
+        );
+
+        let estimates = estimates_from_history(&history).unwrap();
+        assert_eq!(estimates.normal.max_priority_fee_per_gas, 2 * GWEI);
+
+        let quiet = history_without_tips();
+        assert_eq!(
+            estimates_from_history(&quiet)
+                .unwrap()
+                .fast
+                .max_priority_fee_per_gas,
+            MIN_PRIORITY_FEE
+        );
+    }
+
+    #[test]
+    fn serializes_fees_as_decimal_strings() {
+        let estimates = estimates_from_history(&history_without_tips()).unwrap();
+        let json = serde_json::to_value(&estimates).unwrap();
+        assert_eq!(json["base_fee_per_gas"], "7");
+        assert_eq!(json["normal"]["max_fee_per_gas"], "15");
+    }
+
+    fn history_without_tips() -> FeeHistory {
+        history(vec![7, 7], vec![vec![0, 0, 0]])
+    }
+}
@@ -0,0 +1,21 @@
+pub mod batch;
+pub mod events;
+pub mod gas_bumper;
+pub mod gas_oracle;
+pub mod nonce_manager;
+pub mod provider;
+pub mod provider_pool;
+pub mod simulation;
+pub mod tracker;
+pub mod worker;
+
+pub use batch::*;
+pub use events::*;
+pub use gas_bumper::*;
+pub use gas_oracle::*;
+pub use nonce_manager::*;
+pub use provider::*;
+pub use provider_pool::*;
+pub use simulation::*;
+pub use tracker::*;
+pub use worker::*;
@@ -0,0 +1,172 @@
+use crate::gas_oracle::GasOracle;
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, U256};
+use alloy::providers::{DynProvider, Provider};
+use alloy::rpc::types::TransactionRequest;
+use db::nonces::{NonceRepository, NonceState};
+use metrics::ChainMetrics;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// A reservation older than this without a broadcast is treated as abandoned.
+const RESERVATION_STALE_AFTER: Duration = Duration::from_secs(120);
+
+/// Gas limit of a plain value transfer, used for the no-op transactions that fill gaps.
+const TRANSFER_GAS_LIMIT: u64 = 21_000;
+
+/// Hands out nonces for the relayer account from Postgres and keeps them gap-free on chain.
+#[derive(Clone)]
+pub struct NonceManager {
+    chain_id: u64,
+    address: Address,
+    nonces: NonceRepository,
+    provider: DynProvider,
+    gas_oracle: GasOracle,
+    metrics: ChainMetrics,
+}
+
+impl NonceManager {
+    pub fn new(
+        chain_id: u64,
+        address: Address,
+        nonces: NonceRepository,
+        provider: DynProvider,
+        gas_oracle: GasOracle,
+        metrics: ChainMetrics,
+    ) -> Self {
+        Self {
+            chain_id,
+            address,
+            nonces,
+            provider,
+            gas_oracle,
+            metrics,
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Aligns the stored counter with the chain's pending nonce. Returns the next nonce to hand out.
+    pub async fn sync(&self) -> anyhow::Result<u64> {
+        let chain_pending = self.chain_pending_nonce().await?;
+        let next_nonce = self
+            .nonces
+            .sync(self.chain_id as i64, &self.account(), chain_pending as i64)
+            .await? as u64;
+
+        self.metrics.relayer_nonce_current.set(next_nonce as i64);
+        tracing::info!(
+            address = %self.address,
+            chain_pending,
+            next_nonce,
+            "Relayer nonce synced with chain"
+        );
+
+        Ok(next_nonce)
+    }
+
+    pub async fn reserve(&self, tx_request_id: Option<Uuid>) -> anyhow::Result<u64> {
+        let nonce = self
+            .nonces
+            .reserve(self.chain_id as i64, &self.account(), tx_request_id)
+            .await? as u64;
+
+        // The gauge tracks the next fresh nonce, so reusing a released one must not move it back.
+        if nonce as i64 + 1 > self.metrics.relayer_nonce_current.get() {
+            self.metrics.relayer_nonce_current.set(nonce as i64 + 1);
+        }
+
+        Ok(nonce)
+    }
+
+    pub async fn mark_broadcast(&self, nonce: u64) -> anyhow::Result<()> {
+        self.set_state(nonce, NonceState::Broadcast).await
+    }
+
+    /// Gives back a nonce whose transaction never reached the node so the next reservation reuses it.
+    pub async fn release(&self, nonce: u64) -> anyhow::Result<()> {
+        self.set_state(nonce, NonceState::Released).await
+    }
+
+    /// Finds nonces nothing will broadcast and burns each with a zero value self-transfer,
+    /// unblocking every later transaction queued behind it. Returns the nonces filled.
+    pub async fn fill_gaps(&self) -> anyhow::Result<Vec<u64>> {
+        let chain_pending = self.chain_pending_nonce().await?;
+        let stale_after = RESERVATION_STALE_AFTER.as_secs_f64();
+        let gaps = self
+            .nonces
+            .find_gaps(
+                self.chain_id as i64,
+                &self.account(),
+                chain_pending as i64,
+                stale_after,
+            )
+            .await?;
+
+        let mut filled = Vec::new();
+        for gap in gaps {
+            let claimed = self
+                .nonces
+                .claim_gap(self.chain_id as i64, &self.account(), gap, stale_after)
+                .await?;
+            if !claimed {
+                continue;
+            }
+
+            let nonce = gap as u64;
+            match self.send_self_transfer(nonce).await {
+                Ok(()) => {
+                    self.mark_broadcast(nonce).await?;
+                    filled.push(nonce);
+                }
+                Err(e) => {
+                    tracing::error!(nonce, "Failed to fill nonce gap: {}", e);
+                    self.release(nonce).await?;
+                }
+            }
+        }
+
+        if !filled.is_empty() {
+            tracing::warn!(address = %self.address, ?filled, "Filled relayer nonce gaps");
+        }
+
+        Ok(filled)
+    }
+
+    async fn send_self_transfer(&self, nonce: u64) -> anyhow::Result<()> {
+        let fees = self.gas_oracle.suggest().await?;
+        let tx = TransactionRequest::default()
+            .with_from(self.address)
+            .with_to(self.address)
+            .with_value(U256::ZERO)
+            .with_nonce(nonce)
+            .with_chain_id(self.chain_id)
+            .with_gas_limit(TRANSFER_GAS_LIMIT)
+            .with_max_fee_per_gas(fees.max_fee_per_gas)
+            .with_max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
+
+        let pending = self.provider.send_transaction(tx).await?;
+        tracing::info!(nonce, tx_hash = %pending.tx_hash(), "Sent gap filling self-transfer");
+        Ok(())
+    }
+
+    async fn chain_pending_nonce(&self) -> anyhow::Result<u64> {
+        Ok(self
+            .provider
+            .get_transaction_count(self.address)
+            .pending()
+            .await?)
+    }
+
+    async fn set_state(&self, nonce: u64, state: NonceState) -> anyhow::Result<()> {
+        self.nonces
+            .set_state(self.chain_id as i64, &self.account(), nonce as i64, state)
+            .await
+    }
+
+    fn account(&self) -> String {
+        self.address.to_checksum(None)
+    }
+}
@@ -0,0 +1,13 @@
+use crate::provider_pool::ProviderPool;
+use alloy::providers::{DynProvider, Provider, ProviderBuilder};
+use alloy::rpc::client::RpcClient;
+use signer::Signer;
+
+/// Connects to the pool's endpoints with a provider that signs with the relayer account.
+pub fn connect_provider(pool: ProviderPool, signer: &dyn Signer) -> DynProvider {
+    let provider = ProviderBuilder::new()
+        .wallet(signer.wallet())
+        .connect_client(RpcClient::new(pool, false));
+
+    provider.erased()
+}
@@ -0,0 +1,390 @@
+use alloy::primitives::U64;
+use alloy::rpc::json_rpc::{Id, Request, RequestPacket, ResponsePacket};
+use alloy::transports::http::{reqwest, Http};
+use alloy::transports::{TransportError, TransportErrorKind, TransportFut, TransportResult};
+use config::config::RpcConfig;
+use metrics::ChainMetrics;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower::Service;
+
+/// Methods that change node state. They go to the healthiest endpoint once and are never retried.
+const NON_IDEMPOTENT_METHODS: &[&str] = &["eth_sendRawTransaction", "eth_sendTransaction"];
+
+/// Pause before the n-th retry, multiplied by n.
+const RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Weight of the newest sample in the latency and error rate averages.
+const EWMA_ALPHA: f64 = 0.2;
+
+/// Rolling view of one endpoint's reliability.
+#[derive(Debug, Clone, Default)]
+pub struct EndpointHealth {
+    pub consecutive_failures: u32,
+    /// Set while the circuit breaker is open.
+    pub open_until: Option<Instant>,
+    /// Moving average of call latency in seconds.
+    pub latency: f64,
+    /// Moving average of failed calls, between 0 and 1.
+    pub error_rate: f64,
+}
+
+impl EndpointHealth {
+    /// Lower is better; errors weigh far more than latency.
+    pub fn score(&self) -> f64 {
+        self.latency * (1.0 + 10.0 * self.error_rate) + self.error_rate
+    }
+
+    /// A closed circuit, or an open one whose cooldown ran out and may take a trial call.
+    pub fn is_available(&self, now: Instant) -> bool {
+        self.open_until.is_none_or(|until| now >= until)
+    }
+
+    pub fn record_success(&mut self, latency: Duration) {
+        self.consecutive_failures = 0;
+        self.open_until = None;
+        self.latency = ewma(self.latency, latency.as_secs_f64());
+        self.error_rate = ewma(self.error_rate, 0.0);
+    }
+
+    /// Opens the circuit once `threshold` calls in a row failed. A failed trial call after
+    /// the cooldown reopens it straight away since the streak was never reset.
+    pub fn record_failure(&mut self, latency: Duration, threshold: u32, cooldown: Duration) {
+        self.consecutive_failures += 1;
+        self.latency = ewma(self.latency, latency.as_secs_f64());
+        self.error_rate = ewma(self.error_rate, 1.0);
+        if self.consecutive_failures >= threshold {
+            self.open_until = Some(Instant::now() + cooldown);
+        }
+    }
+}
+
+struct Endpoint {
+    /// Host only; provider URLs often carry an API key in the path.
+    name: String,
+    transport: Http<reqwest::Client>,
+    health: Mutex<EndpointHealth>,
+}
+
+struct PoolInner {
+    endpoints: Vec<Endpoint>,
+    config: RpcConfig,
+    metrics: ChainMetrics,
+    highest_block: AtomicU64,
+}
+
+/// A transport spreading JSON-RPC calls over several endpoints. Calls go to the healthiest
+/// endpoint, failing ones are taken out by a circuit breaker and read-only calls are retried
+/// on the next endpoint. Every attempt is recorded with `ChainMetrics::record_rpc_call`.
+#[derive(Clone)]
+pub struct ProviderPool {
+    inner: Arc<PoolInner>,
+}
+
+impl ProviderPool {
+    pub fn new(config: &RpcConfig, metrics: ChainMetrics) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(config.request_timeout)
+            .build()?;
+
+        let endpoints = config
+            .urls
+            .iter()
+            .map(|url| {
+                let url: reqwest::Url = url.parse()?;
+                Ok(Endpoint {
+                    name: url.host_str().unwrap_or("unknown").to_string(),
+                    transport: Http::with_client(client.clone(), url),
+                    health: Mutex::new(EndpointHealth::default()),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        anyhow::ensure!(!endpoints.is_empty(), "no RPC URLs configured");
+
+        Ok(Self {
+            inner: Arc::new(PoolInner {
+                endpoints,
+                config: config.clone(),
+                metrics,
+                highest_block: AtomicU64::new(0),
+            }),
+        })
+    }
+
+    /// Health of every endpoint, in configuration order.
+    pub fn health(&self) -> Vec<(String, EndpointHealth)> {
+        self.inner
+            .endpoints
+            .iter()
+            .map(|e| (e.name.clone(), e.health.lock().unwrap().clone()))
+            .collect()
+    }
+
+    /// Endpoint indices in the order calls try them: available endpoints by score,
+    /// or every endpoint by reopening time when all circuits are open.
+    fn ranked(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let health: Vec<EndpointHealth> = self
+            .inner
+            .endpoints
+            .iter()
+            .map(|e| e.health.lock().unwrap().clone())
+            .collect();
+
+        let mut available: Vec<usize> = (0..health.len())
+            .filter(|&i| health[i].is_available(now))
+            .collect();
+        if available.is_empty() {
+            let mut all: Vec<usize> = (0..health.len()).collect();
+            all.sort_by_key(|&i| health[i].open_until);
+            return all;
+        }
+
+        available.sort_by(|&a, &b| health[a].score().total_cmp(&health[b].score()));
+        available
+    }
+
+    /// Asks one endpoint for its latest block, bypassing the ranking. Used by health checks.
+    pub async fn probe_block_number(&self, index: usize) -> anyhow::Result<u64> {
+        let request: RequestPacket = Request::new("eth_blockNumber", Id::Number(0), ())
+            .serialize()?
+            .into();
+        let ResponsePacket::Single(response) = self.send_to(index, request).await? else {
+            anyhow::bail!("unexpected batch response");
+        };
+        let block_number = match response.payload.try_success_as::<U64>() {
+            Some(block_number) => block_number?.to::<u64>(),
+            None => anyhow::bail!("eth_blockNumber failed: {:?}", response.payload.as_error()),
+        };
+
+        self.inner
+            .highest_block
+            .fetch_max(block_number, Ordering::Relaxed);
+        Ok(block_number)
+    }
+
+    /// Highest block any endpoint reported to `probe_block_number`.
+    pub fn highest_block(&self) -> u64 {
+        self.inner.highest_block.load(Ordering::Relaxed)
+    }
+
+    /// Sends to one endpoint, recording the call in the metrics and the endpoint's health.
+    async fn send_to(
+        &self,
+        index: usize,
+        request: RequestPacket,
+    ) -> TransportResult<ResponsePacket> {
+        let endpoint = &self.inner.endpoints[index];
+        let started = Instant::now();
+        let result = endpoint.transport.clone().call(request).await;
+        let latency = started.elapsed();
+        self.inner
+            .metrics
+            .record_rpc_call(latency.as_secs_f64(), result.is_ok());
+
+        let mut health = endpoint.health.lock().unwrap();
+        match &result {
+            Ok(_) => health.record_success(latency),
+            Err(e) => {
+                health.record_failure(
+                    latency,
+                    self.inner.config.failure_threshold,
+                    self.inner.config.cooldown,
+                );
+                if health.open_until.is_some() {
+                    tracing::warn!(
+                        endpoint = %endpoint.name,
+                        failures = health.consecutive_failures,
+                        "RPC endpoint circuit open"
+                    );
+                }
+                tracing::warn!(endpoint = %endpoint.name, "RPC call failed: {}", e);
+            }
+        }
+
+        result
+    }
+
+    async fn dispatch(self, request: RequestPacket) -> TransportResult<ResponsePacket> {
+        let idempotent = request
+            .method_names()
+            .all(|method| !NON_IDEMPOTENT_METHODS.contains(&method));
+        let attempts = if idempotent {
+            self.inner.config.max_retries + 1
+        } else {
+            1
+        };
+        let ranked = self.ranked();
+
+        let mut last_error = None;
+        for attempt in 0..attempts as usize {
+            if attempt > 0 {
+                tokio::time::sleep(RETRY_BACKOFF * attempt as u32).await;
+            }
+
+            match self
+                .send_to(ranked[attempt % ranked.len()], request.clone())
+                .await
+            {
+                Ok(response) => return Ok(response),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| TransportErrorKind::custom_str("no RPC endpoint tried")))
+    }
+}
+
+impl Service<RequestPacket> for ProviderPool {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        Box::pin(self.clone().dispatch(request))
+    }
+}
+
+fn ewma(average: f64, sample: f64) -> f64 {
+    average + EWMA_ALPHA * (sample - average)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metrics::MetricsCollector;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn config(urls: Vec<String>) -> RpcConfig {
+        RpcConfig {
+            urls,
+            failure_threshold: 2,
+            cooldown: Duration::from_secs(30),
+            max_retries: 2,
+            request_timeout: Duration::from_secs(2),
+        }
+    }
+
+    fn packet(method: &'static str) -> RequestPacket {
+        Request::new(method, Id::Number(1), ())
+            .serialize()
+            .unwrap()
+            .into()
+    }
+
+    /// Answers every HTTP request with a fixed JSON-RPC result.
+    async fn fake_node() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let body = r#"{"jsonrpc":"2.0","id":1,"result":"0xaa36a7"}"#;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        url
+    }
+
+    /// Closes every connection without answering. It keeps its port for the whole test, so
+    /// no other test can bind it and answer in its place.
+    async fn dead_node() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                drop(socket);
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn opens_the_circuit_after_repeated_failures() {
+        let mut health = EndpointHealth::default();
+        let cooldown = Duration::from_secs(30);
+
+        health.record_failure(Duration::from_millis(10), 2, cooldown);
+        assert!(health.is_available(Instant::now()));
+        health.record_failure(Duration::from_millis(10), 2, cooldown);
+        assert!(!health.is_available(Instant::now()));
+        assert!(health.is_available(Instant::now() + cooldown));
+
+        health.record_success(Duration::from_millis(10));
+        assert_eq!(health.consecutive_failures, 0);
+        assert!(health.is_available(Instant::now()));
+    }
+
+    #[test]
+    fn errors_outweigh_latency_in_the_score() {
+        let mut slow = EndpointHealth::default();
+        slow.record_success(Duration::from_millis(800));
+        let mut flaky = EndpointHealth::default();
+        flaky.record_success(Duration::from_millis(50));
+        flaky.record_failure(Duration::from_millis(50), 5, Duration::from_secs(30));
+
+        assert!(slow.score() < flaky.score());
+    }
+
+    #[tokio::test]
+    async fn retries_reads_on_the_next_endpoint() {
+        let pool = ProviderPool::new(
+            &config(vec![dead_node().await, fake_node().await]),
+            MetricsCollector::new().unwrap().chain(11155111),
+        )
+        .unwrap();
+
+        let response = pool.clone().call(packet("eth_chainId")).await.unwrap();
+        assert!(response.is_success());
+
+        let health = pool.health();
+        assert_eq!(health[0].1.consecutive_failures, 1);
+        assert_eq!(health[1].1.consecutive_failures, 0);
+        // The failed endpoint now ranks behind the healthy one.
+        assert_eq!(pool.ranked(), vec![1, 0]);
+        assert_eq!(pool.inner.metrics.rpc_requests_total.get(), 2);
+        assert_eq!(pool.inner.metrics.rpc_errors_total.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn probes_each_endpoint() {
+        let pool = ProviderPool::new(
+            &config(vec![dead_node().await, fake_node().await]),
+            MetricsCollector::new().unwrap().chain(11155111),
+        )
+        .unwrap();
+
+        assert!(pool.probe_block_number(0).await.is_err());
+        assert_eq!(pool.probe_block_number(1).await.unwrap(), 11155111);
+        assert_eq!(pool.highest_block(), 11155111);
+    }
+
+    #[tokio::test]
+    async fn sends_transactions_only_once() {
+        let pool = ProviderPool::new(
+            &config(vec![dead_node().await, fake_node().await]),
+            MetricsCollector::new().unwrap().chain(11155111),
+        )
+        .unwrap();
+
+        assert!(pool
+            .clone()
+            .call(packet("eth_sendRawTransaction"))
+            .await
+            .is_err());
+        assert_eq!(pool.inner.metrics.rpc_requests_total.get(), 1);
+    }
+}
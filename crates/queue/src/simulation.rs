@@ -0,0 +1,391 @@
+use crate::batch::batch_gas_limit;
+use crate::worker::execute_gas_limit;
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, Bytes};
+use alloy::providers::{DynProvider, Provider};
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol_types::{SolCall, SolInterface};
+use config::config::SimulationConfig;
+use forwarder::TrustedForwarder::{self, TrustedForwarderErrors};
+use forwarder::{decode_revert_reason, decode_target_revert};
+use metrics::ChainMetrics;
+use policy::PolicyEngine;
+use serde::Serialize;
+use std::fmt;
+use std::time::Duration;
+
+/// Why a simulated `execute` reverted.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimulatedRevert {
+    /// The target contract reverted, rather than the forwarder refusing the request.
+    pub in_target: bool,
+    pub reason: String,
+    /// Raw revert data, for clients decoding it with the target's ABI.
+    pub data: Bytes,
+}
+
+pub enum SimulationError {
+    /// Relaying the request would only burn gas.
+    Reverted(SimulatedRevert),
+    /// The node could not tell.
+    Rpc(anyhow::Error),
+}
+
+/// Replays `TrustedForwarder.execute` with `eth_call` from the relayer account, so requests
+/// that would revert are refused instead of paid for.
+#[derive(Clone)]
+pub struct Simulator {
+    config: SimulationConfig,
+    chain_id: u64,
+    forwarder: Address,
+    relayer: Address,
+    provider: DynProvider,
+    policy: PolicyEngine,
+    metrics: ChainMetrics,
+}
+
+impl fmt::Display for SimulatedRevert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.in_target {
+            write!(f, "target call reverted: {}", self.reason)
+        } else {
+            f.write_str(&self.reason)
+        }
+    }
+}
+
+impl Simulator {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        config: SimulationConfig,
+        chain_id: u64,
+        forwarder: Address,
+        relayer: Address,
+        provider: DynProvider,
+        policy: PolicyEngine,
+        metrics: ChainMetrics,
+    ) -> Self {
+        Self {
+            config,
+            chain_id,
+            forwarder,
+            relayer,
+            provider,
+            policy,
+            metrics,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Whether a request queued for `waited` has to pass the simulation again before broadcast.
+    pub fn is_stale(&self, waited: Duration) -> bool {
+        self.config.enabled && waited >= self.config.resimulate_after
+    }
+
+    /// Calls `execute` on the latest block with the gas limit it would be sent with.
+    pub async fn simulate(
+        &self,
+        request: &TrustedForwarder::ForwardRequestData,
+    ) -> Result<(), SimulationError> {
+        let result = self.revert_of(request).await;
+        if let Err(SimulationError::Reverted(revert)) = &result {
+            self.metrics.simulation_reverts.inc();
+            tracing::debug!(from = %request.from, to = %request.to, "Simulated relay request reverted: {}", revert);
+        }
+        result
+    }
+
+    /// Calls an atomic `executeBatch` of `requests`, which reverts if any of them is invalid.
+    /// A failing target call does not revert the batch, so only the forwarder's checks count.
+    pub async fn simulate_batch(
+        &self,
+        requests: &[TrustedForwarder::ForwardRequestData],
+    ) -> Result<(), SimulationError> {
+        let value = requests.iter().map(|request| request.value).sum();
+        let gas_limit = batch_gas_limit(requests.iter().map(|request| request.gas.saturating_to()));
+        let tx = TransactionRequest::default()
+            .with_from(self.relayer)
+            .with_to(self.forwarder)
+            .with_input(
+                TrustedForwarder::executeBatchCall {
+                    requests: requests.to_vec(),
+                    refundReceiver: Address::ZERO,
+                }
+                .abi_encode(),
+            )
+            .with_value(value)
+            .with_gas_limit(gas_limit);
+
+        let result = match self.call(tx, false).await {
+            Ok(None) => Ok(()),
+            Ok(Some(data)) => Err(SimulationError::Reverted(SimulatedRevert {
+                in_target: false,
+                reason: decode_revert_reason(&data),
+                data,
+            })),
+            Err(e) => Err(e),
+        };
+        if let Err(SimulationError::Reverted(revert)) = &result {
+            self.metrics.simulation_reverts.inc();
+            tracing::debug!(
+                requests = requests.len(),
+                "Simulated relay batch reverted: {}",
+                revert
+            );
+        }
+        result
+    }
+
+    async fn revert_of(
+        &self,
+        request: &TrustedForwarder::ForwardRequestData,
+    ) -> Result<(), SimulationError> {
+        let execute = TransactionRequest::default()
+            .with_from(self.relayer)
+            .with_to(self.forwarder)
+            .with_input(
+                TrustedForwarder::executeCall {
+                    request: request.clone(),
+                }
+                .abi_encode(),
+            )
+            .with_value(request.value)
+            .with_gas_limit(execute_gas_limit(request.gas.saturating_to()));
+        let Some(data) = self.call(execute, false).await? else {
+            return Ok(());
+        };
+
+        let target_failed = matches!(
+            TrustedForwarderErrors::abi_decode(&data),
+            Ok(TrustedForwarderErrors::FailedCall(_) | TrustedForwarderErrors::FailedInnerCall(_))
+        );
+        if !target_failed {
+            return Err(SimulationError::Reverted(SimulatedRevert {
+                in_target: false,
+                reason: decode_revert_reason(&data),
+                data,
+            }));
+        }
+
+        // The forwarder drops the target's revert data, so the call is replayed the way the
+        // forwarder makes it: from the forwarder, with the signer appended (ERC-2771)
+        let mut input = request.data.to_vec();
+        input.extend_from_slice(request.from.as_slice());
+        let target = TransactionRequest::default()
+            .with_from(self.forwarder)
+            .with_to(request.to)
+            .with_input(input)
+            .with_value(request.value)
+            .with_gas_limit(request.gas.saturating_to());
+        let data = self.call(target, true).await?.unwrap_or_default();
+        let errors = self.policy.current();
+        Err(SimulationError::Reverted(SimulatedRevert {
+            in_target: true,
+            reason: decode_target_revert(&data, errors.target_errors(self.chain_id, request.to)),
+            data,
+        }))
+    }
+
+    /// The revert data of `tx`, or `None` if it goes through.
+    async fn call(
+        &self,
+        tx: TransactionRequest,
+        in_target: bool,
+    ) -> Result<Option<Bytes>, SimulationError> {
+        let e = match self.provider.call(tx).await {
+            Ok(_) => return Ok(None),
+            Err(e) => e,
+        };
+        match e.as_error_resp() {
+            Some(payload) if payload.message.contains("revert") => {
+                Ok(Some(payload.as_revert_data().unwrap_or_default()))
+            }
+            Some(payload) if payload.message.contains("out of gas") => {
+                Err(SimulationError::Reverted(SimulatedRevert {
+                    in_target,
+                    reason: "out of gas".to_string(),
+                    data: Bytes::new(),
+                }))
+            }
+            _ => Err(SimulationError::Rpc(e.into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::aliases::U48;
+    use alloy::primitives::{address, U256};
+    use alloy::providers::ProviderBuilder;
+    use alloy::rpc::json_rpc::ErrorPayload;
+    use alloy::sol_types::{Revert, SolError};
+    use alloy::transports::mock::Asserter;
+    use config::config::{PolicyConfig, PolicySource};
+    use db::policies::PolicyRepository;
+    use metrics::MetricsCollector;
+    use std::path::PathBuf;
+    use std::sync::OnceLock;
+
+    const FORWARDER: Address = address!("0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f");
+
+    /// A simulator whose target declares `Paused(address)`.
+    async fn simulator(asserter: &Asserter) -> Simulator {
+        let provider = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .connect_mocked_client(asserter.clone())
+            .erased();
+        // Written once; tests run concurrently
+        static POLICY: OnceLock<PathBuf> = OnceLock::new();
+        let path = POLICY.get_or_init(|| {
+            let path = std::env::temp_dir().join(format!("simulation-policy-{}.toml", std::process::id()));
+            std::fs::write(
+                &path,
+                "[[contracts]]\naddress = \"0x5FbDB2315678afecb367f032d93F642f64180aa3\"\nerrors = [\"Paused(address)\"]\n",
+            )
+            .unwrap();
+            path
+        });
+        let policy_config = PolicyConfig {
+            source: PolicySource::File(path.clone()),
+            reload_interval: Duration::from_secs(30),
+        };
+        // The file source never touches the database
+        let pool = sqlx::PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let policy = PolicyEngine::load(&policy_config, PolicyRepository::new(pool))
+            .await
+            .unwrap();
+        Simulator::new(
+            SimulationConfig {
+                enabled: true,
+                resimulate_after: Duration::from_secs(30),
+            },
+            11155111,
+            FORWARDER,
+            address!("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"),
+            provider,
+            policy,
+            MetricsCollector::new().unwrap().chain(11155111),
+        )
+    }
+
+    fn request() -> TrustedForwarder::ForwardRequestData {
+        TrustedForwarder::ForwardRequestData {
+            from: address!("0x70997970C51812dc3A010C7d01b50e0d17dc79C8"),
+            to: address!("0x5FbDB2315678afecb367f032d93F642f64180aa3"),
+            value: U256::ZERO,
+            gas: U256::from(100_000),
+            deadline: U48::from(1_900_000_000u64),
+            data: Bytes::from_static(&[0xd0, 0x9d, 0xe0, 0x8a]),
+            signature: Bytes::from(vec![0xab; 65]),
+        }
+    }
+
+    fn reverted(data: &[u8]) -> ErrorPayload {
+        ErrorPayload {
+            code: 3,
+            message: "execution reverted".into(),
+            data: Some(serde_json::value::to_raw_value(&Bytes::copy_from_slice(data)).unwrap()),
+        }
+    }
+
+    fn revert_of(result: Result<(), SimulationError>) -> SimulatedRevert {
+        match result {
+            Err(SimulationError::Reverted(revert)) => revert,
+            Err(SimulationError::Rpc(e)) => panic!("unexpected RPC error: {e}"),
+            Ok(()) => panic!("expected a revert"),
+        }
+    }
+
+    #[tokio::test]
+    async fn passes_requests_that_execute() {
+        let asserter = Asserter::new();
+        let simulator = simulator(&asserter).await;
+        asserter.push_success(&Bytes::new());
+        assert!(simulator.simulate(&request()).await.is_ok());
+        assert!(simulator.is_stale(Duration::from_secs(31)));
+        assert!(!simulator.is_stale(Duration::from_secs(5)));
+    }
+
+    #[tokio::test]
+    async fn decodes_forwarder_errors() {
+        let asserter = Asserter::new();
+        let simulator = simulator(&asserter).await;
+        let data = TrustedForwarder::ERC2771ForwarderInvalidSigner {
+            signer: address!("0x0000000000000000000000000000000000000001"),
+            from: request().from,
+        }
+        .abi_encode();
+        asserter.push_failure(reverted(&data));
+
+        let revert = revert_of(simulator.simulate(&request()).await);
+        assert!(!revert.in_target);
+        assert!(revert.reason.starts_with(
+            "ERC2771ForwarderInvalidSigner: signed by 0x0000000000000000000000000000000000000001"
+        ));
+        assert_eq!(revert.data.as_ref(), data.as_slice());
+        assert_eq!(simulator.metrics.simulation_reverts.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn replays_failed_target_calls() {
+        let asserter = Asserter::new();
+        let simulator = simulator(&asserter).await;
+        asserter.push_failure(reverted(&TrustedForwarder::FailedInnerCall {}.abi_encode()));
+        asserter.push_failure(reverted(&Revert::from("counter is paused").abi_encode()));
+
+        let revert = revert_of(simulator.simulate(&request()).await);
+        assert!(revert.in_target);
+        assert_eq!(
+            revert.to_string(),
+            "target call reverted: revert: counter is paused"
+        );
+
+        // Custom errors declared in the policy are named with their arguments
+        let mut paused = alloy::primitives::keccak256("Paused(address)")[..4].to_vec();
+        paused.extend_from_slice(&[0u8; 12]);
+        paused.extend_from_slice(request().from.as_slice());
+        asserter.push_failure(reverted(&TrustedForwarder::FailedCall {}.abi_encode()));
+        asserter.push_failure(reverted(&paused));
+        let revert = revert_of(simulator.simulate(&request()).await);
+        assert_eq!(
+            revert.reason,
+            "Paused(0x70997970C51812dc3A010C7d01b50e0d17dc79C8)"
+        );
+        assert_eq!(revert.data.as_ref(), paused.as_slice());
+    }
+
+    #[tokio::test]
+    async fn simulates_batches_as_a_whole() {
+        let asserter = Asserter::new();
+        let simulator = simulator(&asserter).await;
+        asserter.push_success(&Bytes::new());
+        assert!(simulator
+            .simulate_batch(&[request(), request()])
+            .await
+            .is_ok());
+
+        let data = TrustedForwarder::ERC2771ForwarderExpiredRequest {
+            deadline: U48::from(1_700_000_000u64),
+        }
+        .abi_encode();
+        asserter.push_failure(reverted(&data));
+        let revert = revert_of(simulator.simulate_batch(&[request()]).await);
+        assert!(!revert.in_target);
+        assert_eq!(revert.data.as_ref(), data.as_slice());
+    }
+
+    #[tokio::test]
+    async fn reports_node_failures_apart_from_reverts() {
+        let asserter = Asserter::new();
+        let simulator = simulator(&asserter).await;
+        asserter.push_failure_msg("header not found");
+        assert!(matches!(
+            simulator.simulate(&request()).await,
+            Err(SimulationError::Rpc(_))
+        ));
+    }
+}
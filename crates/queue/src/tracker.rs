@@ -0,0 +1,747 @@
+use crate::batch::{CallFrame, CallTracer, MinedBatch};
+use crate::events::{RelayEvent, RelayEventBus, RelayEventKind};
+use crate::gas_bumper::bump_fees;
+use crate::gas_oracle::GasOracle;
+use crate::worker::{send_failure, SendFailure};
+use alloy::eips::eip1559::Eip1559Estimation;
+use alloy::eips::BlockId;
+use alloy::network::{EthereumWallet, TransactionBuilder};
+use alloy::primitives::{Address, Bytes, U256};
+use alloy::providers::{DynProvider, Provider};
+use alloy::rpc::types::{TransactionReceipt, TransactionRequest};
+use config::config::TrackerConfig;
+use db::db::{NewTxAttempt, StatusUpdate, TxAttempt, TxRequest, TxRequestRepository, TxStatus};
+use forwarder::decode_revert_reason;
+use metrics::ChainMetrics;
+use std::collections::BTreeMap;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+
+/// Submitted requests checked per poll; the rest wait for the next ones.
+const RECEIPT_BATCH_SIZE: i64 = 100;
+
+/// Errors of batched requests that failed although their transaction did not revert.
+const TARGET_CALL_REVERTED: &str = "target call reverted";
+const SKIPPED_BY_FORWARDER: &str =
+    "skipped by the forwarder: invalid signature, nonce, deadline or target";
+
+/// Error of requests whose nonce was mined without any of their transactions.
+const NONCE_USED_ELSEWHERE: &str = "nonce was used by another transaction of the relayer";
+
+/// Polls receipts of submitted transactions and settles their requests as `confirmed`
+/// or `failed` once they are buried under enough blocks. Transactions that stay unmined
+/// are replaced with higher fees.
+#[derive(Clone)]
+pub struct ReceiptTracker {
+    config: TrackerConfig,
+    chain_id: u64,
+    relayer: Address,
+    tx_requests: TxRequestRepository,
+    provider: DynProvider,
+    /// Signs replacements before they are sent, so their hash is known even if sending fails.
+    wallet: EthereumWallet,
+    gas_oracle: GasOracle,
+    events: RelayEventBus,
+    metrics: ChainMetrics,
+}
+
+impl ReceiptTracker {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        config: TrackerConfig,
+        chain_id: u64,
+        relayer: Address,
+        tx_requests: TxRequestRepository,
+        provider: DynProvider,
+        wallet: EthereumWallet,
+        gas_oracle: GasOracle,
+        events: RelayEventBus,
+        metrics: ChainMetrics,
+    ) -> Self {
+        Self {
+            config,
+            chain_id,
+            relayer,
+            tx_requests,
+            provider,
+            wallet,
+            gas_oracle,
+            events,
+            metrics,
+        }
+    }
+
+    /// Runs in a span carrying the chain id, so every chain's log lines can be told apart.
+    pub fn spawn(self, shutdown: watch::Receiver<bool>) -> JoinHandle<()> {
+        let span = tracing::info_span!("receipt_tracker", chain_id = self.chain_id);
+        tokio::spawn(self.run(shutdown).instrument(span))
+    }
+
+    pub async fn run(self, mut shutdown: watch::Receiver<bool>) {
+        tracing::info!(
+            confirmations = self.config.confirmations,
+            "Receipt tracker started"
+        );
+
+        while !*shutdown.borrow() {
+            if let Err(e) = self.poll().await {
+                tracing::error!("Receipt poll failed: {}", e);
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(self.config.poll_interval) => {}
+                _ = shutdown.changed() => {}
+            }
+        }
+
+        tracing::info!("Receipt tracker stopped");
+    }
+
+    /// Checks the `RECEIPT_BATCH_SIZE` least recently checked submitted requests, so every
+    /// one gets its turn. Requests sharing a transaction are settled together.
+    pub async fn poll(&self) -> anyhow::Result<()> {
+        match self
+            .tx_requests
+            .count_stuck(self.chain_id as i64, self.config.max_pending.as_secs_f64())
+            .await
+        {
+            Ok(stuck) => self.metrics.transactions_stuck.set(stuck),
+            Err(e) => tracing::warn!("Failed to count stuck transactions: {}", e),
+        }
+
+        let submitted = self
+            .tx_requests
+            .list_submitted(self.chain_id as i64, RECEIPT_BATCH_SIZE)
+            .await?;
+        if submitted.is_empty() {
+            return Ok(());
+        }
+
+        let latest_block = self.provider.get_block_number().await?;
+
+        let mut by_hash: BTreeMap<String, Vec<TxRequest>> = BTreeMap::new();
+        for request in submitted {
+            if let Some(tx_hash) = request.tx_hash.clone() {
+                by_hash.entry(tx_hash).or_default().push(request);
+            }
+        }
+
+        for (tx_hash, mut requests) in by_hash {
+            // A batch may straddle pages; it is replaced and settled whole
+            if let Some(batch_id) = requests[0].batch_id {
+                match self.tx_requests.batch_members(batch_id).await {
+                    Ok(members) => {
+                        requests = members
+                            .into_iter()
+                            .filter(|member| {
+                                member.status == TxStatus::Submitted
+                                    && member.tx_hash.as_deref() == Some(tx_hash.as_str())
+                            })
+                            .collect()
+                    }
+                    Err(e) => {
+                        tracing::warn!(%tx_hash, "Failed to read relay batch: {}", e);
+                        continue;
+                    }
+                }
+            }
+            if requests.is_empty() {
+                continue;
+            }
+            if let Err(e) = self.check(&requests, latest_block).await {
+                tracing::warn!(%tx_hash, "Failed to check transaction: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Looks for a receipt of any attempt sharing the requests' nonce, newest first,
+    /// and replaces the transaction with higher fees if none mined within `bump_after`.
+    async fn check(&self, requests: &[TxRequest], latest_block: u64) -> anyhow::Result<()> {
+        let attempts = self.tx_requests.attempts(requests[0].id).await?;
+        let mut hashes: Vec<String> = attempts.iter().rev().map(|a| a.tx_hash.clone()).collect();
+        if hashes.is_empty() {
+            hashes.extend(requests[0].tx_hash.clone());
+        }
+
+        for tx_hash in &hashes {
+            let Some(receipt) = self
+                .provider
+                .get_transaction_receipt(tx_hash.parse()?)
+                .await?
+            else {
+                continue;
+            };
+
+            let Some(block_number) = receipt.block_number else {
+                return Ok(());
+            };
+            if latest_block + 1 < block_number + self.config.confirmations {
+                return Ok(());
+            }
+
+            // Every attempt carries the same calldata
+            let input = attempts.first().map(|attempt| attempt.input.as_slice());
+            self.settle(requests, &receipt, input).await;
+            return Ok(());
+        }
+
+        let Some(latest) = attempts.last() else {
+            return Ok(());
+        };
+        let waited = (chrono::Utc::now() - latest.created_at)
+            .to_std()
+            .unwrap_or_default();
+        if waited < self.config.bump_after {
+            return Ok(());
+        }
+
+        // Without a receipt of any attempt, a mined nonce means another transaction took it
+        // and none of ours can be mined any more
+        if self.nonce_mined(latest.nonce as u64, latest_block).await? {
+            self.fail_unmined(requests).await;
+            return Ok(());
+        }
+        self.bump(requests, latest).await?;
+
+        Ok(())
+    }
+
+    /// Whether the relayer's `nonce` was mined under `confirmations` blocks by `latest_block`.
+    /// Receipts are looked up after `latest_block` is read, so one of ours mined by then is found.
+    async fn nonce_mined(&self, nonce: u64, latest_block: u64) -> anyhow::Result<bool> {
+        let block = (latest_block + 1).saturating_sub(self.config.confirmations);
+        let mined = self
+            .provider
+            .get_transaction_count(self.relayer)
+            .block_id(BlockId::number(block))
+            .await?;
+        Ok(mined > nonce)
+    }
+
+    async fn fail_unmined(&self, requests: &[TxRequest]) {
+        let update = StatusUpdate {
+            error: Some(NONCE_USED_ELSEWHERE.to_string()),
+            ..Default::default()
+        };
+        for request in requests {
+            match self
+                .tx_requests
+                .transition_status(request.id, TxStatus::Submitted, TxStatus::Failed, &update)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    tracing::error!(id = %request.id, "Failed to fail unmined request: {}", e);
+                    continue;
+                }
+            }
+            self.events.publish(RelayEvent::new(
+                RelayEventKind::Failed,
+                request,
+                TxStatus::Failed,
+                &update,
+            ));
+            let processing_time = (chrono::Utc::now() - request.created_at)
+                .as_seconds_f64()
+                .max(0.0);
+            self.metrics.transactions_pending.dec();
+            self.metrics.record_transaction_failure(processing_time);
+            tracing::warn!(id = %request.id, "Relay request failed: {}", NONCE_USED_ELSEWHERE);
+        }
+    }
+
+    /// Re-signs the latest attempt with the same nonce and fees raised per `bump_fees`.
+    ///
+    /// The replacement is recorded as an attempt and the requests point at it before it is
+    /// sent, so it is found if it mines even when sending it failed. Only a refusal of the
+    /// node undoes that, pointing the requests back at the transaction it was to replace.
+    async fn bump(&self, requests: &[TxRequest], latest: &TxAttempt) -> anyhow::Result<()> {
+        let previous = Eip1559Estimation {
+            max_fee_per_gas: latest.max_fee_per_gas.parse()?,
+            max_priority_fee_per_gas: latest.max_priority_fee_per_gas.parse()?,
+        };
+        let network = self.gas_oracle.suggest().await?;
+        let Some(fees) = bump_fees(&previous, &network, self.config.max_fee_cap) else {
+            tracing::warn!(
+                tx_hash = %latest.tx_hash,
+                max_fee_per_gas = previous.max_fee_per_gas,
+                "Fee cap reached, no longer bumping"
+            );
+            return Ok(());
+        };
+
+        let tx = TransactionRequest::default()
+            .with_from(self.relayer)
+            .with_to(latest.to_address.parse()?)
+            .with_input(Bytes::copy_from_slice(&latest.input))
+            .with_value(latest.value.parse::<U256>()?)
+            .with_nonce(latest.nonce as u64)
+            .with_chain_id(self.chain_id)
+            .with_gas_limit(latest.gas_limit as u64)
+            .with_max_fee_per_gas(fees.max_fee_per_gas)
+            .with_max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+            .build(&self.wallet)
+            .await?;
+        let tx_hash = tx.tx_hash().to_string();
+
+        if let Err(e) = self
+            .record_replacement(requests, latest, &tx_hash, &fees)
+            .await
+        {
+            self.undo_replacement(requests, &tx_hash, &latest.tx_hash)
+                .await;
+            return Err(e);
+        }
+
+        if let Err(e) = self.provider.send_tx_envelope(tx).await {
+            match send_failure(&e) {
+                SendFailure::Rejected { .. } => {
+                    self.undo_replacement(requests, &tx_hash, &latest.tx_hash)
+                        .await;
+                    return Err(e.into());
+                }
+                SendFailure::Unknown => tracing::warn!(
+                    %tx_hash,
+                    nonce = latest.nonce,
+                    "Sending the replacement failed, keeping it in case it reached the node: {}",
+                    e
+                ),
+            }
+        }
+
+        self.metrics.queue_retries_total.inc();
+        tracing::info!(
+            replaced = %latest.tx_hash,
+            %tx_hash,
+            nonce = latest.nonce,
+            max_fee_per_gas = fees.max_fee_per_gas,
+            "Replaced stuck transaction with higher fees"
+        );
+
+        Ok(())
+    }
+
+    async fn record_replacement(
+        &self,
+        requests: &[TxRequest],
+        latest: &TxAttempt,
+        tx_hash: &str,
+        fees: &Eip1559Estimation,
+    ) -> anyhow::Result<()> {
+        for request in requests {
+            let attempt = NewTxAttempt {
+                tx_request_id: request.id,
+                tx_hash: tx_hash.to_string(),
+                nonce: latest.nonce,
+                to_address: latest.to_address.clone(),
+                value: latest.value.clone(),
+                input: latest.input.clone(),
+                gas_limit: latest.gas_limit,
+                max_fee_per_gas: fees.max_fee_per_gas.to_string(),
+                max_priority_fee_per_gas: fees.max_priority_fee_per_gas.to_string(),
+            };
+            self.tx_requests.record_attempt(&attempt).await?;
+        }
+        for request in requests {
+            self.point_at(request, tx_hash).await?;
+        }
+        Ok(())
+    }
+
+    /// Forgets a replacement that never reached the node.
+    async fn undo_replacement(&self, requests: &[TxRequest], tx_hash: &str, previous: &str) {
+        if let Err(e) = self.tx_requests.delete_attempts(tx_hash).await {
+            tracing::error!(%tx_hash, "Failed to delete attempts of an unsent replacement: {}", e);
+        }
+        for request in requests {
+            if let Err(e) = self.point_at(request, previous).await {
+                tracing::error!(id = %request.id, %tx_hash, "Failed to undo replacement: {}", e);
+            }
+        }
+    }
+
+    /// Points a submitted request at `tx_hash` and publishes the replacement.
+    async fn point_at(&self, request: &TxRequest, tx_hash: &str) -> anyhow::Result<()> {
+        if self
+            .tx_requests
+            .replace_tx_hash(request.id, tx_hash)
+            .await?
+        {
+            let update = StatusUpdate {
+                tx_hash: Some(tx_hash.to_string()),
+                ..Default::default()
+            };
+            self.events.publish(RelayEvent::new(
+                RelayEventKind::Replaced,
+                request,
+                TxStatus::Submitted,
+                &update,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Settles the requests of a mined transaction. Requests of an `executeBatch` each get
+    /// their own outcome and share of the gas; `input` is the transaction's calldata.
+    async fn settle(
+        &self,
+        requests: &[TxRequest],
+        receipt: &TransactionReceipt,
+        input: Option<&[u8]>,
+    ) {
+        let succeeded = receipt.status();
+        let revert = if succeeded {
+            None
+        } else {
+            Some(self.revert_reason(receipt).await)
+        };
+        let mut batch = input.and_then(|input| MinedBatch::new(input, receipt));
+        let tx_hash = receipt.transaction_hash.to_string();
+        if let Some(batch) = batch.as_mut() {
+            match self
+                .provider
+                .raw_request::<_, CallFrame>(
+                    "debug_traceTransaction".into(),
+                    (receipt.transaction_hash, CallTracer::default()),
+                )
+                .await
+            {
+                Ok(trace) => batch.attribute(&trace),
+                Err(e) => tracing::warn!(
+                    %tx_hash,
+                    "No call trace of the batch, splitting its gas by gas limit: {}",
+                    e
+                ),
+            }
+        }
+
+        if let Err(e) = self.tx_requests.mark_attempt_mined(&tx_hash).await {
+            tracing::error!(%tx_hash, "Failed to flag mined attempt: {}", e);
+        }
+
+        for request in requests {
+            let outcome = batch
+                .as_ref()
+                .and_then(|batch| batch.outcome_of(&request.signature));
+            let (gas_used, error) = match outcome {
+                Some(outcome) if succeeded => (
+                    outcome.gas_used,
+                    match outcome.executed {
+                        Some(true) => None,
+                        Some(false) => Some(TARGET_CALL_REVERTED.to_string()),
+                        None => Some(SKIPPED_BY_FORWARDER.to_string()),
+                    },
+                ),
+                Some(outcome) => (outcome.gas_used, revert.clone()),
+                None => (receipt.gas_used, revert.clone()),
+            };
+
+            let update = StatusUpdate {
+                tx_hash: Some(tx_hash.clone()),
+                gas_used: Some(gas_used as i64),
+                effective_gas_price: Some(receipt.effective_gas_price.to_string()),
+                block_number: receipt.block_number.map(|n| n as i64),
+                error: error.clone(),
+                ..Default::default()
+            };
+            let (status, event) = if error.is_none() {
+                (TxStatus::Confirmed, RelayEventKind::Confirmed)
+            } else {
+                (TxStatus::Failed, RelayEventKind::Failed)
+            };
+
+            match self
+                .tx_requests
+                .transition_status(request.id, TxStatus::Submitted, status, &update)
+                .await
+            {
+                Ok(true) => self
+                    .events
+                    .publish(RelayEvent::new(event, request, status, &update)),
+                Ok(false) => continue,
+                Err(e) => {
+                    tracing::error!(id = %request.id, "Failed to record receipt: {}", e);
+                    continue;
+                }
+            }
+
+            let processing_time = (chrono::Utc::now() - request.created_at)
+                .as_seconds_f64()
+                .max(0.0);
+            self.metrics.transactions_pending.dec();
+            match error {
+                None => {
+                    self.metrics
+                        .record_transaction_success(processing_time, gas_used as f64);
+                    tracing::info!(id = %request.id, tx_hash = %receipt.transaction_hash, "Relay request confirmed");
+                }
+                Some(reason) => {
+                    self.metrics.record_transaction_failure(processing_time);
+                    tracing::warn!(
+                        id = %request.id,
+                        tx_hash = %receipt.transaction_hash,
+                        reason,
+                        "Relay request reverted"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Receipts carry no revert data, so the transaction is replayed with `eth_call`
+    /// on the state of the block before it was mined.
+    async fn revert_reason(&self, receipt: &TransactionReceipt) -> String {
+        let tx = match self
+            .provider
+            .get_transaction_by_hash(receipt.transaction_hash)
+            .await
+        {
+            Ok(Some(tx)) => tx,
+            Ok(None) => return decode_revert_reason(&[]),
+            Err(e) => {
+                tracing::warn!(tx_hash = %receipt.transaction_hash, "Failed to fetch reverted transaction: {}", e);
+                return decode_revert_reason(&[]);
+            }
+        };
+
+        let request = tx.into_request();
+        if request
+            .gas
+            .is_some_and(|gas_limit| receipt.gas_used >= gas_limit)
+        {
+            return "out of gas".to_string();
+        }
+
+        let parent = receipt.block_number.unwrap_or_default().saturating_sub(1);
+        match self
+            .provider
+            .call(request)
+            .block(BlockId::number(parent))
+            .await
+        {
+            Err(e) => {
+                let data = e
+                    .as_error_resp()
+                    .and_then(|payload| payload.as_revert_data())
+                    .unwrap_or_default();
+                decode_revert_reason(&data)
+            }
+            // The replay passed on the parent state, so the revert depended on an earlier transaction in the block.
+            Ok(_) => decode_revert_reason(&[]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::U64;
+    use alloy::providers::ProviderBuilder;
+    use alloy::rpc::types::FeeHistory;
+    use alloy::signers::local::PrivateKeySigner;
+    use alloy::transports::mock::Asserter;
+    use config::config::GasOracleConfig;
+    use db::db::NewTxRequest;
+    use db::tenants::DEFAULT_TENANT_ID;
+    use metrics::MetricsCollector;
+    use sqlx::PgPool;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    // Hardhat/Anvil account #0
+    const RELAYER_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const FORWARDER: &str = "0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f";
+    const GWEI: u128 = 1_000_000_000;
+
+    fn tracker(
+        asserter: &Asserter,
+        tx_requests: TxRequestRepository,
+        shutdown: watch::Receiver<bool>,
+    ) -> ReceiptTracker {
+        let provider = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .connect_mocked_client(asserter.clone())
+            .erased();
+        let signer: PrivateKeySigner = RELAYER_KEY.parse().unwrap();
+        let metrics = MetricsCollector::new().unwrap().chain(11155111);
+        let gas_oracle = GasOracle::new(
+            GasOracleConfig {
+                percentiles: [10.0, 50.0, 90.0],
+                block_count: 1,
+                cache_ttl: Duration::ZERO,
+            },
+            provider.clone(),
+            metrics.clone(),
+        );
+        ReceiptTracker::new(
+            TrackerConfig {
+                confirmations: 1,
+                poll_interval: Duration::from_secs(3),
+                bump_after: Duration::from_secs(60),
+                max_fee_cap: 500 * GWEI,
+                max_pending: Duration::from_secs(3600),
+            },
+            11155111,
+            signer.address(),
+            tx_requests,
+            provider,
+            EthereumWallet::from(signer),
+            gas_oracle,
+            RelayEventBus::new(16, shutdown),
+            metrics,
+        )
+    }
+
+    /// A request submitted a while ago as `tx_hash` with nonce 7, at 10 gwei.
+    async fn submitted(tx_requests: &TxRequestRepository, pool: &PgPool, tx_hash: &str) -> Uuid {
+        let id = tx_requests
+            .insert(&NewTxRequest {
+                chain_id: 11155111,
+                tenant_id: DEFAULT_TENANT_ID,
+                from_address: "0x70997970C51812dc3A010C7d01b50e0d17dc79C8".to_string(),
+                to_address: "0x5FbDB2315678afecb367f032d93F642f64180aa3".to_string(),
+                value: "0".to_string(),
+                gas: 100_000,
+                deadline: 1_900_000_000,
+                data: vec![0xd0, 0x9d, 0xe0, 0x8a],
+                signature: vec![0xab; 65],
+                estimated_cost_wei: None,
+                callback_url: None,
+            })
+            .await
+            .unwrap();
+        tx_requests.claim_pending(11155111, 1).await.unwrap();
+        tx_requests
+            .record_attempt(&NewTxAttempt {
+                tx_request_id: id,
+                tx_hash: tx_hash.to_string(),
+                nonce: 7,
+                to_address: FORWARDER.to_string(),
+                value: "0".to_string(),
+                input: vec![0xdf, 0x90, 0x5c, 0xaf],
+                gas_limit: 201_587,
+                max_fee_per_gas: (10 * GWEI).to_string(),
+                max_priority_fee_per_gas: GWEI.to_string(),
+            })
+            .await
+            .unwrap();
+        tx_requests
+            .transition_status(
+                id,
+                TxStatus::Processing,
+                TxStatus::Submitted,
+                &StatusUpdate {
+                    tx_hash: Some(tx_hash.to_string()),
+                    nonce: Some(7),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        sqlx::query("UPDATE tx_attempts SET created_at = NOW() - INTERVAL '5 minutes'")
+            .execute(pool)
+            .await
+            .unwrap();
+        id
+    }
+
+    fn fee_history() -> FeeHistory {
+        FeeHistory {
+            oldest_block: 100,
+            base_fee_per_gas: vec![10 * GWEI, 10 * GWEI],
+            gas_used_ratio: vec![0.5],
+            reward: Some(vec![vec![GWEI, 2 * GWEI, 3 * GWEI]]),
+            ..Default::default()
+        }
+    }
+
+    fn receipt(tx_hash: &str, block_number: u64) -> serde_json::Value {
+        serde_json::json!({
+            "type": "0x2",
+            "status": "0x1",
+            "cumulativeGasUsed": "0x1d4c0",
+            "logs": [],
+            "logsBloom": format!("0x{}", "00".repeat(256)),
+            "transactionHash": tx_hash,
+            "transactionIndex": "0x0",
+            "blockHash": format!("0x{}", "11".repeat(32)),
+            "blockNumber": U64::from(block_number),
+            "gasUsed": "0x1d4c0",
+            "effectiveGasPrice": "0x4a817c800",
+            "from": "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+            "to": FORWARDER,
+            "contractAddress": null
+        })
+    }
+
+    #[sqlx::test(migrations = "../db/migrations")]
+    async fn settles_replacements_whose_sending_failed(pool: PgPool) {
+        let tx_requests = TxRequestRepository::new(pool.clone());
+        let original = format!("0x{}", "0a".repeat(32));
+        let id = submitted(&tx_requests, &pool, &original).await;
+        let (_shutdown, shutdown) = watch::channel(false);
+        let asserter = Asserter::new();
+        let tracker = tracker(&asserter, tx_requests.clone(), shutdown);
+
+        // Unmined past `bump_after`, with the nonce still open: the transaction is replaced,
+        // and the node times out on the replacement after taking it
+        asserter.push_success(&U64::from(100));
+        asserter.push_success(&Option::<()>::None);
+        asserter.push_success(&U64::from(7));
+        asserter.push_success(&fee_history());
+        asserter.push_failure_msg("request timed out");
+        tracker.poll().await.unwrap();
+
+        let attempts = tx_requests.attempts(id).await.unwrap();
+        assert_eq!(attempts.len(), 2);
+        let replacement = attempts[1].tx_hash.clone();
+        assert_ne!(replacement, original);
+        assert_eq!(attempts[1].nonce, 7);
+        assert_eq!(attempts[1].max_fee_per_gas, (22 * GWEI).to_string());
+        let request = tx_requests.find_by_id(id).await.unwrap().unwrap();
+        assert_eq!(request.tx_hash.as_deref(), Some(replacement.as_str()));
+
+        // The replacement mines, and the request settles with it instead of failing
+        asserter.push_success(&U64::from(101));
+        asserter.push_success(&receipt(&replacement, 101));
+        tracker.poll().await.unwrap();
+
+        let request = tx_requests.find_by_id(id).await.unwrap().unwrap();
+        assert_eq!(request.status, TxStatus::Confirmed);
+        assert_eq!(request.tx_hash.as_deref(), Some(replacement.as_str()));
+        assert_eq!(request.gas_used, Some(120_000));
+        assert!(request.error.is_none());
+        let attempts = tx_requests.attempts(id).await.unwrap();
+        assert!(attempts[1].mined);
+    }
+
+    #[sqlx::test(migrations = "../db/migrations")]
+    async fn undoes_replacements_the_node_refuses(pool: PgPool) {
+        let tx_requests = TxRequestRepository::new(pool.clone());
+        let original = format!("0x{}", "0a".repeat(32));
+        let id = submitted(&tx_requests, &pool, &original).await;
+        let (_shutdown, shutdown) = watch::channel(false);
+        let asserter = Asserter::new();
+        let tracker = tracker(&asserter, tx_requests.clone(), shutdown);
+
+        asserter.push_success(&U64::from(100));
+        asserter.push_success(&Option::<()>::None);
+        asserter.push_success(&U64::from(7));
+        asserter.push_success(&fee_history());
+        asserter.push_failure(alloy::rpc::json_rpc::ErrorPayload {
+            code: -32000,
+            message: "replacement transaction underpriced".into(),
+            data: None,
+        });
+        tracker.poll().await.unwrap();
+
+        assert_eq!(tx_requests.attempts(id).await.unwrap().len(), 1);
+        let request = tx_requests.find_by_id(id).await.unwrap().unwrap();
+        assert_eq!(request.status, TxStatus::Submitted);
+        assert_eq!(request.tx_hash.as_deref(), Some(original.as_str()));
+    }
+}
@@ -0,0 +1,830 @@
+use crate::batch::{batch_gas_limit, BatchBuilder};
+use crate::events::{RelayEvent, RelayEventBus, RelayEventKind};
+use crate::gas_oracle::GasOracle;
+use crate::nonce_manager::NonceManager;
+use crate::simulation::{SimulationError, Simulator};
+use alloy::consensus::TxEnvelope;
+use alloy::eips::eip1559::Eip1559Estimation;
+use alloy::network::{EthereumWallet, TransactionBuilder};
+use alloy::primitives::aliases::U48;
+use alloy::primitives::{Address, Bytes, B256, U256};
+use alloy::providers::{DynProvider, Provider};
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol_types::SolCall;
+use alloy::transports::{RpcError, TransportErrorKind};
+use config::config::{BatchingConfig, QueueConfig};
+use db::db::{NewTxAttempt, StatusUpdate, TxRequest, TxRequestRepository, TxStatus};
+use forwarder::TrustedForwarder;
+use metrics::ChainMetrics;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Semaphore};
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Gas the forwarder itself burns on top of the request's own `gas` (signature check, nonce bump, call).
+const FORWARDER_GAS_OVERHEAD: u64 = 100_000;
+
+/// Gas limit of the `execute` transaction relaying a request with `gas`. The forwarder
+/// keeps 1/63 of the gas back for itself when calling the target (EIP-150).
+pub fn execute_gas_limit(gas: u64) -> u64 {
+    gas + gas / 63 + FORWARDER_GAS_OVERHEAD
+}
+
+/// How often the worker looks for nonces that were reserved but never broadcast.
+const GAP_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Requests stuck in `processing` this long belonged to a worker that died and are requeued.
+/// Every worker looks for them this often, so survivors pick up a dead replica's requests.
+const STALE_PROCESSING_AFTER: Duration = Duration::from_secs(300);
+
+/// Why sending a transaction failed, as far as the node's answer tells.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum SendFailure {
+    /// The node refused it, so it is not in the mempool. `nonce_taken` if another
+    /// transaction holds its nonce.
+    Rejected { nonce_taken: bool },
+    /// Timeouts, dropped connections and other answers: it may have reached the mempool.
+    Unknown,
+}
+
+/// Errors of nodes (geth, erigon, nethermind, reth) refusing a transaction that reached them.
+const REJECTIONS: [&str; 9] = [
+    "underpriced",
+    "insufficient funds",
+    "intrinsic gas too low",
+    "exceeds block gas limit",
+    "less than block base fee",
+    "fee cap less than",
+    "higher than max fee",
+    "tip higher than fee cap",
+    "nonce too high",
+];
+
+/// Errors meaning another transaction already used or holds the nonce.
+const NONCE_CONFLICTS: [&str; 2] = ["nonce too low", "replacement transaction underpriced"];
+
+pub(crate) fn send_failure(error: &RpcError<TransportErrorKind>) -> SendFailure {
+    let Some(payload) = error.as_error_resp() else {
+        return SendFailure::Unknown;
+    };
+    let message = payload.message.to_lowercase();
+    if NONCE_CONFLICTS.iter().any(|known| message.contains(known)) {
+        SendFailure::Rejected { nonce_taken: true }
+    } else if REJECTIONS.iter().any(|known| message.contains(known)) {
+        SendFailure::Rejected { nonce_taken: false }
+    } else {
+        SendFailure::Unknown
+    }
+}
+
+/// Claimed work, processed under one permit.
+enum Job {
+    Single(Box<TxRequest>),
+    /// Requests the worker grouped itself.
+    Batch(Vec<TxRequest>),
+    /// A batch sent through `POST /relay/batch`, in batch order.
+    Atomic(Vec<TxRequest>),
+}
+
+/// A call of the forwarder, ready to be signed.
+struct ForwarderCall {
+    input: Bytes,
+    value: U256,
+    gas_limit: u64,
+}
+
+enum ProcessError {
+    /// Retrying cannot help, e.g. the request expired.
+    Permanent(String),
+    /// Usually an RPC hiccup; the request goes back to the queue until `max_retries`.
+    Transient(anyhow::Error),
+}
+
+/// Pulls pending requests from `tx_requests`, wraps each in `TrustedForwarder.execute`, or
+/// several in `executeBatch`, signs it with the relayer account and broadcasts it.
+#[derive(Clone)]
+pub struct QueueWorker {
+    config: QueueConfig,
+    batching: BatchingConfig,
+    /// Gets the value of requests the forwarder skips in a batch.
+    refund_receiver: Address,
+    chain_id: u64,
+    forwarder: Address,
+    tx_requests: TxRequestRepository,
+    nonce_manager: NonceManager,
+    provider: DynProvider,
+    /// Signs transactions before they are sent, so their hash is known even if sending fails.
+    wallet: EthereumWallet,
+    gas_oracle: GasOracle,
+    simulator: Simulator,
+    events: RelayEventBus,
+    metrics: ChainMetrics,
+}
+
+impl QueueWorker {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        config: QueueConfig,
+        batching: BatchingConfig,
+        refund_receiver: Address,
+        chain_id: u64,
+        forwarder: Address,
+        tx_requests: TxRequestRepository,
+        nonce_manager: NonceManager,
+        provider: DynProvider,
+        wallet: EthereumWallet,
+        gas_oracle: GasOracle,
+        simulator: Simulator,
+        events: RelayEventBus,
+        metrics: ChainMetrics,
+    ) -> Self {
+        Self {
+            config,
+            batching,
+            refund_receiver,
+            chain_id,
+            forwarder,
+            tx_requests,
+            nonce_manager,
+            provider,
+            wallet,
+            gas_oracle,
+            simulator,
+            events,
+            metrics,
+        }
+    }
+
+    /// Runs in a span carrying the chain id, so every chain's log lines can be told apart.
+    pub fn spawn(self, shutdown: watch::Receiver<bool>) -> JoinHandle<()> {
+        let span = tracing::info_span!("queue_worker", chain_id = self.chain_id);
+        tokio::spawn(self.run(shutdown).instrument(span))
+    }
+
+    /// Runs until `shutdown` flips to `true`, then waits for in-flight requests to finish.
+    pub async fn run(self, mut shutdown: watch::Receiver<bool>) {
+        let semaphore = Arc::new(Semaphore::new(self.config.concurrency));
+        let mut in_flight = JoinSet::new();
+        let mut last_gap_check = Instant::now();
+        let mut batch = BatchBuilder::new(self.batching.clone());
+
+        self.requeue_stale().await;
+        let mut last_stale_check = Instant::now();
+
+        tracing::info!(
+            concurrency = self.config.concurrency,
+            batch_size = self.config.batch_size,
+            batching = self.batching.enabled,
+            "Queue worker started"
+        );
+
+        while !*shutdown.borrow() {
+            while in_flight.try_join_next().is_some() {}
+
+            if last_gap_check.elapsed() >= GAP_CHECK_INTERVAL {
+                if let Err(e) = self.nonce_manager.fill_gaps().await {
+                    tracing::error!("Nonce gap check failed: {}", e);
+                }
+                last_gap_check = Instant::now();
+            }
+            if last_stale_check.elapsed() >= STALE_PROCESSING_AFTER {
+                self.requeue_stale().await;
+                last_stale_check = Instant::now();
+            }
+
+            // Batches sent through the API go out whole, whether or not the worker batches
+            if semaphore.available_permits() > 0 {
+                match self
+                    .tx_requests
+                    .claim_pending_batch(self.chain_id as i64)
+                    .await
+                {
+                    Ok(requests) if !requests.is_empty() => {
+                        self.dispatch(&semaphore, &mut in_flight, Job::Atomic(requests))
+                            .await
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::error!("Failed to claim pending batch: {}", e),
+                }
+            }
+
+            // Batched requests share a permit, so claiming is only bounded by the batch size
+            let available = if self.batching.enabled && semaphore.available_permits() > 0 {
+                self.config.batch_size as usize
+            } else {
+                semaphore
+                    .available_permits()
+                    .min(self.config.batch_size as usize)
+            };
+            let mut claimed = 0;
+            if available > 0 {
+                match self
+                    .tx_requests
+                    .claim_pending(self.chain_id as i64, available as i64)
+                    .await
+                {
+                    Ok(requests) => {
+                        claimed = requests.len();
+                        for request in requests {
+                            // A request over the ceiling on its own would only close the batch
+                            if !self.batching.enabled
+                                || batch_gas_limit([request.gas as u64]) > self.batching.max_gas
+                            {
+                                self.dispatch(
+                                    &semaphore,
+                                    &mut in_flight,
+                                    Job::Single(Box::new(request)),
+                                )
+                                .await;
+                                continue;
+                            }
+                            if let Some(closed) = batch.push(request, Instant::now()) {
+                                self.dispatch(&semaphore, &mut in_flight, Job::Batch(closed))
+                                    .await;
+                            }
+                            if let Some(ready) = batch.ready(Instant::now()) {
+                                self.dispatch(&semaphore, &mut in_flight, Job::Batch(ready))
+                                    .await;
+                            }
+                        }
+                    }
+                    Err(e) => tracing::error!("Failed to claim pending requests: {}", e),
+                }
+            }
+            if let Some(ready) = batch.ready(Instant::now()) {
+                self.dispatch(&semaphore, &mut in_flight, Job::Batch(ready))
+                    .await;
+            }
+
+            self.update_queue_depth().await;
+
+            // A full batch means more work is probably waiting, so only sleep when idle.
+            if claimed == 0 || claimed < available {
+                let sleep = batch
+                    .time_left(Instant::now())
+                    .map_or(self.config.poll_interval, |left| {
+                        left.min(self.config.poll_interval)
+                    });
+                tokio::select! {
+                    _ = tokio::time::sleep(sleep) => {}
+                    _ = shutdown.changed() => {}
+                }
+            }
+        }
+
+        // Claimed requests are not left waiting for a window that will not close
+        if !batch.is_empty() {
+            self.dispatch(&semaphore, &mut in_flight, Job::Batch(batch.take()))
+                .await;
+        }
+
+        tracing::info!(
+            in_flight = in_flight.len(),
+            "Queue worker shutting down, waiting for in-flight requests"
+        );
+        while in_flight.join_next().await.is_some() {}
+        tracing::info!("Queue worker stopped");
+    }
+
+    /// Processes `job` in the background once a permit is free.
+    async fn dispatch(&self, semaphore: &Arc<Semaphore>, in_flight: &mut JoinSet<()>, job: Job) {
+        let permit = semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("queue semaphore is never closed");
+        let worker = self.clone();
+        in_flight.spawn(
+            async move {
+                match job {
+                    Job::Single(request) => worker.process(*request).await,
+                    Job::Batch(requests) => worker.process_batch(requests).await,
+                    Job::Atomic(requests) => worker.process_atomic(requests).await,
+                }
+                drop(permit);
+            }
+            .in_current_span(),
+        );
+    }
+
+    fn observe_wait(&self, request: &TxRequest) {
+        let waited = (chrono::Utc::now() - request.created_at).as_seconds_f64();
+        self.metrics.queue_processing_time.observe(waited.max(0.0));
+    }
+
+    async fn process(&self, request: TxRequest) {
+        self.observe_wait(&request);
+
+        let result = match self.prepare(&request, true).await {
+            Ok(data) => {
+                self.broadcast(std::slice::from_ref(&request), execute_call(data))
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok((tx_hash, nonce)) => self.submitted(&[request], tx_hash, nonce).await,
+            Err(e) => self.handle_error(&request, e).await,
+        }
+    }
+
+    /// Sends requests the worker grouped itself in one non-atomic `executeBatch`, so an
+    /// invalid request is skipped instead of reverting the others. Requests that cannot go
+    /// out are settled on their own, and a lone remaining request is sent with `execute`.
+    async fn process_batch(&self, requests: Vec<TxRequest>) {
+        let mut ready = Vec::with_capacity(requests.len());
+        for request in requests {
+            self.observe_wait(&request);
+            match self.prepare(&request, true).await {
+                Ok(data) => ready.push((request, data)),
+                Err(e) => self.handle_error(&request, e).await,
+            }
+        }
+
+        if ready.len() <= 1 {
+            if let Some((request, data)) = ready.pop() {
+                match self
+                    .broadcast(std::slice::from_ref(&request), execute_call(data))
+                    .await
+                {
+                    Ok((tx_hash, nonce)) => self.submitted(&[request], tx_hash, nonce).await,
+                    Err(e) => self.handle_error(&request, e).await,
+                }
+            }
+            return;
+        }
+
+        let (requests, data): (Vec<TxRequest>, Vec<_>) = ready.into_iter().unzip();
+        match self
+            .broadcast(&requests, execute_batch_call(data, self.refund_receiver))
+            .await
+        {
+            Ok((tx_hash, nonce)) => {
+                let ids: Vec<Uuid> = requests.iter().map(|request| request.id).collect();
+                match self
+                    .tx_requests
+                    .create_batch(
+                        self.chain_id as i64,
+                        &self.refund_receiver.to_checksum(None),
+                        &ids,
+                    )
+                    .await
+                {
+                    Ok(batch_id) => {
+                        tracing::info!(%batch_id, %tx_hash, requests = ids.len(), "Relay batch submitted")
+                    }
+                    Err(e) => tracing::error!(%tx_hash, "Failed to record relay batch: {}", e),
+                }
+                self.metrics.batch_size.observe(requests.len() as f64);
+                self.submitted(&requests, tx_hash, nonce).await;
+            }
+            Err(e) => self.handle_errors(&requests, e).await,
+        }
+    }
+
+    /// Sends a batch from `POST /relay/batch` as an atomic `executeBatch`: its requests
+    /// go out together or not at all.
+    async fn process_atomic(&self, requests: Vec<TxRequest>) {
+        let mut data = Vec::with_capacity(requests.len());
+        for request in &requests {
+            self.observe_wait(request);
+            match self.prepare(request, false).await {
+                Ok(request) => data.push(request),
+                Err(ProcessError::Permanent(reason)) => {
+                    let reason = format!("{reason} (request {} of the batch)", request.id);
+                    return self
+                        .handle_errors(&requests, ProcessError::Permanent(reason))
+                        .await;
+                }
+                Err(e) => return self.handle_errors(&requests, e).await,
+            }
+        }
+
+        // Members of one signer depend on each other's nonces, so the batch is only
+        // simulated as a whole
+        let waited = requests
+            .iter()
+            .map(|request| {
+                (chrono::Utc::now() - request.created_at)
+                    .to_std()
+                    .unwrap_or_default()
+            })
+            .max()
+            .unwrap_or_default();
+        if self.simulator.is_stale(waited) {
+            if let Err(e) = self.simulator.simulate_batch(&data).await {
+                return self.handle_errors(&requests, simulation_error(e)).await;
+            }
+        }
+
+        match self
+            .broadcast(&requests, execute_batch_call(data, Address::ZERO))
+            .await
+        {
+            Ok((tx_hash, nonce)) => {
+                tracing::info!(batch_id = ?requests[0].batch_id, %tx_hash, requests = requests.len(), "Relay batch submitted");
+                self.metrics.batch_size.observe(requests.len() as f64);
+                self.submitted(&requests, tx_hash, nonce).await;
+            }
+            Err(e) => self.handle_errors(&requests, e).await,
+        }
+    }
+
+    /// The request as `TrustedForwarder` takes it, unless it expired or, having waited
+    /// long, would now revert.
+    async fn prepare(
+        &self,
+        request: &TxRequest,
+        simulate: bool,
+    ) -> Result<TrustedForwarder::ForwardRequestData, ProcessError> {
+        if request.deadline <= chrono::Utc::now().timestamp() {
+            return Err(ProcessError::Permanent("deadline expired".to_string()));
+        }
+
+        let forward_request = forward_request_data(request).map_err(ProcessError::Permanent)?;
+
+        // What passed the simulation on arrival may revert now, e.g. once its nonce was used
+        let waited = (chrono::Utc::now() - request.created_at)
+            .to_std()
+            .unwrap_or_default();
+        if simulate && self.simulator.is_stale(waited) {
+            self.simulator
+                .simulate(&forward_request)
+                .await
+                .map_err(simulation_error)?;
+        }
+        Ok(forward_request)
+    }
+
+    /// Signs `call` with the next relayer nonce, records it as an attempt of every request it
+    /// relays and sends it. Recording first means a broadcast is never lost, even if the
+    /// worker dies before marking the requests submitted.
+    ///
+    /// The nonce is only given back when the node refused the transaction. After a timeout
+    /// or a dropped connection it may be in the mempool anyway, so it is treated as sent:
+    /// the tracker then finds its receipt or replaces it under the same nonce.
+    async fn broadcast(
+        &self,
+        requests: &[TxRequest],
+        call: ForwarderCall,
+    ) -> Result<(B256, u64), ProcessError> {
+        let fees = self
+            .gas_oracle
+            .suggest()
+            .await
+            .map_err(ProcessError::Transient)?;
+        let nonce = self
+            .nonce_manager
+            .reserve(Some(requests[0].id))
+            .await
+            .map_err(ProcessError::Transient)?;
+
+        let tx = match self.sign(&call, nonce, &fees).await {
+            Ok(tx) => tx,
+            Err(e) => {
+                self.release_nonce(nonce, false).await;
+                return Err(ProcessError::Transient(e));
+            }
+        };
+        let tx_hash = *tx.tx_hash();
+
+        for request in requests {
+            let attempt = NewTxAttempt {
+                tx_request_id: request.id,
+                tx_hash: tx_hash.to_string(),
+                nonce: nonce as i64,
+                to_address: self.forwarder.to_checksum(None),
+                value: call.value.to_string(),
+                input: call.input.to_vec(),
+                gas_limit: call.gas_limit as i64,
+                max_fee_per_gas: fees.max_fee_per_gas.to_string(),
+                max_priority_fee_per_gas: fees.max_priority_fee_per_gas.to_string(),
+            };
+            if let Err(e) = self.tx_requests.record_attempt(&attempt).await {
+                self.forget_attempts(tx_hash).await;
+                self.release_nonce(nonce, false).await;
+                return Err(ProcessError::Transient(e));
+            }
+        }
+
+        match self.provider.send_tx_envelope(tx).await {
+            Ok(_) => {}
+            Err(e) => match send_failure(&e) {
+                SendFailure::Rejected { nonce_taken } => {
+                    self.forget_attempts(tx_hash).await;
+                    self.release_nonce(nonce, nonce_taken).await;
+                    return Err(ProcessError::Transient(e.into()));
+                }
+                SendFailure::Unknown => tracing::warn!(
+                    %tx_hash,
+                    nonce,
+                    "Sending the transaction failed, keeping it in case it reached the node: {}",
+                    e
+                ),
+            },
+        }
+
+        if let Err(e) = self.nonce_manager.mark_broadcast(nonce).await {
+            tracing::error!(nonce, "Failed to mark nonce as broadcast: {}", e);
+        }
+
+        Ok((tx_hash, nonce))
+    }
+
+    async fn forget_attempts(&self, tx_hash: B256) {
+        if let Err(e) = self.tx_requests.delete_attempts(&tx_hash.to_string()).await {
+            tracing::error!(%tx_hash, "Failed to delete attempts of an unsent transaction: {}", e);
+        }
+    }
+
+    /// Gives back a nonce no transaction of ours holds. If the chain has already used it,
+    /// the counter is synced past it instead of handing it out again.
+    async fn release_nonce(&self, nonce: u64, taken: bool) {
+        if let Err(e) = self.nonce_manager.release(nonce).await {
+            tracing::error!(nonce, "Failed to release nonce: {}", e);
+        }
+        if taken {
+            if let Err(e) = self.nonce_manager.sync().await {
+                tracing::error!(nonce, "Failed to sync nonce after a conflict: {}", e);
+            }
+        }
+    }
+
+    async fn submitted(&self, requests: &[TxRequest], tx_hash: B256, nonce: u64) {
+        self.metrics.relayer_tx_sent.inc();
+        let update = StatusUpdate {
+            tx_hash: Some(tx_hash.to_string()),
+            nonce: Some(nonce as i64),
+            ..Default::default()
+        };
+        for request in requests {
+            self.metrics.transactions_pending.inc();
+            tracing::info!(id = %request.id, %tx_hash, nonce, "Relay request submitted");
+            self.transition(request, TxStatus::Submitted, &update).await;
+        }
+    }
+
+    async fn handle_error(&self, request: &TxRequest, error: ProcessError) {
+        match error {
+            ProcessError::Permanent(reason) => {
+                tracing::warn!(id = %request.id, "Relay request failed: {}", reason);
+                self.fail(request, reason).await;
+            }
+            ProcessError::Transient(e) => {
+                if request.retry_count as u32 + 1 >= self.config.max_retries {
+                    tracing::error!(id = %request.id, "Relay request failed after retries: {}", e);
+                    self.fail(request, e.to_string()).await;
+                    return;
+                }
+
+                self.metrics.queue_retries_total.inc();
+                tracing::warn!(id = %request.id, retry = request.retry_count + 1, "Retrying relay request: {}", e);
+                if let Err(e) = self.tx_requests.requeue(request.id, &e.to_string()).await {
+                    tracing::error!(id = %request.id, "Failed to requeue relay request: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Settles every request of a batch that could not go out the same way.
+    async fn handle_errors(&self, requests: &[TxRequest], error: ProcessError) {
+        for request in requests {
+            let error = match &error {
+                ProcessError::Permanent(reason) => ProcessError::Permanent(reason.clone()),
+                ProcessError::Transient(e) => ProcessError::Transient(anyhow::anyhow!("{e:#}")),
+            };
+            self.handle_error(request, error).await;
+        }
+    }
+
+    /// Signs `call` locally, so its hash is known before it is sent.
+    async fn sign(
+        &self,
+        call: &ForwarderCall,
+        nonce: u64,
+        fees: &Eip1559Estimation,
+    ) -> anyhow::Result<TxEnvelope> {
+        let tx = TransactionRequest::default()
+            .with_from(self.nonce_manager.address())
+            .with_to(self.forwarder)
+            .with_input(call.input.clone())
+            .with_value(call.value)
+            .with_nonce(nonce)
+            .with_chain_id(self.chain_id)
+            .with_gas_limit(call.gas_limit)
+            .with_max_fee_per_gas(fees.max_fee_per_gas)
+            .with_max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
+
+        Ok(tx.build(&self.wallet).await?)
+    }
+
+    async fn fail(&self, request: &TxRequest, reason: String) {
+        let update = StatusUpdate {
+            error: Some(reason),
+            ..Default::default()
+        };
+        self.transition(request, TxStatus::Failed, &update).await;
+    }
+
+    /// Moves the request out of `processing`; `false` if it could not.
+    async fn transition(&self, request: &TxRequest, to: TxStatus, update: &StatusUpdate) -> bool {
+        match self
+            .tx_requests
+            .transition_status(request.id, TxStatus::Processing, to, update)
+            .await
+        {
+            Ok(true) => {
+                let event = match to {
+                    TxStatus::Failed => RelayEventKind::Failed,
+                    _ => RelayEventKind::Submitted,
+                };
+                self.events
+                    .publish(RelayEvent::new(event, request, to, update));
+                true
+            }
+            Ok(false) => {
+                tracing::warn!(id = %request.id, "Relay request left processing concurrently");
+                false
+            }
+            Err(e) => {
+                tracing::error!(id = %request.id, "Failed to update relay request: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Takes over requests abandoned in `processing`. Those with a recorded attempt were
+    /// broadcast and go to the tracker, as requeueing them would relay them twice; the rest
+    /// go back to the queue.
+    async fn requeue_stale(&self) {
+        let older_than = STALE_PROCESSING_AFTER.as_secs_f64();
+        match self
+            .tx_requests
+            .stale_processing(self.chain_id as i64, older_than)
+            .await
+        {
+            Ok(stale) if !stale.is_empty() => {
+                if let Err(e) = self.submit_broadcast(&stale).await {
+                    tracing::error!("Failed to hand broadcast requests to the tracker: {}", e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to read stale requests: {}", e),
+        }
+
+        match self
+            .tx_requests
+            .requeue_stale_processing(self.chain_id as i64, older_than)
+            .await
+        {
+            Ok(0) => {}
+            Ok(requeued) => tracing::warn!(requeued, "Requeued requests abandoned in processing"),
+            Err(e) => tracing::error!("Failed to requeue stale requests: {}", e),
+        }
+    }
+
+    /// Marks stale requests submitted under their latest attempt, if they have one.
+    async fn submit_broadcast(&self, stale: &[TxRequest]) -> anyhow::Result<()> {
+        let ids: Vec<Uuid> = stale.iter().map(|request| request.id).collect();
+        let attempts = self.tx_requests.attempts_of(&ids).await?;
+        for request in stale {
+            let Some(latest) = attempts
+                .iter()
+                .rfind(|attempt| attempt.tx_request_id == request.id)
+            else {
+                continue;
+            };
+            let update = StatusUpdate {
+                tx_hash: Some(latest.tx_hash.clone()),
+                nonce: Some(latest.nonce),
+                ..Default::default()
+            };
+            if self.transition(request, TxStatus::Submitted, &update).await {
+                self.metrics.transactions_pending.inc();
+                tracing::warn!(id = %request.id, tx_hash = %latest.tx_hash, "Relay request abandoned after its broadcast, handed to the tracker");
+            }
+        }
+        Ok(())
+    }
+
+    async fn update_queue_depth(&self) {
+        match self
+            .tx_requests
+            .count_by_status(self.chain_id as i64, TxStatus::Pending)
+            .await
+        {
+            Ok(depth) => self.metrics.queue_depth.set(depth),
+            Err(e) => tracing::error!("Failed to read queue depth: {}", e),
+        }
+    }
+}
+
+fn execute_call(request: TrustedForwarder::ForwardRequestData) -> ForwarderCall {
+    ForwarderCall {
+        value: request.value,
+        gas_limit: execute_gas_limit(request.gas.saturating_to()),
+        input: TrustedForwarder::executeCall { request }
+            .abi_encode()
+            .into(),
+    }
+}
+
+/// `executeBatch` of `requests`; atomic when `refund_receiver` is the zero address.
+fn execute_batch_call(
+    requests: Vec<TrustedForwarder::ForwardRequestData>,
+    refund_receiver: Address,
+) -> ForwarderCall {
+    ForwarderCall {
+        value: requests.iter().map(|request| request.value).sum(),
+        gas_limit: batch_gas_limit(requests.iter().map(|request| request.gas.saturating_to())),
+        input: TrustedForwarder::executeBatchCall {
+            requests,
+            refundReceiver: refund_receiver,
+        }
+        .abi_encode()
+        .into(),
+    }
+}
+
+fn simulation_error(error: SimulationError) -> ProcessError {
+    match error {
+        SimulationError::Reverted(revert) => {
+            ProcessError::Permanent(format!("simulation reverted: {revert}"))
+        }
+        SimulationError::Rpc(e) => ProcessError::Transient(e),
+    }
+}
+
+/// Rebuilds the ABI struct `TrustedForwarder.execute` expects from a stored row.
+pub fn forward_request_data(
+    request: &TxRequest,
+) -> Result<TrustedForwarder::ForwardRequestData, String> {
+    Ok(TrustedForwarder::ForwardRequestData {
+        from: request
+            .from_address
+            .parse::<Address>()
+            .map_err(|e| format!("invalid from address: {e}"))?,
+        to: request
+            .to_address
+            .parse::<Address>()
+            .map_err(|e| format!("invalid to address: {e}"))?,
+        value: request
+            .value
+            .parse::<U256>()
+            .map_err(|e| format!("invalid value: {e}"))?,
+        gas: U256::from(request.gas as u64),
+        deadline: U48::saturating_from(request.deadline as u64),
+        data: Bytes::copy_from_slice(&request.data),
+        signature: Bytes::copy_from_slice(&request.signature),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::rpc::json_rpc::ErrorPayload;
+
+    fn node_error(message: &str) -> RpcError<TransportErrorKind> {
+        RpcError::ErrorResp(ErrorPayload {
+            code: -32000,
+            message: message.to_string().into(),
+            data: None,
+        })
+    }
+
+    #[test]
+    fn only_refusals_of_the_node_free_the_nonce() {
+        assert_eq!(
+            send_failure(&node_error("insufficient funds for gas * price + value")),
+            SendFailure::Rejected { nonce_taken: false }
+        );
+        assert_eq!(
+            send_failure(&node_error("transaction underpriced")),
+            SendFailure::Rejected { nonce_taken: false }
+        );
+        assert_eq!(
+            send_failure(&node_error("nonce too low: next nonce 8, tx nonce 7")),
+            SendFailure::Rejected { nonce_taken: true }
+        );
+        assert_eq!(
+            send_failure(&node_error("replacement transaction underpriced")),
+            SendFailure::Rejected { nonce_taken: true }
+        );
+
+        // The transaction may be in the mempool after any of these
+        assert_eq!(
+            send_failure(&node_error("already known")),
+            SendFailure::Unknown
+        );
+        assert_eq!(
+            send_failure(&node_error("request timed out")),
+            SendFailure::Unknown
+        );
+        assert_eq!(
+            send_failure(&TransportErrorKind::backend_gone()),
+            SendFailure::Unknown
+        );
+    }
+}
@@ -0,0 +1,293 @@
+use crate::auth::{AdminAuth, GeneratedKey};
+use crate::states::AppState;
+use crate::webhooks_handler::valid_callback_url;
+use alloy::hex;
+use alloy::primitives::B256;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use db::tenants::{ApiKey, NewApiKey};
+use serde::Deserialize;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Webhook secrets start with this, like API keys start with `rk_`.
+const WEBHOOK_SECRET_PREFIX: &str = "whsec_";
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTenant {
+    pub name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateApiKey {
+    pub name: Option<String>,
+    /// Rate limit tier; the default tier when left out.
+    pub tier: Option<String>,
+}
+
+/// Wei per UTC day and per calendar month as decimal strings; `null` lifts a limit.
+#[derive(Debug, Deserialize)]
+pub struct SetBudget {
+    pub daily_wei: Option<String>,
+    pub monthly_wei: Option<String>,
+}
+
+/// Default callback URL of a tenant's requests; `null` removes it.
+#[derive(Debug, Deserialize)]
+pub struct SetWebhook {
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RotateQuery {
+    /// How long the old key keeps working, so clients can switch over.
+    #[serde(default)]
+    pub grace_secs: u64,
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+fn internal_error(action: &str, e: anyhow::Error) -> Response {
+    tracing::error!("Failed to {}: {}", action, e);
+    error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        &format!("Failed to {action}"),
+    )
+}
+
+/// Budgets are stored as NUMERIC(78, 0), enough for any uint256.
+fn valid_wei(amount: &Option<String>) -> bool {
+    amount.as_deref().is_none_or(|amount| {
+        !amount.is_empty() && amount.len() <= 78 && amount.bytes().all(|b| b.is_ascii_digit())
+    })
+}
+
+/// The plaintext key is only ever returned here; `api_keys` keeps its hash.
+fn created_key(key: String, api_key: ApiKey) -> Response {
+    (
+        StatusCode::CREATED,
+        Json(serde_json::json!({ "key": key, "api_key": api_key })),
+    )
+        .into_response()
+}
+
+pub async fn create_tenant_handler(
+    _: AdminAuth,
+    State(app_state): State<AppState>,
+    Json(request): Json<CreateTenant>,
+) -> Response {
+    let name = request.name.trim();
+    if name.is_empty() {
+        return error(StatusCode::BAD_REQUEST, "Tenant name must not be empty");
+    }
+
+    match app_state.db.tenants().create_tenant(name).await {
+        Ok(Some(tenant)) => {
+            tracing::info!(tenant_id = %tenant.id, name, "Tenant created");
+            (StatusCode::CREATED, Json(tenant)).into_response()
+        }
+        Ok(None) => error(
+            StatusCode::CONFLICT,
+            "A tenant with this name already exists",
+        ),
+        Err(e) => internal_error("create tenant", e),
+    }
+}
+
+pub async fn list_tenants_handler(_: AdminAuth, State(app_state): State<AppState>) -> Response {
+    match app_state.db.tenants().list_tenants().await {
+        Ok(tenants) => Json(tenants).into_response(),
+        Err(e) => internal_error("list tenants", e),
+    }
+}
+
+/// Sets a tenant's gas budgets. Requests that could take it over either are refused.
+pub async fn set_budget_handler(
+    _: AdminAuth,
+    State(app_state): State<AppState>,
+    Path(tenant_id): Path<Uuid>,
+    Json(request): Json<SetBudget>,
+) -> Response {
+    if !valid_wei(&request.daily_wei) || !valid_wei(&request.monthly_wei) {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "Budgets must be wei amounts as decimal strings, or null",
+        );
+    }
+
+    match app_state
+        .db
+        .tenants()
+        .set_budget(
+            tenant_id,
+            request.daily_wei.as_deref(),
+            request.monthly_wei.as_deref(),
+        )
+        .await
+    {
+        Ok(Some(tenant)) => {
+            tracing::info!(%tenant_id, daily_wei = ?tenant.daily_budget_wei, monthly_wei = ?tenant.monthly_budget_wei, "Tenant budget set");
+            Json(tenant).into_response()
+        }
+        Ok(None) => error(StatusCode::NOT_FOUND, "Tenant not found"),
+        Err(e) => internal_error("set tenant budget", e),
+    }
+}
+
+/// Sets a tenant's default callback URL and issues a new signing secret for its callbacks,
+/// returned only here. Calling it again rotates the secret.
+pub async fn set_webhook_handler(
+    _: AdminAuth,
+    State(app_state): State<AppState>,
+    Path(tenant_id): Path<Uuid>,
+    Json(request): Json<SetWebhook>,
+) -> Response {
+    if !request.url.as_deref().is_none_or(valid_callback_url) {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "url must be an http(s) URL or null",
+        );
+    }
+
+    let secret = format!("{WEBHOOK_SECRET_PREFIX}{}", hex::encode(B256::random()));
+    match app_state
+        .db
+        .tenants()
+        .set_webhook(tenant_id, request.url.as_deref(), &secret)
+        .await
+    {
+        Ok(Some(tenant)) => {
+            tracing::info!(%tenant_id, url = ?tenant.webhook_url, "Tenant webhook set");
+            Json(serde_json::json!({ "tenant": tenant, "secret": secret })).into_response()
+        }
+        Ok(None) => error(StatusCode::NOT_FOUND, "Tenant not found"),
+        Err(e) => internal_error("set tenant webhook", e),
+    }
+}
+
+/// Every key of a tenant, without the keys themselves.
+pub async fn list_api_keys_handler(
+    _: AdminAuth,
+    State(app_state): State<AppState>,
+    Path(tenant_id): Path<Uuid>,
+) -> Response {
+    let tenants = app_state.db.tenants();
+    match tenants.find_tenant(tenant_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return error(StatusCode::NOT_FOUND, "Tenant not found"),
+        Err(e) => return internal_error("list API keys", e),
+    }
+
+    match tenants.list_keys(tenant_id).await {
+        Ok(keys) => Json(keys).into_response(),
+        Err(e) => internal_error("list API keys", e),
+    }
+}
+
+pub async fn create_api_key_handler(
+    _: AdminAuth,
+    State(app_state): State<AppState>,
+    Path(tenant_id): Path<Uuid>,
+    Json(request): Json<CreateApiKey>,
+) -> Response {
+    let tiers = &app_state.config.rate_limit.tiers;
+    if let Some(tier) = &request.tier {
+        if !tiers.iter().any(|t| &t.name == tier) {
+            return error(
+                StatusCode::BAD_REQUEST,
+                &format!("Rate limit tier {tier} is not configured"),
+            );
+        }
+    }
+
+    let tenants = app_state.db.tenants();
+    match tenants.find_tenant(tenant_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return error(StatusCode::NOT_FOUND, "Tenant not found"),
+        Err(e) => return internal_error("create API key", e),
+    }
+
+    let generated = GeneratedKey::new();
+    let new_key = NewApiKey {
+        tenant_id,
+        name: request.name,
+        tier: request.tier,
+        prefix: generated.prefix,
+        key_hash: generated.hash,
+    };
+    match tenants.create_key(&new_key).await {
+        Ok(api_key) => {
+            tracing::info!(%tenant_id, key_id = %api_key.id, "API key created");
+            created_key(generated.key, api_key)
+        }
+        Err(e) => internal_error("create API key", e),
+    }
+}
+
+/// Issues a replacement key. The old one stops working after `grace_secs`.
+pub async fn rotate_api_key_handler(
+    _: AdminAuth,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<RotateQuery>,
+) -> Response {
+    let generated = GeneratedKey::new();
+    let grace = Duration::from_secs(query.grace_secs);
+    match app_state
+        .db
+        .tenants()
+        .rotate_key(id, &generated.prefix, &generated.hash, grace)
+        .await
+    {
+        Ok(Some(api_key)) => {
+            tracing::info!(old_key_id = %id, key_id = %api_key.id, grace_secs = query.grace_secs, "API key rotated");
+            created_key(generated.key, api_key)
+        }
+        Ok(None) => error(
+            StatusCode::NOT_FOUND,
+            "API key not found, expired or revoked",
+        ),
+        Err(e) => internal_error("rotate API key", e),
+    }
+}
+
+pub async fn revoke_api_key_handler(
+    _: AdminAuth,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Response {
+    match app_state.db.tenants().revoke_key(id).await {
+        Ok(Some(api_key)) => {
+            tracing::info!(key_id = %id, "API key revoked");
+            Json(api_key).into_response()
+        }
+        Ok(None) => error(
+            StatusCode::NOT_FOUND,
+            "API key not found or already revoked",
+        ),
+        Err(e) => internal_error("revoke API key", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budgets_are_decimal_wei() {
+        assert!(valid_wei(&None));
+        assert!(valid_wei(&Some("0".to_string())));
+        assert!(valid_wei(&Some("1000000000000000000".to_string())));
+        assert!(valid_wei(&Some("9".repeat(78))));
+        assert!(!valid_wei(&Some("9".repeat(79))));
+        assert!(!valid_wei(&Some(String::new())));
+        assert!(!valid_wei(&Some("0x10".to_string())));
+        assert!(!valid_wei(&Some("-1".to_string())));
+    }
+}
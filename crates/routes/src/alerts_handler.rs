@@ -0,0 +1,78 @@
+use crate::auth::AdminAuth;
+use crate::states::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Alerts returned when no `limit` is given, and the most returned at once.
+const DEFAULT_ALERTS_LIMIT: i64 = 100;
+const MAX_ALERTS_LIMIT: i64 = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct AlertsQuery {
+    /// `false` for open alerts only, `true` for resolved ones only.
+    pub resolved: Option<bool>,
+    pub limit: Option<i64>,
+}
+
+/// Fired alerts, newest first.
+pub async fn alerts_handler(
+    _: AdminAuth,
+    State(app_state): State<AppState>,
+    Query(query): Query<AlertsQuery>,
+) -> Response {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_ALERTS_LIMIT)
+        .clamp(1, MAX_ALERTS_LIMIT);
+    match app_state.db.alerts().list(query.resolved, limit).await {
+        Ok(alerts) => Json(alerts).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to list alerts: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to list alerts" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Closes an open alert and notifies the alert sinks.
+pub async fn resolve_alert_handler(
+    _: AdminAuth,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Response {
+    let result = match app_state.alerts.resolve(id).await {
+        Ok(Some(alert)) => return Json(alert).into_response(),
+        Ok(None) => app_state.db.alerts().find_by_id(id).await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(Some(_)) => (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({ "error": "Alert is already resolved" })),
+        )
+            .into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Alert not found" })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(%id, "Failed to resolve alert: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to resolve alert" })),
+            )
+                .into_response()
+        }
+    }
+}
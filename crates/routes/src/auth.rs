@@ -0,0 +1,213 @@
+use crate::states::AppState;
+use alloy::hex;
+use alloy::primitives::B256;
+use axum::{
+    extract::{FromRequestParts, Query, Request},
+    http::{request::Parts, HeaderMap, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use db::tenants::DEFAULT_TENANT_ID;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Handed-out keys start with this, so leaked ones are easy to search for.
+const API_KEY_PREFIX: &str = "rk_";
+
+/// Characters of a key stored in the clear to tell keys apart.
+const VISIBLE_KEY_CHARS: usize = 10;
+
+/// The tenant a request is made for. With authentication disabled every request belongs
+/// to the default tenant and carries no key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiKeyAuth {
+    pub tenant_id: Uuid,
+    pub key_id: Option<Uuid>,
+    /// Rate limit tier of the key.
+    pub tier: Option<String>,
+}
+
+/// Proof that a request presented `auth.admin_key`.
+#[derive(Debug, Clone, Copy)]
+pub struct AdminAuth;
+
+/// A freshly generated key: the plaintext for the caller, the rest for `api_keys`.
+pub struct GeneratedKey {
+    pub key: String,
+    pub prefix: String,
+    pub hash: String,
+}
+
+impl GeneratedKey {
+    pub fn new() -> Self {
+        let key = format!("{API_KEY_PREFIX}{}", hex::encode(B256::random()));
+        Self {
+            prefix: key[..VISIBLE_KEY_CHARS].to_string(),
+            hash: hash_api_key(&key),
+            key,
+        }
+    }
+}
+
+impl Default for GeneratedKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Hex SHA-256 of a key, the form it is stored and looked up in.
+pub fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// The API key of a request, from `X-API-Key` or `Authorization: Bearer`.
+pub fn api_key(headers: &HeaderMap) -> Option<&str> {
+    if let Some(key) = headers.get("x-api-key").and_then(|v| v.to_str().ok()) {
+        return Some(key.trim());
+    }
+    headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+#[derive(Deserialize)]
+struct ApiKeyQuery {
+    api_key: Option<String>,
+}
+
+/// The API key of a request from `?api_key=`, for browser `EventSource` and `WebSocket`
+/// clients, which cannot set headers. Headers take precedence.
+pub fn query_api_key(uri: &Uri) -> Option<String> {
+    Query::<ApiKeyQuery>::try_from_uri(uri).ok()?.0.api_key
+}
+
+fn unauthorized(message: &str) -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [("www-authenticate", "Bearer")],
+        Json(serde_json::json!({ "error": message })),
+    )
+        .into_response()
+}
+
+impl FromRequestParts<AppState> for ApiKeyAuth {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        app_state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        // Already authenticated by `require_api_key`
+        if let Some(auth) = parts.extensions.get::<ApiKeyAuth>() {
+            return Ok(auth.clone());
+        }
+        if !app_state.config.auth.enabled {
+            return Ok(Self {
+                tenant_id: DEFAULT_TENANT_ID,
+                key_id: None,
+                tier: None,
+            });
+        }
+
+        let key = api_key(&parts.headers)
+            .map(str::to_string)
+            .or_else(|| query_api_key(&parts.uri));
+        let Some(key) = key.filter(|key| !key.is_empty()) else {
+            return Err(unauthorized(
+                "An API key is required, send it as X-API-Key or Authorization: Bearer",
+            ));
+        };
+        match app_state
+            .db
+            .tenants()
+            .authenticate(&hash_api_key(&key))
+            .await
+        {
+            Ok(Some(key)) => Ok(Self {
+                tenant_id: key.tenant_id,
+                key_id: Some(key.id),
+                tier: key.tier,
+            }),
+            Ok(None) => Err(unauthorized("Invalid, expired or revoked API key")),
+            Err(e) => {
+                tracing::error!("Failed to authenticate API key: {}", e);
+                Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": "Failed to authenticate API key" })),
+                )
+                    .into_response())
+            }
+        }
+    }
+}
+
+impl FromRequestParts<AppState> for AdminAuth {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        app_state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(admin_key) = &app_state.config.auth.admin_key else {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "The admin API is disabled, set auth.admin_key to enable it" })),
+            )
+                .into_response());
+        };
+
+        // Comparing hashes keeps the comparison time independent of the admin key
+        let presented = api_key(&parts.headers).map(hash_api_key);
+        if presented.as_deref() != Some(hash_api_key(admin_key.expose()).as_str()) {
+            return Err(unauthorized("Invalid admin key"));
+        }
+        Ok(AdminAuth)
+    }
+}
+
+/// Rejects requests without a valid API key and hands the tenant on to the handlers.
+pub async fn require_api_key(auth: ApiKeyAuth, mut request: Request, next: Next) -> Response {
+    request.extensions_mut().insert(auth);
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn generated_keys_are_stored_hashed() {
+        let generated = GeneratedKey::new();
+        assert!(generated.key.starts_with("rk_"));
+        assert_eq!(generated.key.len(), 67);
+        assert!(generated.key.starts_with(&generated.prefix));
+        assert_eq!(generated.hash, hash_api_key(&generated.key));
+        assert_ne!(GeneratedKey::new().key, generated.key);
+        assert_eq!(
+            hash_api_key("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn reads_the_key_from_either_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(api_key(&headers), None);
+        headers.insert("authorization", HeaderValue::from_static("Bearer secret"));
+        assert_eq!(api_key(&headers), Some("secret"));
+        headers.insert("x-api-key", HeaderValue::from_static("other"));
+        assert_eq!(api_key(&headers), Some("other"));
+    }
+
+    #[test]
+    fn reads_the_key_from_the_query() {
+        let uri: Uri = "/relay/1/events?api_key=rk_abc&x=1".parse().unwrap();
+        assert_eq!(query_api_key(&uri).as_deref(), Some("rk_abc"));
+        assert_eq!(query_api_key(&"/ws".parse().unwrap()), None);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::health_checks::register_chain_health_checks;
+use alloy::primitives::Address;
+use alloy::providers::Provider;
+use config::config::{BatchingConfig, ChainConfig, HealthConfig, QueueConfig, SimulationConfig};
+use db::db::DbState;
+use forwarder::ForwarderDomain;
+use metrics::{HealthChecker, MetricsCollector};
+use policy::PolicyEngine;
+use queue::{
+    connect_provider, GasOracle, NonceManager, ProviderPool, QueueWorker, ReceiptTracker,
+    RelayEventBus, Simulator,
+};
+use signer::load_signer;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// What the API needs to accept and price requests for one configured chain.
+#[derive(Clone)]
+pub struct ChainContext {
+    pub name: String,
+    pub chain_id: u64,
+    pub forwarder: ForwarderDomain,
+    pub relayer: Address,
+    pub gas_oracle: GasOracle,
+    pub simulator: Simulator,
+}
+
+/// The configured chains, keyed by chain id.
+#[derive(Clone, Default)]
+pub struct Chains {
+    chains: Arc<BTreeMap<u64, ChainContext>>,
+}
+
+/// A running chain: its API context and the background tasks relaying on it.
+pub struct ChainRuntime {
+    pub context: ChainContext,
+    pub tasks: Vec<JoinHandle<()>>,
+}
+
+impl Chains {
+    pub fn new(chains: impl IntoIterator<Item = ChainContext>) -> Self {
+        let chains = chains
+            .into_iter()
+            .map(|chain| (chain.chain_id, chain))
+            .collect();
+        Self {
+            chains: Arc::new(chains),
+        }
+    }
+
+    pub fn get(&self, chain_id: u64) -> Option<&ChainContext> {
+        self.chains.get(&chain_id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ChainContext> {
+        self.chains.values()
+    }
+
+    /// The chain a request names, or the only configured one if it names none.
+    pub fn resolve(&self, chain_id: Option<u64>) -> Result<&ChainContext, String> {
+        match chain_id {
+            Some(chain_id) => self
+                .get(chain_id)
+                .ok_or_else(|| format!("chain {chain_id} is not supported")),
+            None if self.chains.len() == 1 => Ok(self.chains.values().next().expect("one chain")),
+            None => Err("chainId is required when more than one chain is configured".to_string()),
+        }
+    }
+}
+
+impl ChainRuntime {
+    /// Connects to the chain, syncs the relayer nonce, registers the chain's health checks
+    /// and spawns its queue worker and receipt tracker.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn start(
+        config: &ChainConfig,
+        queue: &QueueConfig,
+        batching: &BatchingConfig,
+        health: &HealthConfig,
+        simulation: &SimulationConfig,
+        policy: &PolicyEngine,
+        db: &DbState,
+        metrics: &MetricsCollector,
+        events: &RelayEventBus,
+        checker: &mut HealthChecker,
+        shutdown: watch::Receiver<bool>,
+    ) -> anyhow::Result<Self> {
+        let chain_metrics = metrics.chain(config.chain_id);
+        let forwarder_address = config.forwarder_address.parse()?;
+        let forwarder =
+            ForwarderDomain::new(&config.forwarder_name, config.chain_id, forwarder_address);
+
+        let signer = load_signer(&config.signer)?;
+        tracing::info!(
+            chain = %config.name,
+            chain_id = config.chain_id,
+            "Relayer account {} loaded from {}",
+            signer.address(),
+            signer.kind()
+        );
+
+        let rpc_pool = ProviderPool::new(&config.rpc, chain_metrics.clone())?;
+        let provider = connect_provider(rpc_pool.clone(), signer.as_ref());
+        let reported = provider.get_chain_id().await?;
+        anyhow::ensure!(
+            reported == config.chain_id,
+            "RPC endpoints of chain {} report chain id {}, expected {}",
+            config.name,
+            reported,
+            config.chain_id
+        );
+        tracing::info!(
+            chain = %config.name,
+            "Using {} RPC endpoint(s)",
+            config.rpc.urls.len()
+        );
+
+        let gas_oracle = GasOracle::new(
+            config.gas_oracle.clone(),
+            provider.clone(),
+            chain_metrics.clone(),
+        );
+        let nonce_manager = NonceManager::new(
+            config.chain_id,
+            signer.address(),
+            db.nonces(),
+            provider.clone(),
+            gas_oracle.clone(),
+            chain_metrics.clone(),
+        );
+        nonce_manager.sync().await?;
+        nonce_manager.fill_gaps().await?;
+
+        register_chain_health_checks(
+            checker,
+            health,
+            config,
+            db,
+            &rpc_pool,
+            &provider,
+            signer.address(),
+            &chain_metrics,
+        );
+
+        let simulator = Simulator::new(
+            simulation.clone(),
+            config.chain_id,
+            forwarder_address,
+            signer.address(),
+            provider.clone(),
+            policy.clone(),
+            chain_metrics.clone(),
+        );
+        let refund_receiver = match &batching.refund_receiver {
+            Some(address) => address.parse()?,
+            None => signer.address(),
+        };
+        let worker = QueueWorker::new(
+            queue.clone(),
+            batching.clone(),
+            refund_receiver,
+            config.chain_id,
+            forwarder_address,
+            db.tx_requests(),
+            nonce_manager,
+            provider.clone(),
+            signer.wallet(),
+            gas_oracle.clone(),
+            simulator.clone(),
+            events.clone(),
+            chain_metrics.clone(),
+        )
+        .spawn(shutdown.clone());
+        let tracker = ReceiptTracker::new(
+            config.tracker.clone(),
+            config.chain_id,
+            signer.address(),
+            db.tx_requests(),
+            provider,
+            signer.wallet(),
+            gas_oracle.clone(),
+            events.clone(),
+            chain_metrics,
+        )
+        .spawn(shutdown);
+
+        Ok(Self {
+            context: ChainContext {
+                name: config.name.clone(),
+                chain_id: config.chain_id,
+                forwarder,
+                relayer: signer.address(),
+                gas_oracle,
+                simulator,
+            },
+            tasks: vec![worker, tracker],
+        })
+    }
+}
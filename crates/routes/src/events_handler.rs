@@ -0,0 +1,354 @@
+use crate::auth::ApiKeyAuth;
+use crate::relay_status_handler::{checksummed, with_attempts};
+use crate::states::AppState;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Json,
+};
+use db::db::TxStatus;
+use futures::stream;
+use metrics::MetricsCollector;
+use queue::{Received, RelayEvent, RelayEventSubscription};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Topics one WebSocket connection may be subscribed to at once.
+const MAX_SUBSCRIPTIONS: usize = 100;
+
+/// What a WebSocket client asks for. Without an address the topic is every relay event of
+/// the tenant.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum ClientMessage {
+    Subscribe { address: Option<String> },
+    Unsubscribe { address: Option<String> },
+}
+
+/// Events a WebSocket subscription receives, always limited to the tenant's own requests.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Topic {
+    Tenant,
+    /// Requests from or to this checksummed address.
+    Address(String),
+}
+
+/// Counts an open stream in `gas_relayer_event_stream_connections` for as long as it lives.
+struct ConnectionGuard {
+    metrics: MetricsCollector,
+    transport: &'static str,
+}
+
+/// Where the event stream of one relay request stands.
+struct RequestStream {
+    app_state: AppState,
+    id: Uuid,
+    subscription: RelayEventSubscription,
+    /// Events were missed, so a fresh status goes out next.
+    resync: bool,
+    finished: bool,
+    _connection: ConnectionGuard,
+}
+
+impl ConnectionGuard {
+    fn new(metrics: &MetricsCollector, transport: &'static str) -> Self {
+        metrics
+            .event_stream_connections
+            .with_label_values(&[transport])
+            .inc();
+        Self {
+            metrics: metrics.clone(),
+            transport,
+        }
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.metrics
+            .event_stream_connections
+            .with_label_values(&[self.transport])
+            .dec();
+    }
+}
+
+impl Topic {
+    pub fn matches(&self, event: &RelayEvent) -> bool {
+        match self {
+            Topic::Tenant => true,
+            Topic::Address(address) => event.from == *address || event.to == *address,
+        }
+    }
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+fn is_final(status: TxStatus) -> bool {
+    matches!(status, TxStatus::Confirmed | TxStatus::Failed)
+}
+
+/// The request's full status as a `status` event, and whether it is settled.
+async fn status_event(app_state: &AppState, id: Uuid) -> anyhow::Result<(Event, bool)> {
+    let request = app_state
+        .db
+        .tx_requests()
+        .find_by_id(id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("relay request {id} disappeared"))?;
+    let finished = is_final(request.status);
+    let mut statuses = with_attempts(app_state, vec![request]).await?;
+    let event = Event::default()
+        .event("status")
+        .json_data(statuses.remove(0))?;
+    Ok((event, finished))
+}
+
+impl RequestStream {
+    async fn next(mut self) -> Option<(Result<Event, axum::Error>, Self)> {
+        loop {
+            if self.finished {
+                return None;
+            }
+            if self.resync {
+                self.resync = false;
+                return match status_event(&self.app_state, self.id).await {
+                    Ok((event, finished)) => {
+                        self.finished = finished;
+                        Some((Ok(event), self))
+                    }
+                    Err(e) => {
+                        tracing::error!(id = %self.id, "Failed to resync relay event stream: {}", e);
+                        None
+                    }
+                };
+            }
+
+            match self.subscription.recv().await {
+                Received::Event(event) if event.id == self.id => {
+                    self.finished = event.event.is_final();
+                    let event = Event::default()
+                        .event(event.event.as_str())
+                        .json_data(&*event);
+                    return Some((event, self));
+                }
+                Received::Event(_) => {}
+                Received::Lagged(_) => self.resync = true,
+                Received::Closed => return None,
+            }
+        }
+    }
+}
+
+/// Server-sent events of one of the tenant's relay requests: its current `status` first,
+/// then an event per change named after it (`submitted`, `replaced`, `confirmed`, `failed`).
+/// The stream ends once the request is settled.
+pub async fn relay_events_handler(
+    State(app_state): State<AppState>,
+    auth: ApiKeyAuth,
+    Path(id): Path<Uuid>,
+) -> Response {
+    // Subscribed before the status is read, so no change can slip in between
+    let subscription = app_state.events.subscribe();
+    match app_state.db.tx_requests().find_by_id(id).await {
+        Ok(Some(request)) if request.tenant_id == auth.tenant_id => {}
+        Ok(_) => return error(StatusCode::NOT_FOUND, "Relay request not found"),
+        Err(e) => {
+            tracing::error!("Failed to read relay request: {}", e);
+            return error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to read relay request",
+            );
+        }
+    }
+
+    let state = RequestStream {
+        _connection: ConnectionGuard::new(&app_state.metrics, "sse"),
+        app_state: app_state.clone(),
+        id,
+        subscription,
+        resync: true,
+        finished: false,
+    };
+    let events = stream::unfold(state, RequestStream::next);
+    Sse::new(events)
+        .keep_alive(KeepAlive::new().interval(app_state.config.events.keep_alive))
+        .into_response()
+}
+
+/// WebSocket of the tenant's relay events. Clients send
+/// `{"action": "subscribe"}` for all of them or `{"action": "subscribe", "address": "0x…"}`
+/// for those from or to an address, and `unsubscribe` likewise.
+pub async fn websocket_handler(
+    State(app_state): State<AppState>,
+    auth: ApiKeyAuth,
+    ws: WebSocketUpgrade,
+) -> Response {
+    ws.on_upgrade(move |socket| stream_events(socket, app_state, auth.tenant_id))
+}
+
+async fn stream_events(mut socket: WebSocket, app_state: AppState, tenant_id: Uuid) {
+    let _connection = ConnectionGuard::new(&app_state.metrics, "websocket");
+    let mut subscription = app_state.events.subscribe();
+    let mut topics = HashSet::new();
+    let period = app_state.config.events.keep_alive;
+    let mut keep_alive = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+
+    loop {
+        let reply = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => handle_message(&text, &mut topics),
+                Some(Ok(Message::Binary(_))) => json!({ "type": "error", "error": "Messages must be JSON text" }),
+                // Pings are answered by axum
+                Some(Ok(Message::Ping(_) | Message::Pong(_))) => continue,
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Err(e)) => {
+                    tracing::debug!("WebSocket closed with an error: {}", e);
+                    break;
+                }
+            },
+            received = subscription.recv() => match received {
+                Received::Event(event)
+                    if event.tenant_id == tenant_id
+                        && topics.iter().any(|topic: &Topic| topic.matches(&event)) =>
+                {
+                    json!({ "type": "event", "event": &*event })
+                }
+                Received::Event(_) => continue,
+                Received::Lagged(missed) if !topics.is_empty() => {
+                    json!({ "type": "lagged", "missed": missed })
+                }
+                Received::Lagged(_) => continue,
+                Received::Closed => {
+                    let _ = socket.send(Message::Close(None)).await;
+                    break;
+                }
+            },
+            _ = keep_alive.tick() => {
+                if socket.send(Message::Ping(Default::default())).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+        };
+
+        if socket
+            .send(Message::Text(reply.to_string().into()))
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+/// Applies a client message to the connection's topics and returns the reply.
+fn handle_message(text: &str, topics: &mut HashSet<Topic>) -> serde_json::Value {
+    let message = match serde_json::from_str::<ClientMessage>(text) {
+        Ok(message) => message,
+        Err(e) => return json!({ "type": "error", "error": format!("Invalid message: {e}") }),
+    };
+    let (subscribe, address) = match message {
+        ClientMessage::Subscribe { address } => (true, address),
+        ClientMessage::Unsubscribe { address } => (false, address),
+    };
+    let topic = match address {
+        None => Topic::Tenant,
+        Some(address) => match checksummed(&address) {
+            Some(address) => Topic::Address(address),
+            None => {
+                return json!({ "type": "error", "error": format!("Invalid address {address}") })
+            }
+        },
+    };
+
+    if !subscribe {
+        topics.remove(&topic);
+        return json!({ "type": "unsubscribed", "topic": topic });
+    }
+    if topics.len() >= MAX_SUBSCRIPTIONS && !topics.contains(&topic) {
+        return json!({
+            "type": "error",
+            "error": format!("At most {MAX_SUBSCRIPTIONS} subscriptions per connection"),
+        });
+    }
+    topics.insert(topic.clone());
+    json!({ "type": "subscribed", "topic": topic })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::db::NewTxRequest;
+
+    const FROM: &str = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+    const TO: &str = "0x5cA35C1148F3Fc40b1f57885Aa8628f9Bf9F524f";
+
+    #[test]
+    fn subscribes_to_tenant_and_address_topics() {
+        let mut topics = HashSet::new();
+        let reply = handle_message(r#"{"action":"subscribe"}"#, &mut topics);
+        assert_eq!(reply, json!({ "type": "subscribed", "topic": "tenant" }));
+
+        // Addresses are matched checksummed, whatever the case they were sent in
+        let reply = handle_message(
+            &format!(
+                r#"{{"action":"subscribe","address":"{}"}}"#,
+                FROM.to_lowercase()
+            ),
+            &mut topics,
+        );
+        assert_eq!(reply["topic"], json!({ "address": FROM }));
+        assert_eq!(topics.len(), 2);
+
+        let reply = handle_message(r#"{"action":"unsubscribe"}"#, &mut topics);
+        assert_eq!(reply["type"], "unsubscribed");
+        assert_eq!(topics, HashSet::from([Topic::Address(FROM.to_string())]));
+
+        for invalid in [
+            r#"{"action":"subscribe","address":"0x12"}"#,
+            r#"{"action":"listen"}"#,
+            "not json",
+        ] {
+            assert_eq!(handle_message(invalid, &mut topics)["type"], "error");
+        }
+        assert_eq!(topics.len(), 1);
+    }
+
+    #[test]
+    fn address_topics_match_either_side() {
+        let request = NewTxRequest {
+            chain_id: 11155111,
+            tenant_id: Uuid::from_u128(1),
+            from_address: FROM.to_string(),
+            to_address: TO.to_string(),
+            value: "0".to_string(),
+            gas: 100_000,
+            deadline: 1_900_000_000,
+            data: vec![],
+            signature: vec![],
+            estimated_cost_wei: None,
+            callback_url: None,
+        };
+        let event = RelayEvent::queued(Uuid::from_u128(2), &request);
+
+        assert!(Topic::Tenant.matches(&event));
+        assert!(Topic::Address(FROM.to_string()).matches(&event));
+        assert!(Topic::Address(TO.to_string()).matches(&event));
+        assert!(
+            !Topic::Address("0x70997970C51812dc3A010C7d01b50e0d17dc79C8".to_string())
+                .matches(&event)
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use crate::states::AppState;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct GasQuery {
+    #[serde(rename = "chainId")]
+    pub chain_id: Option<u64>,
+}
+
+/// Current slow/normal/fast EIP-1559 fee suggestions of a chain, in wei.
+pub async fn gas_handler(
+    State(app_state): State<AppState>,
+    Query(query): Query<GasQuery>,
+) -> Response {
+    let chain = match app_state.chains.resolve(query.chain_id) {
+        Ok(chain) => chain,
+        Err(reason) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": reason })),
+            )
+                .into_response();
+        }
+    };
+
+    match chain.gas_oracle.estimates().await {
+        Ok(estimates) => Json(estimates).into_response(),
+        Err(e) => {
+            tracing::error!(
+                chain_id = chain.chain_id,
+                "Failed to estimate gas fees: {}",
+                e
+            );
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(serde_json::json!({ "error": "Gas estimates are unavailable" })),
+            )
+                .into_response()
+        }
+    }
+}
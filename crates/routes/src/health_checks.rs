@@ -0,0 +1,241 @@
+use alloy::primitives::Address;
+use alloy::providers::{DynProvider, Provider};
+use config::config::{ChainConfig, HealthConfig};
+use db::db::{DbState, TxRequestRepository, TxStatus};
+use futures::future::BoxFuture;
+use metrics::{ChainMetrics, ComponentHealth, HealthCheck, HealthChecker, MetricsCollector};
+use queue::ProviderPool;
+use std::time::Instant;
+
+/// Database reachability, ping latency and connection pool usage.
+pub struct DatabaseHealthCheck {
+    db: DbState,
+    metrics: MetricsCollector,
+    max_latency_ms: u64,
+}
+
+/// Reachability and block height lag of one RPC endpoint of a chain's pool.
+pub struct RpcEndpointHealthCheck {
+    chain: String,
+    pool: ProviderPool,
+    index: usize,
+    name: String,
+    max_block_lag: u64,
+}
+
+/// Relayer account balance on one chain versus the configured minimum.
+pub struct BalanceHealthCheck {
+    chain: String,
+    provider: DynProvider,
+    address: Address,
+    metrics: ChainMetrics,
+    min_balance_eth: f64,
+}
+
+/// Number of requests waiting for one chain's queue worker.
+pub struct QueueBacklogHealthCheck {
+    chain: String,
+    tx_requests: TxRequestRepository,
+    chain_id: u64,
+    max_backlog: i64,
+}
+
+/// Registers the database check; every chain adds its own with [`register_chain_health_checks`].
+pub fn register_health_checks(
+    checker: &mut HealthChecker,
+    config: &HealthConfig,
+    db: &DbState,
+    metrics: &MetricsCollector,
+) {
+    checker.register(DatabaseHealthCheck {
+        db: db.clone(),
+        metrics: metrics.clone(),
+        max_latency_ms: config.max_db_latency.as_millis() as u64,
+    });
+}
+
+/// Registers every RPC endpoint, the relayer balance and the queue backlog of one chain,
+/// named after the chain, e.g. `relayer_balance:sepolia`.
+#[allow(clippy::too_many_arguments)]
+pub fn register_chain_health_checks(
+    checker: &mut HealthChecker,
+    config: &HealthConfig,
+    chain: &ChainConfig,
+    db: &DbState,
+    rpc_pool: &ProviderPool,
+    provider: &DynProvider,
+    relayer: Address,
+    metrics: &ChainMetrics,
+) {
+    for (index, (name, _)) in rpc_pool.health().into_iter().enumerate() {
+        checker.register(RpcEndpointHealthCheck {
+            chain: chain.name.clone(),
+            pool: rpc_pool.clone(),
+            index,
+            name,
+            max_block_lag: config.max_block_lag,
+        });
+    }
+    checker.register(BalanceHealthCheck {
+        chain: chain.name.clone(),
+        provider: provider.clone(),
+        address: relayer,
+        metrics: metrics.clone(),
+        min_balance_eth: config.min_relayer_balance_eth,
+    });
+    checker.register(QueueBacklogHealthCheck {
+        chain: chain.name.clone(),
+        tx_requests: db.tx_requests(),
+        chain_id: chain.chain_id,
+        max_backlog: config.max_queue_backlog,
+    });
+}
+
+impl HealthCheck for DatabaseHealthCheck {
+    fn name(&self) -> String {
+        "database".to_string()
+    }
+
+    fn check(&self) -> BoxFuture<'_, ComponentHealth> {
+        Box::pin(async move {
+            let started = Instant::now();
+            let result = DbState::ping_db(&self.db.pool).await;
+            let latency = started.elapsed();
+            self.metrics
+                .record_db_query(latency.as_secs_f64(), result.is_ok());
+
+            let size = self.db.pool.size();
+            let idle = self.db.pool.num_idle() as u32;
+            let max = self.db.pool.options().get_max_connections();
+            self.metrics
+                .db_connections_active
+                .set(size.saturating_sub(idle) as i64);
+
+            let latency_ms = latency.as_millis() as u64;
+            let health = match result {
+                Err(e) => ComponentHealth::unhealthy(&format!("Database ping failed: {e}")),
+                Ok(()) if latency_ms > self.max_latency_ms => {
+                    ComponentHealth::degraded("Database ping is slow")
+                }
+                Ok(()) if idle == 0 && size >= max => {
+                    ComponentHealth::degraded("Database connection pool is exhausted")
+                }
+                Ok(()) => ComponentHealth::healthy("Database connection active"),
+            };
+
+            health
+                .with_response_time(latency_ms)
+                .with_detail("pool_size", serde_json::json!(size))
+                .with_detail("idle_connections", serde_json::json!(idle))
+                .with_detail("max_connections", serde_json::json!(max))
+        })
+    }
+}
+
+impl HealthCheck for RpcEndpointHealthCheck {
+    fn name(&self) -> String {
+        format!("rpc:{}:{}", self.chain, self.name)
+    }
+
+    fn check(&self) -> BoxFuture<'_, ComponentHealth> {
+        Box::pin(async move {
+            let started = Instant::now();
+            let result = self.pool.probe_block_number(self.index).await;
+            let latency_ms = started.elapsed().as_millis() as u64;
+
+            let health = match result {
+                Ok(block_number) => {
+                    let lag = self.pool.highest_block().saturating_sub(block_number);
+                    let health = if lag > self.max_block_lag {
+                        ComponentHealth::degraded(&format!("Endpoint is {lag} blocks behind"))
+                    } else {
+                        ComponentHealth::healthy("Endpoint in sync")
+                    };
+                    health
+                        .with_detail("block_number", serde_json::json!(block_number))
+                        .with_detail("block_lag", serde_json::json!(lag))
+                }
+                Err(e) => {
+                    // Losing one endpoint only degrades the pool while others still answer.
+                    let message = format!("Endpoint unreachable: {e}");
+                    let now = Instant::now();
+                    let others_available = self
+                        .pool
+                        .health()
+                        .iter()
+                        .enumerate()
+                        .any(|(i, (_, health))| i != self.index && health.is_available(now));
+                    if others_available {
+                        ComponentHealth::degraded(&message)
+                    } else {
+                        ComponentHealth::unhealthy(&message)
+                    }
+                }
+            };
+
+            health.with_response_time(latency_ms)
+        })
+    }
+}
+
+impl HealthCheck for BalanceHealthCheck {
+    fn name(&self) -> String {
+        format!("relayer_balance:{}", self.chain)
+    }
+
+    fn check(&self) -> BoxFuture<'_, ComponentHealth> {
+        Box::pin(async move {
+            let balance = match self.provider.get_balance(self.address).await {
+                Ok(balance) => balance,
+                Err(e) => {
+                    return ComponentHealth::unhealthy(&format!("Failed to read balance: {e}"))
+                }
+            };
+
+            let balance_eth = u128::try_from(balance).unwrap_or(u128::MAX) as f64 / 1e18;
+            self.metrics.relayer_balance.set(balance_eth);
+
+            let health = if balance.is_zero() {
+                ComponentHealth::unhealthy("Relayer account has no funds")
+            } else if balance_eth < self.min_balance_eth {
+                ComponentHealth::degraded("Relayer balance is below the minimum")
+            } else {
+                ComponentHealth::healthy("Relayer balance sufficient")
+            };
+
+            health
+                .with_detail("address", serde_json::json!(self.address))
+                .with_detail("balance_eth", serde_json::json!(balance_eth))
+                .with_detail("min_balance_eth", serde_json::json!(self.min_balance_eth))
+        })
+    }
+}
+
+impl HealthCheck for QueueBacklogHealthCheck {
+    fn name(&self) -> String {
+        format!("queue:{}", self.chain)
+    }
+
+    fn check(&self) -> BoxFuture<'_, ComponentHealth> {
+        Box::pin(async move {
+            let pending = match self
+                .tx_requests
+                .count_by_status(self.chain_id as i64, TxStatus::Pending)
+                .await
+            {
+                Ok(pending) => pending,
+                Err(e) => return ComponentHealth::unhealthy(&format!("Failed to read queue: {e}")),
+            };
+
+            let health = if pending > self.max_backlog {
+                ComponentHealth::degraded("Queue backlog above threshold")
+            } else {
+                ComponentHealth::healthy("Queue is draining")
+            };
+
+            health
+                .with_detail("pending", serde_json::json!(pending))
+                .with_detail("max_backlog", serde_json::json!(self.max_backlog))
+        })
+    }
+}
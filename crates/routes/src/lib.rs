@@ -1,4 +1,19 @@
+pub mod admin_handler;
+pub mod alerts_handler;
+pub mod auth;
+pub mod chains;
 pub mod db_health_handler;
+pub mod events_handler;
+pub mod gas_handler;
+pub mod health_checks;
 pub mod metrics_handler;
+pub mod monitoring;
+pub mod policy_handler;
+pub mod rate_limit;
+pub mod relay_batch_handler;
+pub mod relay_handler;
+pub mod relay_status_handler;
 pub mod routes;
-pub mod states;
\ No newline at end of file
+pub mod states;
+pub mod usage_handler;
+pub mod webhooks_handler;
@@ -1,21 +1,23 @@
 use crate::states::AppState;
 use axum::{
-    extract::State,
-    http::{StatusCode, header},
+    extract::{Query, State},
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use metrics::HealthChecker;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 
 pub async fn metrics_handler(State(app_state): State<AppState>) -> impl IntoResponse {
     match app_state.metrics.export_metrics() {
-        Ok(metrics_output) => {
-            Response::builder()
-                .status(StatusCode::OK)
-                .header(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")
-                .body(metrics_output)
-                .unwrap()
-        }
+        Ok(metrics_output) => Response::builder()
+            .status(StatusCode::OK)
+            .header(
+                header::CONTENT_TYPE,
+                "text/plain; version=0.0.4; charset=utf-8",
+            )
+            .body(metrics_output)
+            .unwrap(),
         Err(e) => {
             tracing::error!("Failed to export metrics: {}", e);
             Response::builder()
@@ -26,27 +28,25 @@ pub async fn metrics_handler(State(app_state): State<AppState>) -> impl IntoResp
     }
 }
 
-pub async fn health_handler(State(_app_state): State<AppState>) -> impl IntoResponse {
-    let health_check = HealthChecker::new();
-    let system_health = health_check.check_system_health().await;
-    
+pub async fn health_handler(State(app_state): State<AppState>) -> impl IntoResponse {
+    let system_health = app_state.health.check_system_health().await;
+
     let status_code = match system_health.overall_status {
         metrics::HealthStatus::Healthy => StatusCode::OK,
         metrics::HealthStatus::Degraded => StatusCode::OK,
         metrics::HealthStatus::Unhealthy => StatusCode::SERVICE_UNAVAILABLE,
     };
-    
+
     (status_code, Json(system_health)).into_response()
 }
 
 pub async fn readiness_handler(State(app_state): State<AppState>) -> impl IntoResponse {
     match db::db::DbState::ping_db(&app_state.db.pool).await {
-        Ok(_) => {
-            Json(serde_json::json!({
-                "status": "ready",
-                "timestamp": chrono::Utc::now()
-            })).into_response()
-        }
+        Ok(_) => Json(serde_json::json!({
+            "status": "ready",
+            "timestamp": chrono::Utc::now()
+        }))
+        .into_response(),
         Err(e) => {
             tracing::error!("Readiness check failed: {}", e);
             (
@@ -55,8 +55,9 @@ pub async fn readiness_handler(State(app_state): State<AppState>) -> impl IntoRe
                     "status": "not_ready",
                     "error": "Database connection failed",
                     "timestamp": chrono::Utc::now()
-                }))
-            ).into_response()
+                })),
+            )
+                .into_response()
         }
     }
 }
@@ -65,5 +66,72 @@ pub async fn liveness_handler() -> impl IntoResponse {
     Json(serde_json::json!({
         "status": "alive",
         "timestamp": chrono::Utc::now()
-    })).into_response()
-}
\ No newline at end of file
+    }))
+    .into_response()
+}
+/// Rows returned by the history endpoints when no `limit` is given, and the most they return.
+const DEFAULT_HISTORY_LIMIT: i64 = 100;
+const MAX_HISTORY_LIMIT: i64 = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct HealthHistoryQuery {
+    pub component: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MetricsHistoryQuery {
+    pub name: String,
+    pub since: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
+
+/// Persisted health results, newest first.
+pub async fn health_history_handler(
+    State(app_state): State<AppState>,
+    Query(query): Query<HealthHistoryQuery>,
+) -> Response {
+    let limit = history_limit(query.limit);
+    match app_state
+        .db
+        .monitoring()
+        .health_history(query.component.as_deref(), query.since, limit)
+        .await
+    {
+        Ok(records) => Json(records).into_response(),
+        Err(e) => history_error("health", e),
+    }
+}
+
+/// Persisted snapshots of one metric, newest first.
+pub async fn metrics_history_handler(
+    State(app_state): State<AppState>,
+    Query(query): Query<MetricsHistoryQuery>,
+) -> Response {
+    let limit = history_limit(query.limit);
+    match app_state
+        .db
+        .monitoring()
+        .metric_history(&query.name, query.since, limit)
+        .await
+    {
+        Ok(snapshots) => Json(snapshots).into_response(),
+        Err(e) => history_error("metrics", e),
+    }
+}
+
+fn history_limit(limit: Option<i64>) -> i64 {
+    limit
+        .unwrap_or(DEFAULT_HISTORY_LIMIT)
+        .clamp(1, MAX_HISTORY_LIMIT)
+}
+
+fn history_error(kind: &str, e: anyhow::Error) -> Response {
+    tracing::error!("Failed to read {} history: {}", kind, e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(serde_json::json!({ "error": format!("Failed to read {kind} history") })),
+    )
+        .into_response()
+}
@@ -0,0 +1,325 @@
+use config::config::MonitoringConfig;
+use db::monitoring::{
+    MonitoringRepository, NewHealthCheck, NewMetricSnapshot, NewPerformanceMetric, NewQueueStats,
+};
+use metrics::{HealthChecker, MetricSample, MetricValue, MetricsCollector};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+type SeriesKey = (String, BTreeMap<String, String>);
+
+/// Histogram totals of the previous snapshot, so each interval records only its own observations.
+#[derive(Default)]
+struct PreviousSnapshot {
+    taken_at: Option<Instant>,
+    histograms: HashMap<SeriesKey, (u64, f64)>,
+    /// Settled transactions per `chain_id` label.
+    transactions_total: HashMap<String, f64>,
+}
+
+/// Periodically writes health results and metric values to the monitoring tables
+/// and prunes rows older than the retention.
+pub struct MonitoringRecorder {
+    config: MonitoringConfig,
+    monitoring: MonitoringRepository,
+    health: Arc<HealthChecker>,
+    metrics: MetricsCollector,
+}
+
+impl MonitoringRecorder {
+    pub fn new(
+        config: MonitoringConfig,
+        monitoring: MonitoringRepository,
+        health: Arc<HealthChecker>,
+        metrics: MetricsCollector,
+    ) -> Self {
+        Self {
+            config,
+            monitoring,
+            health,
+            metrics,
+        }
+    }
+
+    pub fn spawn(self, shutdown: watch::Receiver<bool>) -> JoinHandle<()> {
+        tokio::spawn(self.run(shutdown))
+    }
+
+    pub async fn run(self, mut shutdown: watch::Receiver<bool>) {
+        tracing::info!(
+            interval_secs = self.config.snapshot_interval.as_secs(),
+            "Monitoring recorder started"
+        );
+
+        let mut previous = PreviousSnapshot::default();
+        while !*shutdown.borrow() {
+            if let Err(e) = self.record(&mut previous).await {
+                tracing::error!("Failed to record monitoring snapshot: {}", e);
+            }
+            self.prune().await;
+
+            tokio::select! {
+                _ = tokio::time::sleep(self.config.snapshot_interval) => {}
+                _ = shutdown.changed() => {}
+            }
+        }
+
+        tracing::info!("Monitoring recorder stopped");
+    }
+
+    /// Health runs first, since several checks refresh gauges that the snapshot then reads.
+    async fn record(&self, previous: &mut PreviousSnapshot) -> anyhow::Result<()> {
+        let system_health = self.health.check_system_health().await;
+        let checks: Vec<NewHealthCheck> = system_health
+            .components
+            .into_iter()
+            .map(|(component, health)| NewHealthCheck {
+                component,
+                status: health.status.as_str().to_string(),
+                response_time_ms: health
+                    .response_time_ms
+                    .map(|ms| ms.min(i32::MAX as u64) as i32),
+                details: Some(serde_json::json!({
+                    "message": health.message,
+                    "details": health.details,
+                })),
+            })
+            .collect();
+        self.monitoring.record_health_checks(&checks).await?;
+
+        let samples = self.metrics.snapshot();
+        let now = Instant::now();
+        self.monitoring
+            .record_metric_snapshots(&metric_snapshots(&samples))
+            .await?;
+        self.monitoring
+            .record_performance_metrics(&performance_since(&previous.histograms, &samples))
+            .await?;
+        for stats in queue_stats(previous, &samples, now) {
+            self.monitoring.record_queue_stats(&stats).await?;
+        }
+
+        previous.taken_at = Some(now);
+        previous.histograms = histogram_totals(&samples);
+        previous.transactions_total = samples
+            .iter()
+            .filter(|s| s.name == "gas_relayer_transactions_total")
+            .filter_map(|s| Some((s.labels.get("chain_id")?.clone(), counter(s))))
+            .collect();
+        Ok(())
+    }
+
+    async fn prune(&self) {
+        let retention = chrono::Duration::from_std(self.config.retention).unwrap_or_default();
+        match self.monitoring.prune(chrono::Utc::now() - retention).await {
+            Ok(0) => {}
+            Ok(removed) => tracing::debug!(removed, "Pruned monitoring rows"),
+            Err(e) => tracing::error!("Failed to prune monitoring rows: {}", e),
+        }
+    }
+}
+
+/// Counters and gauges as they are; histograms as their `_count` and `_sum` series.
+fn metric_snapshots(samples: &[MetricSample]) -> Vec<NewMetricSnapshot> {
+    let mut snapshots = Vec::new();
+    for sample in samples {
+        let labels = (!sample.labels.is_empty()).then(|| serde_json::json!(sample.labels));
+        let mut push = |metric_name: String, metric_value: f64| {
+            snapshots.push(NewMetricSnapshot {
+                metric_name,
+                metric_value,
+                labels: labels.clone(),
+            })
+        };
+        match sample.value {
+            MetricValue::Counter(value) | MetricValue::Gauge(value) => {
+                push(sample.name.clone(), value)
+            }
+            MetricValue::Histogram { count, sum } => {
+                push(format!("{}_count", sample.name), count as f64);
+                push(format!("{}_sum", sample.name), sum);
+            }
+        }
+    }
+    snapshots
+}
+
+/// Average duration of the observations each histogram series received since `previous`.
+/// Series without new observations are skipped.
+fn performance_since(
+    previous: &HashMap<SeriesKey, (u64, f64)>,
+    samples: &[MetricSample],
+) -> Vec<NewPerformanceMetric> {
+    histogram_totals(samples)
+        .into_iter()
+        .filter_map(|((name, labels), (count, sum))| {
+            let (previous_count, previous_sum) = previous
+                .get(&(name.clone(), labels.clone()))
+                .copied()
+                .unwrap_or_default();
+            let observations = count.checked_sub(previous_count).filter(|n| *n > 0)?;
+            let operation = name
+                .trim_start_matches("gas_relayer_")
+                .trim_end_matches("_seconds")
+                .to_string();
+
+            Some(NewPerformanceMetric {
+                operation,
+                duration_ms: (sum - previous_sum) / observations as f64 * 1000.0,
+                success: true,
+                error_message: None,
+                metadata: Some(serde_json::json!({
+                    "observations": observations,
+                    "labels": labels,
+                })),
+            })
+        })
+        .collect()
+}
+
+/// One row per chain, named after its chain id.
+fn queue_stats(
+    previous: &PreviousSnapshot,
+    samples: &[MetricSample],
+    now: Instant,
+) -> Vec<NewQueueStats> {
+    let totals = histogram_totals(samples);
+    samples
+        .iter()
+        .filter(|s| s.name == "gas_relayer_queue_depth")
+        .filter_map(|depth_sample| {
+            let chain_id = depth_sample.labels.get("chain_id")?;
+            let depth = match depth_sample.value {
+                MetricValue::Gauge(depth) => depth as i32,
+                _ => return None,
+            };
+
+            let processing_rate = previous.taken_at.map(|taken_at| {
+                let settled = samples
+                    .iter()
+                    .find(|s| {
+                        s.name == "gas_relayer_transactions_total"
+                            && s.labels == depth_sample.labels
+                    })
+                    .map(counter)
+                    .unwrap_or_default()
+                    - previous
+                        .transactions_total
+                        .get(chain_id)
+                        .copied()
+                        .unwrap_or_default();
+                settled.max(0.0) / now.duration_since(taken_at).as_secs_f64().max(1.0)
+            });
+
+            let wait_key = (
+                "gas_relayer_queue_processing_time_seconds".to_string(),
+                depth_sample.labels.clone(),
+            );
+            let avg_wait_time_ms = totals.get(&wait_key).and_then(|(count, sum)| {
+                let (previous_count, previous_sum) = previous
+                    .histograms
+                    .get(&wait_key)
+                    .copied()
+                    .unwrap_or_default();
+                let observations = count.checked_sub(previous_count).filter(|n| *n > 0)?;
+                Some((sum - previous_sum) / observations as f64 * 1000.0)
+            });
+
+            Some(NewQueueStats {
+                queue_name: chain_id.clone(),
+                depth,
+                processing_rate,
+                avg_wait_time_ms,
+            })
+        })
+        .collect()
+}
+
+fn histogram_totals(samples: &[MetricSample]) -> HashMap<SeriesKey, (u64, f64)> {
+    samples
+        .iter()
+        .filter_map(|sample| match sample.value {
+            MetricValue::Histogram { count, sum } => {
+                Some(((sample.name.clone(), sample.labels.clone()), (count, sum)))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn counter(sample: &MetricSample) -> f64 {
+    match sample.value {
+        MetricValue::Counter(value) => value,
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn performance_covers_only_new_observations() {
+        let metrics = MetricsCollector::new().unwrap();
+        metrics.record_db_query(0.010, true);
+        metrics.record_db_query(0.030, true);
+        metrics.chain(11155111).record_rpc_call(0.5, true);
+
+        let first = metrics.snapshot();
+        let db_query = performance_since(&HashMap::new(), &first)
+            .into_iter()
+            .find(|m| m.operation == "db_query_duration")
+            .unwrap();
+        assert!((db_query.duration_ms - 20.0).abs() < 1e-9);
+        assert_eq!(db_query.metadata.unwrap()["observations"], 2);
+
+        metrics.record_db_query(0.100, true);
+        let second = performance_since(&histogram_totals(&first), &metrics.snapshot());
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].operation, "db_query_duration");
+        assert!((second[0].duration_ms - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn snapshots_split_histograms_into_count_and_sum() {
+        let metrics = MetricsCollector::new().unwrap();
+        metrics.chain(11155111).record_rpc_call(0.5, true);
+
+        let snapshots = metric_snapshots(&metrics.snapshot());
+        let value = |name: &str| {
+            snapshots
+                .iter()
+                .find(|s| s.metric_name == name)
+                .map(|s| s.metric_value)
+        };
+        assert_eq!(value("gas_relayer_rpc_latency_seconds_count"), Some(1.0));
+        assert_eq!(value("gas_relayer_rpc_latency_seconds_sum"), Some(0.5));
+        assert_eq!(value("gas_relayer_rpc_requests_total"), Some(1.0));
+    }
+
+    #[test]
+    fn queue_stats_are_split_by_chain() {
+        let metrics = MetricsCollector::new().unwrap();
+        let sepolia = metrics.chain(11155111);
+        sepolia.queue_depth.set(4);
+        sepolia.queue_processing_time.observe(2.0);
+        metrics.chain(11155420).queue_depth.set(1);
+
+        let stats = queue_stats(
+            &PreviousSnapshot::default(),
+            &metrics.snapshot(),
+            Instant::now(),
+        );
+        let summary: Vec<_> = stats
+            .iter()
+            .map(|s| (s.queue_name.as_str(), s.depth, s.avg_wait_time_ms))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("11155111", 4, Some(2000.0)), ("11155420", 1, None)]
+        );
+    }
+}
@@ -0,0 +1,32 @@
+use crate::auth::AdminAuth;
+use crate::states::AppState;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+/// The relay policy requests are currently checked against.
+pub async fn policy_handler(_: AdminAuth, State(app_state): State<AppState>) -> Response {
+    Json(app_state.policy.current().as_ref().clone()).into_response()
+}
+
+/// Re-reads the policy source now instead of waiting for the reload interval.
+pub async fn reload_policy_handler(_: AdminAuth, State(app_state): State<AppState>) -> Response {
+    match app_state.policy.reload().await {
+        Ok(changed) => Json(serde_json::json!({
+            "changed": changed,
+            "policy": app_state.policy.current().as_ref(),
+        }))
+        .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to reload relay policy: {}", e);
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    }
+}
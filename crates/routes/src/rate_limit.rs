@@ -0,0 +1,407 @@
+use crate::auth::ApiKeyAuth;
+use crate::states::AppState;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use config::config::{RateLimit, RateLimitBackend, RateLimitConfig, RateLimitTier};
+use db::db::DbState;
+use db::rate_limits::{BucketState, RateLimitRepository};
+use metrics::MetricsCollector;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// How often buckets that have refilled completely are dropped.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Token buckets per client IP, API key and sender, kept in memory or in Postgres.
+#[derive(Clone)]
+pub struct RateLimiter {
+    config: Arc<RateLimitConfig>,
+    store: BucketStore,
+    metrics: MetricsCollector,
+}
+
+#[derive(Clone)]
+enum BucketStore {
+    Memory(Arc<Mutex<HashMap<String, MemoryBucket>>>),
+    Postgres(RateLimitRepository),
+}
+
+struct MemoryBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// Outcome of one bucket, reported in the `X-RateLimit-*` headers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Until the next token, when the request was refused.
+    pub retry_after: Duration,
+    /// Until the bucket is full again.
+    pub reset: Duration,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig, db: &DbState, metrics: MetricsCollector) -> Self {
+        let store = match config.backend {
+            RateLimitBackend::Memory => BucketStore::Memory(Arc::default()),
+            RateLimitBackend::Postgres => BucketStore::Postgres(db.rate_limits()),
+        };
+        Self {
+            config: Arc::new(config.clone()),
+            store,
+            metrics,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Limit of a key tier. Keys without a tier, or with one that is not configured,
+    /// get the `default` tier.
+    pub fn tier_limit(&self, tier: Option<&str>) -> RateLimit {
+        let find = |name: &str| self.config.tiers.iter().find(|t| t.name == name);
+        let configured = tier.and_then(|name| {
+            let found = find(name);
+            if found.is_none() {
+                tracing::warn!(
+                    tier = name,
+                    "API key has an unknown rate limit tier, using the default one"
+                );
+            }
+            found
+        });
+        configured
+            .or_else(|| find("default"))
+            .map(|tier: &RateLimitTier| tier.limit)
+            .expect("the default tier is always configured")
+    }
+
+    pub async fn check_ip(&self, ip: IpAddr) -> RateLimitDecision {
+        self.check(&format!("ip:{ip}"), self.config.ip).await
+    }
+
+    pub async fn check_key(&self, key_id: Uuid, tier: Option<&str>) -> RateLimitDecision {
+        self.check(&format!("key:{key_id}"), self.tier_limit(tier))
+            .await
+    }
+
+    pub async fn check_sender(&self, from: &str) -> RateLimitDecision {
+        self.check(
+            &format!("sender:{}", from.to_lowercase()),
+            self.config.sender,
+        )
+        .await
+    }
+
+    /// Takes a token from `bucket`. If the shared store is unreachable the request is let
+    /// through rather than failing every API call.
+    async fn check(&self, bucket: &str, limit: RateLimit) -> RateLimitDecision {
+        let burst = limit.burst as f64;
+        let per_second = limit.per_minute as f64 / 60.0;
+        let state = match &self.store {
+            BucketStore::Memory(buckets) => {
+                let mut buckets = buckets.lock().expect("rate limit lock poisoned");
+                let now = Instant::now();
+                let bucket = buckets.entry(bucket.to_string()).or_insert(MemoryBucket {
+                    tokens: burst,
+                    updated_at: now,
+                });
+                let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+                bucket.tokens = (bucket.tokens + elapsed * per_second).min(burst);
+                bucket.updated_at = now;
+                let allowed = bucket.tokens >= 1.0;
+                if allowed {
+                    bucket.tokens -= 1.0;
+                }
+                BucketState {
+                    allowed,
+                    tokens: bucket.tokens,
+                }
+            }
+            BucketStore::Postgres(buckets) => match buckets.take(bucket, burst, per_second).await {
+                Ok(state) => state,
+                Err(e) => {
+                    tracing::error!(
+                        bucket,
+                        "Rate limit check failed, allowing the request: {}",
+                        e
+                    );
+                    BucketState {
+                        allowed: true,
+                        tokens: burst,
+                    }
+                }
+            },
+        };
+
+        let decision = RateLimitDecision::new(state, limit);
+        if !decision.allowed {
+            self.metrics.rate_limit_hits.inc();
+        }
+        decision
+    }
+
+    /// Longest time any bucket takes to refill; idle buckets older than this are full.
+    fn max_refill(&self) -> Duration {
+        let limits = self.config.tiers.iter().map(|tier| tier.limit);
+        [self.config.ip, self.config.sender]
+            .into_iter()
+            .chain(limits)
+            .map(|limit| {
+                Duration::from_secs_f64(limit.burst as f64 * 60.0 / limit.per_minute as f64)
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    pub fn spawn(self, shutdown: watch::Receiver<bool>) -> JoinHandle<()> {
+        tokio::spawn(self.run(shutdown))
+    }
+
+    pub async fn run(self, mut shutdown: watch::Receiver<bool>) {
+        while !*shutdown.borrow() {
+            tokio::select! {
+                _ = tokio::time::sleep(PRUNE_INTERVAL) => {}
+                _ = shutdown.changed() => {}
+            }
+            if let Err(e) = self.prune().await {
+                tracing::warn!("Failed to prune rate limit buckets: {}", e);
+            }
+        }
+    }
+
+    async fn prune(&self) -> anyhow::Result<()> {
+        let idle = self.max_refill();
+        match &self.store {
+            BucketStore::Memory(buckets) => {
+                let mut buckets = buckets.lock().expect("rate limit lock poisoned");
+                buckets.retain(|_, bucket| bucket.updated_at.elapsed() < idle);
+            }
+            BucketStore::Postgres(buckets) => {
+                let before = Utc::now() - chrono::Duration::from_std(idle)?;
+                let pruned = buckets.prune(before).await?;
+                tracing::debug!(pruned, "Pruned idle rate limit buckets");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RateLimitDecision {
+    fn new(state: BucketState, limit: RateLimit) -> Self {
+        let per_second = limit.per_minute as f64 / 60.0;
+        let tokens = state.tokens.max(0.0);
+        let retry_after = if state.allowed {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - tokens).max(0.0) / per_second)
+        };
+        Self {
+            allowed: state.allowed,
+            limit: limit.burst,
+            remaining: tokens.floor() as u32,
+            retry_after,
+            reset: Duration::from_secs_f64((limit.burst as f64 - tokens).max(0.0) / per_second),
+        }
+    }
+
+    /// Sets `X-RateLimit-Limit`, `-Remaining` and `-Reset` (seconds until the bucket is
+    /// full), plus `Retry-After` when refused. Headers already set by a more specific
+    /// bucket (sender over API key over client IP) are kept.
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        let seconds = |duration: Duration| HeaderValue::from(duration.as_secs_f64().ceil() as u64);
+        headers
+            .entry("x-ratelimit-limit")
+            .or_insert(HeaderValue::from(self.limit));
+        headers
+            .entry("x-ratelimit-remaining")
+            .or_insert(HeaderValue::from(self.remaining));
+        headers
+            .entry("x-ratelimit-reset")
+            .or_insert(seconds(self.reset));
+        if !self.allowed {
+            headers
+                .entry("retry-after")
+                .or_insert(seconds(self.retry_after.max(Duration::from_secs(1))));
+        }
+    }
+
+    /// `429 Too Many Requests` with the rate limit headers.
+    pub fn reject(&self, scope: &str) -> Response {
+        let mut response = (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(serde_json::json!({
+                "error": format!("Rate limit exceeded for this {scope}"),
+                "retry_after_secs": self.retry_after.as_secs_f64().ceil() as u64,
+            })),
+        )
+            .into_response();
+        self.apply(response.headers_mut());
+        response
+    }
+}
+
+/// The client address: the first `X-Forwarded-For` entry if the proxy is trusted, else the peer.
+fn client_ip(request: &Request, trust_forwarded_for: bool) -> Option<IpAddr> {
+    let forwarded = trust_forwarded_for
+        .then(|| request.headers().get("x-forwarded-for")?.to_str().ok())
+        .flatten()
+        .and_then(|value| value.split(',').next()?.trim().parse().ok());
+    forwarded.or_else(|| {
+        request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+    })
+}
+
+/// Limits requests per client IP, before they are authenticated.
+pub async fn rate_limit_middleware(
+    State(app_state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let limiter = &app_state.rate_limiter;
+    let ip = client_ip(&request, limiter.config.trust_forwarded_for);
+    let Some(ip) = ip.filter(|_| limiter.enabled()) else {
+        return next.run(request).await;
+    };
+
+    let decision = limiter.check_ip(ip).await;
+    if !decision.allowed {
+        tracing::warn!(%ip, path = %request.uri().path(), "Rate limited client");
+        return decision.reject("client");
+    }
+    let mut response = next.run(request).await;
+    decision.apply(response.headers_mut());
+    response
+}
+
+/// Limits requests per API key by the key's tier. Runs after `require_api_key`.
+pub async fn key_rate_limit_middleware(
+    State(app_state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let limiter = &app_state.rate_limiter;
+    let auth = request.extensions().get::<ApiKeyAuth>();
+    let Some((key_id, tier)) = auth
+        .filter(|_| limiter.enabled())
+        .and_then(|auth| Some((auth.key_id?, auth.tier.clone())))
+    else {
+        return next.run(request).await;
+    };
+
+    let decision = limiter.check_key(key_id, tier.as_deref()).await;
+    if !decision.allowed {
+        tracing::warn!(%key_id, path = %request.uri().path(), "Rate limited API key");
+        return decision.reject("API key");
+    }
+    let mut response = next.run(request).await;
+    decision.apply(response.headers_mut());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(per_minute: u32, burst: u32) -> RateLimiter {
+        let limit = RateLimit { per_minute, burst };
+        let config = RateLimitConfig {
+            enabled: true,
+            backend: RateLimitBackend::Memory,
+            ip: limit,
+            trust_forwarded_for: false,
+            sender: limit,
+            tiers: vec![
+                RateLimitTier {
+                    name: "default".to_string(),
+                    limit,
+                },
+                RateLimitTier {
+                    name: "partner".to_string(),
+                    limit: RateLimit {
+                        per_minute,
+                        burst: burst * 10,
+                    },
+                },
+            ],
+        };
+        RateLimiter {
+            config: Arc::new(config),
+            store: BucketStore::Memory(Arc::default()),
+            metrics: MetricsCollector::new().unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn refuses_once_the_burst_is_spent() {
+        let limiter = limiter(60, 2);
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+
+        let first = limiter.check_ip(ip).await;
+        assert!(first.allowed);
+        assert_eq!((first.limit, first.remaining), (2, 1));
+        assert!(limiter.check_ip(ip).await.allowed);
+
+        let refused = limiter.check_ip(ip).await;
+        assert!(!refused.allowed);
+        assert_eq!(refused.remaining, 0);
+        assert!(refused.retry_after <= Duration::from_secs(1));
+        assert_eq!(limiter.metrics.rate_limit_hits.get(), 1);
+
+        // Every client has its own bucket
+        assert!(limiter.check_ip("10.0.0.1".parse().unwrap()).await.allowed);
+    }
+
+    #[tokio::test]
+    async fn api_keys_use_their_tier() {
+        let limiter = limiter(60, 1);
+        assert_eq!(limiter.tier_limit(Some("partner")).burst, 10);
+        assert_eq!(limiter.tier_limit(Some("unknown")).burst, 1);
+        assert_eq!(limiter.tier_limit(None).burst, 1);
+
+        let key = Uuid::from_u128(1);
+        assert!(limiter.check_key(key, None).await.allowed);
+        assert!(!limiter.check_key(key, None).await.allowed);
+        let partner = limiter.check_key(Uuid::from_u128(2), Some("partner")).await;
+        assert_eq!(partner.remaining, 9);
+    }
+
+    #[test]
+    fn sets_rate_limit_headers() {
+        let refused = RateLimitDecision::new(
+            BucketState {
+                allowed: false,
+                tokens: 0.5,
+            },
+            RateLimit {
+                per_minute: 30,
+                burst: 5,
+            },
+        );
+        let response = refused.reject("client");
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let headers = response.headers();
+        assert_eq!(headers["x-ratelimit-limit"], "5");
+        assert_eq!(headers["x-ratelimit-remaining"], "0");
+        assert_eq!(headers["x-ratelimit-reset"], "9");
+        assert_eq!(headers["retry-after"], "1");
+    }
+}
@@ -0,0 +1,284 @@
+use crate::auth::ApiKeyAuth;
+use crate::relay_handler::{
+    budget_exceeded, check_request, estimated_cost, invalid_callback_url, max_fee_per_gas,
+    new_tx_request, simulation_failed,
+};
+use crate::relay_status_handler::with_attempts;
+use crate::states::AppState;
+use alloy::primitives::{Address, U256};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use forwarder::ForwardRequestData;
+use queue::{batch_gas_limit, RelayEvent};
+use serde::Deserialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Signed forward requests relayed together in one atomic `executeBatch`, in this order.
+#[derive(Debug, Deserialize)]
+pub struct RelayBatchRequest {
+    #[serde(rename = "chainId")]
+    pub chain_id: Option<u64>,
+    /// Receives the status changes of every request of the batch.
+    #[serde(default, alias = "callbackUrl")]
+    pub callback_url: Option<String>,
+    pub requests: Vec<ForwardRequestData>,
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+/// The forwarder checks each request against its signer's nonce at the time it runs, so
+/// requests of one signer must carry consecutive nonces in batch order.
+fn check_nonces(requests: &[ForwardRequestData]) -> Result<(), String> {
+    let mut next: HashMap<Address, U256> = HashMap::new();
+    for (index, request) in requests.iter().enumerate() {
+        if let Some(expected) = next.get(&request.from) {
+            if request.nonce != *expected {
+                return Err(format!(
+                    "Request {index} of {} must have nonce {expected}, following the signer's previous request",
+                    request.from
+                ));
+            }
+        }
+        next.insert(request.from, request.nonce + U256::from(1));
+    }
+    Ok(())
+}
+
+/// Queues several signed requests to go out in one atomic `executeBatch` transaction: if
+/// any of them is invalid by the time it is mined, none is executed. Each request is
+/// checked like one sent to `POST /relay`, and refusals name the request by `index`.
+pub async fn relay_batch_handler(
+    State(app_state): State<AppState>,
+    auth: ApiKeyAuth,
+    Json(RelayBatchRequest {
+        chain_id,
+        callback_url,
+        requests,
+    }): Json<RelayBatchRequest>,
+) -> Response {
+    let chain = match app_state.chains.resolve(chain_id) {
+        Ok(chain) => chain,
+        Err(reason) => return error(StatusCode::BAD_REQUEST, &reason),
+    };
+
+    if let Some(response) = invalid_callback_url(callback_url.as_deref()) {
+        return response;
+    }
+
+    let max_requests = app_state.config.batching.max_requests;
+    if requests.is_empty() {
+        return error(StatusCode::BAD_REQUEST, "requests must not be empty");
+    }
+    if requests.len() > max_requests {
+        return error(
+            StatusCode::BAD_REQUEST,
+            &format!("A batch holds at most {max_requests} requests"),
+        );
+    }
+
+    let now = chrono::Utc::now().timestamp() as u64;
+    for (index, request) in requests.iter().enumerate() {
+        if let Err((status, mut body)) = check_request(&app_state, chain, request, now) {
+            body["index"] = index.into();
+            return (status, Json(body)).into_response();
+        }
+    }
+    if let Err(reason) = check_nonces(&requests) {
+        return error(StatusCode::BAD_REQUEST, &reason);
+    }
+
+    let max_gas = app_state.config.batching.max_gas;
+    for (index, request) in requests.iter().enumerate() {
+        if request.gas > U256::from(max_gas) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": format!("Gas {} is over the batch ceiling of {max_gas}", request.gas),
+                    "index": index,
+                })),
+            )
+                .into_response();
+        }
+    }
+    let gas_limit = batch_gas_limit(requests.iter().map(|request| request.gas.to::<u64>()));
+    if gas_limit > max_gas {
+        return error(
+            StatusCode::BAD_REQUEST,
+            &format!("The batch needs a gas limit of {gas_limit}, over the ceiling of {max_gas}"),
+        );
+    }
+
+    if app_state.rate_limiter.enabled() {
+        let mut senders: Vec<Address> = requests.iter().map(|request| request.from).collect();
+        senders.sort();
+        senders.dedup();
+        for sender in senders {
+            let decision = app_state
+                .rate_limiter
+                .check_sender(&sender.to_string())
+                .await;
+            if !decision.allowed {
+                tracing::warn!(from = %sender, "Rate limited sender");
+                return decision.reject("sender");
+            }
+        }
+    }
+
+    if chain.simulator.enabled() {
+        let data: Vec<_> = requests
+            .iter()
+            .map(ForwardRequestData::execute_data)
+            .collect();
+        if let Err(e) = chain.simulator.simulate_batch(&data).await {
+            return simulation_failed(chain, e);
+        }
+    }
+
+    // Each request is held against the budget as if it were relayed alone
+    let max_fee_per_gas = max_fee_per_gas(chain).await;
+    let new_requests: Vec<_> = requests
+        .iter()
+        .map(|request| {
+            let mut new_request = new_tx_request(
+                chain.chain_id,
+                auth.tenant_id,
+                request,
+                estimated_cost(request, max_fee_per_gas),
+            );
+            new_request.callback_url = callback_url.clone();
+            new_request
+        })
+        .collect();
+
+    match app_state
+        .db
+        .tx_requests()
+        .insert_batch_within_budget(&new_requests)
+        .await
+    {
+        Ok(Err(exceeded)) => budget_exceeded(&app_state, auth.tenant_id, exceeded),
+        Ok(Ok((batch_id, ids))) => {
+            for (id, new_request) in ids.iter().zip(&new_requests) {
+                app_state
+                    .events
+                    .publish(RelayEvent::queued(*id, new_request));
+            }
+            tracing::info!(%batch_id, chain_id = chain.chain_id, tenant_id = %auth.tenant_id, requests = ids.len(), "Relay batch accepted");
+            (
+                StatusCode::ACCEPTED,
+                Json(serde_json::json!({
+                    "batchId": batch_id,
+                    "ids": ids,
+                    "chainId": chain.chain_id,
+                    "status": "pending"
+                })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to persist relay batch: {}", e);
+            error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to persist relay batch",
+            )
+        }
+    }
+}
+
+/// The tenant's requests of a batch in batch order, as `GET /relay/{id}` returns them.
+/// Batches the queue worker formed may hold other tenants' requests, which are left out.
+pub async fn relay_batch_status_handler(
+    State(app_state): State<AppState>,
+    auth: ApiKeyAuth,
+    Path(id): Path<Uuid>,
+) -> Response {
+    let requests = match app_state.db.tx_requests().batch_members(id).await {
+        Ok(requests) => requests
+            .into_iter()
+            .filter(|request| request.tenant_id == auth.tenant_id)
+            .collect::<Vec<_>>(),
+        Err(e) => {
+            tracing::error!("Failed to read relay batch: {}", e);
+            return error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to read relay batch",
+            );
+        }
+    };
+    if requests.is_empty() {
+        return error(StatusCode::NOT_FOUND, "Relay batch not found");
+    }
+
+    match with_attempts(&app_state, requests).await {
+        Ok(requests) => Json(serde_json::json!({ "id": id, "requests": requests })).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to read relay batch: {}", e);
+            error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to read relay batch",
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{address, Bytes};
+
+    fn request(from: Address, nonce: u64) -> ForwardRequestData {
+        ForwardRequestData {
+            from,
+            to: address!("0x5FbDB2315678afecb367f032d93F642f64180aa3"),
+            value: U256::ZERO,
+            gas: U256::from(100_000),
+            nonce: U256::from(nonce),
+            deadline: 1_893_456_000,
+            data: Bytes::new(),
+            signature: Bytes::from(vec![0; 65]),
+        }
+    }
+
+    #[test]
+    fn requires_consecutive_nonces_per_signer() {
+        let alice = address!("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let bob = address!("0x70997970C51812dc3A010C7d01b50e0d17dc79C8");
+
+        assert!(check_nonces(&[request(alice, 4), request(bob, 0), request(alice, 5)]).is_ok());
+        let error = check_nonces(&[request(alice, 4), request(alice, 6)]).unwrap_err();
+        assert!(error.starts_with("Request 1 of 0xf39F"), "{error}");
+        assert!(check_nonces(&[request(alice, 5), request(alice, 4)]).is_err());
+    }
+
+    #[test]
+    fn parses_batches() {
+        let body = r#"{
+            "chainId": 11155111,
+            "callbackUrl": "https://example.com/hook",
+            "requests": [{
+                "from": "0x70997970C51812dc3A010C7d01b50e0d17dc79C8",
+                "to": "0x5FbDB2315678afecb367f032d93F642f64180aa3",
+                "value": "0x0",
+                "gas": "0x186a0",
+                "nonce": "0x0",
+                "deadline": 1893456000,
+                "data": "0x",
+                "signature": "0x00"
+            }]
+        }"#;
+        let batch: RelayBatchRequest = serde_json::from_str(body).unwrap();
+        assert_eq!(batch.chain_id, Some(11155111));
+        assert_eq!(batch.requests.len(), 1);
+        assert_eq!(
+            batch.callback_url.as_deref(),
+            Some("https://example.com/hook")
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use crate::auth::ApiKeyAuth;
+use crate::chains::ChainContext;
+use crate::states::AppState;
+use crate::webhooks_handler::valid_callback_url;
+use alloy::primitives::U256;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use db::db::NewTxRequest;
+use db::spend::BudgetExceeded;
+use forwarder::ForwardRequestData;
+use policy::PolicyViolation;
+use queue::{execute_gas_limit, RelayEvent, SimulationError};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// A signed forward request and the chain it is meant for. `chainId` may be left out
+/// when the relayer serves a single chain.
+#[derive(Debug, Deserialize)]
+pub struct RelayRequest {
+    #[serde(rename = "chainId")]
+    pub chain_id: Option<u64>,
+    /// Receives the request's status changes instead of the tenant's webhook URL.
+    #[serde(default, alias = "callbackUrl")]
+    pub callback_url: Option<String>,
+    #[serde(flatten)]
+    pub request: ForwardRequestData,
+}
+
+pub async fn relay_handler(
+    State(app_state): State<AppState>,
+    auth: ApiKeyAuth,
+    Json(RelayRequest {
+        chain_id,
+        callback_url,
+        request,
+    }): Json<RelayRequest>,
+) -> Response {
+    let chain = match app_state.chains.resolve(chain_id) {
+        Ok(chain) => chain,
+        Err(reason) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": reason })),
+            )
+                .into_response();
+        }
+    };
+
+    if let Some(response) = invalid_callback_url(callback_url.as_deref()) {
+        return response;
+    }
+
+    let now = chrono::Utc::now().timestamp() as u64;
+    if let Err((status, body)) = check_request(&app_state, chain, &request, now) {
+        return (status, Json(body)).into_response();
+    }
+
+    if app_state.rate_limiter.enabled() {
+        let decision = app_state
+            .rate_limiter
+            .check_sender(&request.from.to_string())
+            .await;
+        if !decision.allowed {
+            tracing::warn!(from = %request.from, "Rate limited sender");
+            return decision.reject("sender");
+        }
+    }
+
+    if chain.simulator.enabled() {
+        if let Err(e) = chain.simulator.simulate(&request.execute_data()).await {
+            return simulation_failed(chain, e);
+        }
+    }
+
+    // The most the transaction should cost at current fees, held against the tenant's budget
+    let max_fee_per_gas = max_fee_per_gas(chain).await;
+    let estimated_cost_wei = estimated_cost(&request, max_fee_per_gas);
+
+    let mut new_request =
+        new_tx_request(chain.chain_id, auth.tenant_id, &request, estimated_cost_wei);
+    new_request.callback_url = callback_url;
+    match app_state
+        .db
+        .tx_requests()
+        .insert_within_budget(&new_request)
+        .await
+    {
+        Ok(Err(exceeded)) => budget_exceeded(&app_state, auth.tenant_id, exceeded),
+        Ok(Ok(id)) => {
+            app_state
+                .events
+                .publish(RelayEvent::queued(id, &new_request));
+            tracing::info!(%id, chain_id = chain.chain_id, tenant_id = %auth.tenant_id, from = %request.from, to = %request.to, "Relay request accepted");
+            (
+                StatusCode::ACCEPTED,
+                Json(serde_json::json!({
+                    "id": id,
+                    "chainId": chain.chain_id,
+                    "status": "pending"
+                })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to persist relay request: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to persist relay request" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// The `400` for a callback URL that is not http(s), if it is not.
+pub(crate) fn invalid_callback_url(url: Option<&str>) -> Option<Response> {
+    match url {
+        Some(url) if !valid_callback_url(url) => Some(
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": "callback_url must be an http(s) URL" })),
+            )
+                .into_response(),
+        ),
+        _ => None,
+    }
+}
+
+/// Checks a request on its own: its fields, its signature and the relay policy.
+pub(crate) fn check_request(
+    app_state: &AppState,
+    chain: &ChainContext,
+    request: &ForwardRequestData,
+    now: u64,
+) -> Result<(), (StatusCode, serde_json::Value)> {
+    if let Err(reason) = request.validate(now) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            serde_json::json!({ "error": reason }),
+        ));
+    }
+
+    if let Err(e) = chain.forwarder.recover_signer(request) {
+        app_state.metrics.invalid_signatures.inc();
+        tracing::warn!(from = %request.from, chain_id = chain.chain_id, "Rejected relay request: {}", e);
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            serde_json::json!({ "error": e.to_string() }),
+        ));
+    }
+
+    if let Err(violation) = app_state.policy.evaluate(chain.chain_id, request) {
+        if matches!(violation, PolicyViolation::GasTooHigh { .. }) {
+            app_state.metrics.gas_limit_violations.inc();
+        }
+        tracing::warn!(from = %request.from, to = %request.to, chain_id = chain.chain_id, "Relay request refused by policy: {}", violation);
+        return Err((
+            StatusCode::FORBIDDEN,
+            serde_json::json!({
+                "error": violation.to_string(),
+                "reason": violation,
+            }),
+        ));
+    }
+
+    Ok(())
+}
+
+/// `422` with the decoded revert, or `503` if the node could not run the simulation.
+pub(crate) fn simulation_failed(chain: &ChainContext, error: SimulationError) -> Response {
+    match error {
+        SimulationError::Reverted(revert) => {
+            tracing::warn!(
+                chain_id = chain.chain_id,
+                "Relay request would revert: {}",
+                revert
+            );
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(serde_json::json!({
+                    "error": format!("Simulation reverted: {revert}"),
+                    "reason": {
+                        "code": "simulation_reverted",
+                        "in_target": revert.in_target,
+                        "revert_reason": revert.reason,
+                        "data": revert.data,
+                    },
+                })),
+            )
+                .into_response()
+        }
+        SimulationError::Rpc(e) => {
+            tracing::error!(
+                chain_id = chain.chain_id,
+                "Failed to simulate relay request: {}",
+                e
+            );
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({
+                    "error": "Could not simulate the relay request, try again"
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// The chain's current max fee per gas, if the gas oracle has one.
+pub(crate) async fn max_fee_per_gas(chain: &ChainContext) -> Option<u128> {
+    match chain.gas_oracle.suggest().await {
+        Ok(fees) => Some(fees.max_fee_per_gas),
+        Err(e) => {
+            tracing::warn!(
+                chain_id = chain.chain_id,
+                "No gas price to estimate the request cost with: {}",
+                e
+            );
+            None
+        }
+    }
+}
+
+/// The `execute` gas limit times the max fee per gas, in wei.
+pub(crate) fn estimated_cost(
+    request: &ForwardRequestData,
+    max_fee_per_gas: Option<u128>,
+) -> Option<String> {
+    max_fee_per_gas.map(|fee| {
+        let gas_limit = execute_gas_limit(request.gas.to::<u64>());
+        (U256::from(gas_limit) * U256::from(fee)).to_string()
+    })
+}
+
+pub(crate) fn budget_exceeded(
+    app_state: &AppState,
+    tenant_id: Uuid,
+    exceeded: BudgetExceeded,
+) -> Response {
+    app_state.metrics.budget_rejections.inc();
+    tracing::warn!(%tenant_id, period = exceeded.period.as_str(), "Relay request over the tenant's budget");
+    (
+        StatusCode::FORBIDDEN,
+        Json(serde_json::json!({
+            "error": format!("The tenant's {} gas budget would be exceeded", exceeded.period.as_str()),
+            "reason": {
+                "code": "budget_exceeded",
+                "period": exceeded.period,
+                "budget_wei": exceeded.budget_wei,
+                "used_wei": exceeded.used_wei,
+                "estimated_cost_wei": exceeded.estimated_cost_wei,
+            },
+        })),
+    )
+        .into_response()
+}
+
+pub(crate) fn new_tx_request(
+    chain_id: u64,
+    tenant_id: Uuid,
+    request: &ForwardRequestData,
+    estimated_cost_wei: Option<String>,
+) -> NewTxRequest {
+    NewTxRequest {
+        chain_id: chain_id as i64,
+        tenant_id,
+        from_address: request.from.to_checksum(None),
+        to_address: request.to.to_checksum(None),
+        value: request.value.to_string(),
+        gas: request.gas.to::<i64>(),
+        deadline: request.deadline as i64,
+        data: request.data.to_vec(),
+        signature: request.signature.to_vec(),
+        estimated_cost_wei,
+        callback_url: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REQUEST: &str = r#"{
+        "from": "0x70997970C51812dc3A010C7d01b50e0d17dc79C8",
+        "to": "0x5FbDB2315678afecb367f032d93F642f64180aa3",
+        "value": "0x0",
+        "gas": "0x186a0",
+        "nonce": "0x0",
+        "deadline": 1893456000,
+        "data": "0x",
+        "signature": "0x00"
+    }"#;
+
+    #[test]
+    fn chain_id_is_optional() {
+        let request: RelayRequest = serde_json::from_str(REQUEST).unwrap();
+        assert_eq!(request.chain_id, None);
+        assert_eq!(request.request.deadline, 1893456000);
+
+        let with_chain = REQUEST.replacen('{', r#"{ "chainId": 11155420,"#, 1);
+        let request: RelayRequest = serde_json::from_str(&with_chain).unwrap();
+        assert_eq!(request.chain_id, Some(11155420));
+        assert_eq!(request.request.gas.to::<u64>(), 100_000);
+
+        let with_callback =
+            REQUEST.replacen('{', r#"{ "callback_url": "https://example.com/hook","#, 1);
+        let request: RelayRequest = serde_json::from_str(&with_callback).unwrap();
+        assert_eq!(
+            request.callback_url.as_deref(),
+            Some("https://example.com/hook")
+        );
+    }
+}
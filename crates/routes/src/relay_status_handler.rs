@@ -0,0 +1,376 @@
+use crate::auth::ApiKeyAuth;
+use crate::states::AppState;
+use alloy::hex;
+use alloy::primitives::{Address, U256};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use db::db::{Cursor, TxAttempt, TxRequest, TxRequestFilter, TxStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Requests returned per page when no `limit` is given, and the most returned at once.
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+const MAX_PAGE_LIMIT: i64 = 200;
+
+#[derive(Debug, Deserialize)]
+pub struct RelayListQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub status: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    /// `next_cursor` of the previous page.
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// A relay request as clients see it: where it stands, how it got there and what it cost.
+#[derive(Debug, Serialize)]
+pub struct RelayStatus {
+    pub id: Uuid,
+    pub chain_id: i64,
+    pub status: &'static str,
+    pub from: String,
+    pub to: String,
+    pub value: String,
+    pub data: String,
+    pub deadline: i64,
+    pub tx_hash: Option<String>,
+    pub nonce: Option<i64>,
+    pub block_number: Option<i64>,
+    pub error: Option<String>,
+    pub retry_count: i32,
+    pub callback_url: Option<String>,
+    /// The `executeBatch` the request was relayed in, see `GET /relay/batch/{id}`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<Uuid>,
+    pub gas: GasCost,
+    pub lifecycle: Vec<LifecycleEvent>,
+    pub attempts: Vec<AttemptStatus>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Wei amounts are decimal strings.
+#[derive(Debug, Serialize)]
+pub struct GasCost {
+    /// Gas the signed request allows the target call.
+    pub limit: i64,
+    pub used: Option<i64>,
+    pub effective_gas_price: Option<String>,
+    /// `used` times `effective_gas_price`, once the transaction settled.
+    pub cost_wei: Option<String>,
+    /// What the request was held against the tenant's budget for.
+    pub estimated_cost_wei: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LifecycleEvent {
+    /// `queued`, `submitted`, `replaced`, `confirmed` or `failed`.
+    pub event: &'static str,
+    pub at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AttemptStatus {
+    pub tx_hash: String,
+    pub nonce: i64,
+    pub gas_limit: i64,
+    pub max_fee_per_gas: String,
+    pub max_priority_fee_per_gas: String,
+    pub mined: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RelayStatus {
+    pub fn new(request: TxRequest, attempts: Vec<TxAttempt>) -> Self {
+        let cost_wei = match (request.gas_used, &request.effective_gas_price) {
+            (Some(used), Some(price)) => U256::from_str_radix(price, 10)
+                .ok()
+                .map(|price| (U256::from(used as u64) * price).to_string()),
+            _ => None,
+        };
+
+        Self {
+            lifecycle: lifecycle(&request, &attempts),
+            attempts: attempts
+                .into_iter()
+                .map(|attempt| AttemptStatus {
+                    tx_hash: attempt.tx_hash,
+                    nonce: attempt.nonce,
+                    gas_limit: attempt.gas_limit,
+                    max_fee_per_gas: attempt.max_fee_per_gas,
+                    max_priority_fee_per_gas: attempt.max_priority_fee_per_gas,
+                    mined: attempt.mined,
+                    created_at: attempt.created_at,
+                })
+                .collect(),
+            id: request.id,
+            chain_id: request.chain_id,
+            status: request.status.as_str(),
+            from: request.from_address,
+            to: request.to_address,
+            value: request.value,
+            data: format!("0x{}", hex::encode(&request.data)),
+            deadline: request.deadline,
+            tx_hash: request.tx_hash,
+            nonce: request.nonce,
+            block_number: request.block_number,
+            error: request.error,
+            retry_count: request.retry_count,
+            callback_url: request.callback_url,
+            batch_id: request.batch_id,
+            gas: GasCost {
+                limit: request.gas,
+                used: request.gas_used,
+                effective_gas_price: request.effective_gas_price,
+                cost_wei,
+                estimated_cost_wei: request.estimated_cost_wei,
+            },
+            created_at: request.created_at,
+            updated_at: request.updated_at,
+        }
+    }
+}
+
+/// Queued, then one event per broadcast (the first submits, later ones replace it with
+/// higher fees), then the outcome.
+fn lifecycle(request: &TxRequest, attempts: &[TxAttempt]) -> Vec<LifecycleEvent> {
+    let mut events = vec![LifecycleEvent {
+        event: "queued",
+        at: request.created_at,
+        tx_hash: None,
+        error: None,
+    }];
+    events.extend(
+        attempts
+            .iter()
+            .enumerate()
+            .map(|(i, attempt)| LifecycleEvent {
+                event: if i == 0 { "submitted" } else { "replaced" },
+                at: attempt.created_at,
+                tx_hash: Some(attempt.tx_hash.clone()),
+                error: None,
+            }),
+    );
+
+    // Requests broadcast before attempts were recorded only have their current hash
+    if attempts.is_empty() && request.tx_hash.is_some() {
+        events.push(LifecycleEvent {
+            event: "submitted",
+            at: request.updated_at,
+            tx_hash: request.tx_hash.clone(),
+            error: None,
+        });
+    }
+    match request.status {
+        TxStatus::Confirmed => events.push(LifecycleEvent {
+            event: "confirmed",
+            at: request.updated_at,
+            tx_hash: request.tx_hash.clone(),
+            error: None,
+        }),
+        TxStatus::Failed => events.push(LifecycleEvent {
+            event: "failed",
+            at: request.updated_at,
+            tx_hash: request.tx_hash.clone(),
+            error: request.error.clone(),
+        }),
+        TxStatus::Pending | TxStatus::Processing | TxStatus::Submitted => {}
+    }
+    events
+}
+
+/// Cursors are opaque to clients: the hex of the last request's creation time and id.
+pub fn encode_cursor(cursor: &Cursor) -> String {
+    hex::encode(format!(
+        "{}:{}",
+        cursor.created_at.timestamp_micros(),
+        cursor.id
+    ))
+}
+
+pub fn decode_cursor(cursor: &str) -> Option<Cursor> {
+    let decoded = String::from_utf8(hex::decode(cursor).ok()?).ok()?;
+    let (micros, id) = decoded.split_once(':')?;
+    Some(Cursor {
+        created_at: DateTime::from_timestamp_micros(micros.parse().ok()?)?,
+        id: Uuid::parse_str(id).ok()?,
+    })
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+fn internal_error(e: anyhow::Error) -> Response {
+    tracing::error!("Failed to read relay requests: {}", e);
+    error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to read relay requests",
+    )
+}
+
+/// Addresses are stored checksummed.
+pub(crate) fn checksummed(address: &str) -> Option<String> {
+    address
+        .parse::<Address>()
+        .ok()
+        .map(|address| address.to_checksum(None))
+}
+
+pub(crate) async fn with_attempts(
+    app_state: &AppState,
+    requests: Vec<TxRequest>,
+) -> anyhow::Result<Vec<RelayStatus>> {
+    let ids: Vec<Uuid> = requests.iter().map(|request| request.id).collect();
+    let mut attempts: HashMap<Uuid, Vec<TxAttempt>> = HashMap::new();
+    for attempt in app_state.db.tx_requests().attempts_of(&ids).await? {
+        attempts
+            .entry(attempt.tx_request_id)
+            .or_default()
+            .push(attempt);
+    }
+    Ok(requests
+        .into_iter()
+        .map(|request| {
+            let attempts = attempts.remove(&request.id).unwrap_or_default();
+            RelayStatus::new(request, attempts)
+        })
+        .collect())
+}
+
+/// One of the tenant's relay requests; other tenants' requests are not found.
+pub async fn relay_status_handler(
+    State(app_state): State<AppState>,
+    auth: ApiKeyAuth,
+    Path(id): Path<Uuid>,
+) -> Response {
+    let request = match app_state.db.tx_requests().find_by_id(id).await {
+        Ok(Some(request)) if request.tenant_id == auth.tenant_id => request,
+        Ok(_) => return error(StatusCode::NOT_FOUND, "Relay request not found"),
+        Err(e) => return internal_error(e),
+    };
+    match with_attempts(&app_state, vec![request]).await {
+        Ok(mut statuses) => Json(statuses.remove(0)).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// The newest of the tenant's requests whose transaction, current or replaced, has this hash.
+pub async fn relay_by_hash_handler(
+    State(app_state): State<AppState>,
+    auth: ApiKeyAuth,
+    Path(tx_hash): Path<String>,
+) -> Response {
+    let request = match app_state.db.tx_requests().find_by_tx_hash(&tx_hash).await {
+        Ok(requests) => requests
+            .into_iter()
+            .rfind(|request| request.tenant_id == auth.tenant_id),
+        Err(e) => return internal_error(e),
+    };
+    let Some(request) = request else {
+        return error(
+            StatusCode::NOT_FOUND,
+            "No relay request with this transaction hash",
+        );
+    };
+    match with_attempts(&app_state, vec![request]).await {
+        Ok(mut statuses) => Json(statuses.remove(0)).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// The tenant's requests, newest first. Follow `next_cursor` for older ones; it is `null`
+/// on the last page.
+pub async fn list_relays_handler(
+    State(app_state): State<AppState>,
+    auth: ApiKeyAuth,
+    Query(query): Query<RelayListQuery>,
+) -> Response {
+    let mut filter = TxRequestFilter {
+        tenant_id: Some(auth.tenant_id),
+        since: query.since,
+        ..Default::default()
+    };
+    if let Some(from) = &query.from {
+        let Some(address) = checksummed(from) else {
+            return error(StatusCode::BAD_REQUEST, &format!("Invalid address {from}"));
+        };
+        filter.from_address = Some(address);
+    }
+    if let Some(to) = &query.to {
+        let Some(address) = checksummed(to) else {
+            return error(StatusCode::BAD_REQUEST, &format!("Invalid address {to}"));
+        };
+        filter.to_address = Some(address);
+    }
+    if let Some(status) = query.status {
+        match TxStatus::try_from(status) {
+            Ok(status) => filter.status = Some(status),
+            Err(e) => return error(StatusCode::BAD_REQUEST, &e),
+        }
+    }
+    let after = match query.cursor.as_deref().map(decode_cursor) {
+        None => None,
+        Some(Some(cursor)) => Some(cursor),
+        Some(None) => return error(StatusCode::BAD_REQUEST, "Invalid cursor"),
+    };
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .clamp(1, MAX_PAGE_LIMIT);
+
+    // One more than asked for tells whether there is a next page
+    let mut requests = match app_state
+        .db
+        .tx_requests()
+        .list_after(&filter, after, limit + 1)
+        .await
+    {
+        Ok(requests) => requests,
+        Err(e) => return internal_error(e),
+    };
+    let has_more = requests.len() as i64 > limit;
+    requests.truncate(limit as usize);
+    let next_cursor = requests.last().filter(|_| has_more).map(|last| {
+        encode_cursor(&Cursor {
+            created_at: last.created_at,
+            id: last.id,
+        })
+    });
+
+    match with_attempts(&app_state, requests).await {
+        Ok(items) => Json(serde_json::json!({
+            "items": items,
+            "next_cursor": next_cursor,
+        }))
+        .into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursors_round_trip() {
+        let cursor = Cursor {
+            created_at: DateTime::from_timestamp_micros(1_730_851_200_123_456).unwrap(),
+            id: Uuid::from_u128(42),
+        };
+        assert_eq!(decode_cursor(&encode_cursor(&cursor)), Some(cursor));
+        assert_eq!(decode_cursor("not hex"), None);
+        assert_eq!(decode_cursor(&hex::encode("12:not-a-uuid")), None);
+    }
+}
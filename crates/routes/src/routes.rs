@@ -1,23 +1,124 @@
+use crate::admin_handler::{
+    create_api_key_handler, create_tenant_handler, list_api_keys_handler, list_tenants_handler,
+    revoke_api_key_handler, rotate_api_key_handler, set_budget_handler, set_webhook_handler,
+};
+use crate::alerts_handler::{alerts_handler, resolve_alert_handler};
+use crate::auth::require_api_key;
+use crate::chains::{ChainRuntime, Chains};
 use crate::db_health_handler::db_health_handler;
-use crate::metrics_handler::{metrics_handler, health_handler, readiness_handler, liveness_handler};
+use crate::events_handler::{relay_events_handler, websocket_handler};
+use crate::gas_handler::gas_handler;
+use crate::health_checks::register_health_checks;
+use crate::metrics_handler::{
+    health_handler, health_history_handler, liveness_handler, metrics_handler,
+    metrics_history_handler, readiness_handler,
+};
+use crate::monitoring::MonitoringRecorder;
+use crate::policy_handler::{policy_handler, reload_policy_handler};
+use crate::rate_limit::{key_rate_limit_middleware, rate_limit_middleware, RateLimiter};
+use crate::relay_batch_handler::{relay_batch_handler, relay_batch_status_handler};
+use crate::relay_handler::relay_handler;
+use crate::relay_status_handler::{
+    list_relays_handler, relay_by_hash_handler, relay_status_handler,
+};
 use crate::states::AppState;
-use axum::{Router, middleware};
-use axum::routing::get;
+use crate::usage_handler::{admin_usage_handler, usage_handler};
+use crate::webhooks_handler::{deliveries_handler, redeliver_handler};
+use alerts::{AlertEngine, AlertRules, LogSink, WebhookSink};
+use axum::routing::{get, post, put};
+use axum::{middleware, Router};
 use config::config::Configuration;
 use db::db::DbState;
-use metrics::{MetricsCollector, metrics_middleware};
+use metrics::{metrics_middleware, HealthChecker, MetricsCollector};
+use policy::PolicyEngine;
+use queue::RelayEventBus;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use tokio::signal;
+use tokio::sync::watch;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use webhooks::WebhookDispatcher;
 
 pub fn api_router(app_state: AppState) -> Router {
-    Router::new()
+    let health = Router::new()
         .route("/db-health", get(db_health_handler))
-        .route("/metrics", get(metrics_handler))
         .route("/health", get(health_handler))
+        .route("/health/history", get(health_history_handler))
         .route("/ready", get(readiness_handler))
-        .route("/alive", get(liveness_handler))
+        .route("/alive", get(liveness_handler));
+    let metrics = Router::new()
+        .route("/metrics", get(metrics_handler))
+        .route("/metrics/history", get(metrics_history_handler));
+
+    let mut public = Router::new();
+    let mut protected = Router::new()
+        .route("/relay", get(list_relays_handler).post(relay_handler))
+        .route("/relay/batch", post(relay_batch_handler))
+        .route("/relay/batch/{id}", get(relay_batch_status_handler))
+        .route("/relay/{id}", get(relay_status_handler))
+        .route("/relay/{id}/events", get(relay_events_handler))
+        .route("/relay/by-hash/{tx_hash}", get(relay_by_hash_handler))
+        .route("/ws", get(websocket_handler))
+        .route("/gas", get(gas_handler))
+        .route("/tenants/{id}/usage", get(usage_handler))
+        .route("/webhooks/deliveries", get(deliveries_handler))
+        .route(
+            "/webhooks/deliveries/{id}/redeliver",
+            post(redeliver_handler),
+        );
+    for (routes, is_public) in [
+        (health, app_state.config.auth.public_health),
+        (metrics, app_state.config.auth.public_metrics),
+    ] {
+        if is_public {
+            public = public.merge(routes);
+        } else {
+            protected = protected.merge(routes);
+        }
+    }
+
+    // The last layer added runs first: authenticate, then limit by the key's tier
+    let protected = protected
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            key_rate_limit_middleware,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            require_api_key,
+        ));
+
+    // Admin routes check `auth.admin_key` themselves
+    let admin = Router::new()
+        .route("/alerts", get(alerts_handler))
+        .route("/alerts/{id}/resolve", post(resolve_alert_handler))
+        .route("/policy", get(policy_handler))
+        .route("/policy/reload", post(reload_policy_handler))
+        .route(
+            "/admin/tenants",
+            get(list_tenants_handler).post(create_tenant_handler),
+        )
+        .route(
+            "/admin/tenants/{id}/keys",
+            get(list_api_keys_handler).post(create_api_key_handler),
+        )
+        .route("/admin/tenants/{id}/budget", put(set_budget_handler))
+        .route("/admin/tenants/{id}/usage", get(admin_usage_handler))
+        .route("/admin/tenants/{id}/webhook", put(set_webhook_handler))
+        .route("/admin/keys/{id}/rotate", post(rotate_api_key_handler))
+        .route("/admin/keys/{id}/revoke", post(revoke_api_key_handler));
+
+    // Public probes and scrapes are never rate limited
+    let limited = protected
+        .merge(admin)
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            rate_limit_middleware,
+        ));
+
+    public
+        .merge(limited)
         // Add metrics middleware to all routes
         .layer(middleware::from_fn_with_state(
             app_state.metrics.clone(),
@@ -29,33 +130,130 @@ pub fn api_router(app_state: AppState) -> Router {
 pub async fn start_app(config: Arc<Configuration>, db: DbState) -> anyhow::Result<()> {
     // Initialize tracing
     init_tracing()?;
-    
+
+    // Uptime is reported from here
+    let mut health = HealthChecker::new();
+
     // Initialize metrics collector
     let metrics = MetricsCollector::new()?;
-    
+
+    register_health_checks(&mut health, &config.health, &db, &metrics);
+
+    let policy = PolicyEngine::load(&config.policy, db.policies()).await?;
+    tracing::info!(source = ?config.policy.source, "Relay policy loaded");
+
+    // Every chain gets its own nonce manager, queue worker and receipt tracker
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let events = RelayEventBus::new(config.events.buffer_size, shutdown_rx.clone());
+    let mut contexts = Vec::new();
+    let mut tasks = Vec::new();
+    for chain in &config.chains {
+        let runtime = ChainRuntime::start(
+            chain,
+            &config.queue,
+            &config.batching,
+            &config.health,
+            &config.simulation,
+            &policy,
+            &db,
+            &metrics,
+            &events,
+            &mut health,
+            shutdown_rx.clone(),
+        )
+        .await?;
+        contexts.push(runtime.context);
+        tasks.extend(runtime.tasks);
+    }
+    let chains = Chains::new(contexts);
+    let health = Arc::new(health);
+
+    let monitoring = MonitoringRecorder::new(
+        config.monitoring.clone(),
+        db.monitoring(),
+        health.clone(),
+        metrics.clone(),
+    )
+    .spawn(shutdown_rx.clone());
+
+    let alert_rules = match &config.alerting.rules_path {
+        Some(path) => AlertRules::from_file(path)?,
+        None => AlertRules::default(),
+    };
+    tracing::info!("Loaded {} alert rule(s)", alert_rules.rules.len());
+    let mut alert_engine = AlertEngine::new(
+        &config.alerting,
+        alert_rules,
+        db.alerts(),
+        health.clone(),
+        metrics.clone(),
+    )
+    .with_sink(LogSink);
+    if let Some(url) = &config.alerting.webhook_url {
+        alert_engine = alert_engine.with_sink(WebhookSink::new(
+            url.clone(),
+            config.alerting.webhook_timeout,
+        )?);
+    }
+    let alerting = alert_engine.clone().spawn(shutdown_rx.clone());
+    let policy_reload = policy.clone().spawn(shutdown_rx.clone());
+    let rate_limiter = RateLimiter::new(&config.rate_limit, &db, metrics.clone());
+    let rate_limit_pruning = rate_limiter.clone().spawn(shutdown_rx.clone());
+    let webhook_dispatch =
+        WebhookDispatcher::new(config.webhooks.clone(), db.webhooks(), metrics.clone())?
+            .spawn(shutdown_rx);
+
     let listening_addr = config.listening_addr;
-    let app_state = AppState::new(db, config, metrics);
+    let app_state = AppState::new(
+        db,
+        config,
+        metrics,
+        chains,
+        health,
+        alert_engine,
+        policy,
+        rate_limiter,
+        events,
+    );
     let api_router = api_router(app_state);
     let listener = TcpListener::bind(listening_addr).await?;
-    
+
     tracing::info!("Starting gas relayer server on {}", listening_addr);
-    
-    axum::serve(listener, api_router)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
-    
+
+    // Peer addresses are needed to rate limit per client IP
+    axum::serve(
+        listener,
+        api_router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        shutdown_signal().await;
+        let _ = shutdown_tx.send(true);
+    })
+    .await?;
+
+    // Let the workers finish broadcasting what they already claimed before exiting
+    for task in tasks {
+        task.await?;
+    }
+    monitoring.await?;
+    alerting.await?;
+    policy_reload.await?;
+    rate_limit_pruning.await?;
+    webhook_dispatch.await?;
+
     Ok(())
 }
 
 fn init_tracing() -> anyhow::Result<()> {
     tracing_subscriber::registry()
         .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "gas_relayer=debug,tower_http=debug,axum::rejection=trace".into()),
+            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+                "gas_relayer=debug,tower_http=debug,axum::rejection=trace".into()
+            }),
         )
         .with(tracing_subscriber::fmt::layer().with_target(false))
         .init();
-    
+
     Ok(())
 }
 
@@ -78,4 +276,4 @@ async fn shutdown_signal() {
     let terminate = std::future::pending::<()>();
 
     tokio::select! {_ = ctrl_c => {}, _ = terminate => {},}
-}
\ No newline at end of file
+}
@@ -1,6 +1,11 @@
+use crate::chains::Chains;
+use crate::rate_limit::RateLimiter;
+use alerts::AlertEngine;
 use config::config::Configuration;
 use db::db::DbState;
-use metrics::MetricsCollector;
+use metrics::{HealthChecker, MetricsCollector};
+use policy::PolicyEngine;
+use queue::RelayEventBus;
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -8,10 +13,37 @@ pub struct AppState {
     pub db: DbState,
     pub config: Arc<Configuration>,
     pub metrics: MetricsCollector,
+    pub chains: Chains,
+    pub health: Arc<HealthChecker>,
+    pub alerts: AlertEngine,
+    pub policy: PolicyEngine,
+    pub rate_limiter: RateLimiter,
+    pub events: RelayEventBus,
 }
 
 impl AppState {
-    pub fn new(db: DbState, config: Arc<Configuration>, metrics: MetricsCollector) -> Self {
-        Self { db, config, metrics }
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        db: DbState,
+        config: Arc<Configuration>,
+        metrics: MetricsCollector,
+        chains: Chains,
+        health: Arc<HealthChecker>,
+        alerts: AlertEngine,
+        policy: PolicyEngine,
+        rate_limiter: RateLimiter,
+        events: RelayEventBus,
+    ) -> Self {
+        Self {
+            db,
+            config,
+            metrics,
+            chains,
+            health,
+            alerts,
+            policy,
+            rate_limiter,
+            events,
+        }
     }
-}
\ No newline at end of file
+}
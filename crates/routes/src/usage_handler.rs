@@ -0,0 +1,97 @@
+use crate::auth::{AdminAuth, ApiKeyAuth};
+use crate::states::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use db::spend::SpendBucketSize;
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// How far back the buckets go when no `since` is given.
+const DEFAULT_USAGE_DAYS: i64 = 30;
+
+#[derive(Debug, Deserialize)]
+pub struct UsageQuery {
+    /// `hour`, `day` or `month`; `day` when left out.
+    pub bucket: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+/// Budget use of a tenant's own transactions. Tenants only see themselves.
+pub async fn usage_handler(
+    State(app_state): State<AppState>,
+    auth: ApiKeyAuth,
+    Path(tenant_id): Path<Uuid>,
+    Query(query): Query<UsageQuery>,
+) -> Response {
+    if app_state.config.auth.enabled && auth.tenant_id != tenant_id {
+        return error(
+            StatusCode::FORBIDDEN,
+            "API keys can only read their own tenant's usage",
+        );
+    }
+    usage(&app_state, tenant_id, query).await
+}
+
+pub async fn admin_usage_handler(
+    _: AdminAuth,
+    State(app_state): State<AppState>,
+    Path(tenant_id): Path<Uuid>,
+    Query(query): Query<UsageQuery>,
+) -> Response {
+    usage(&app_state, tenant_id, query).await
+}
+
+/// The current daily and monthly periods against the budgets, and settled spend per bucket.
+async fn usage(app_state: &AppState, tenant_id: Uuid, query: UsageQuery) -> Response {
+    let size = match query.bucket.map(SpendBucketSize::try_from) {
+        None => SpendBucketSize::Day,
+        Some(Ok(size)) => size,
+        Some(Err(e)) => return error(StatusCode::BAD_REQUEST, &e),
+    };
+    let until = query.until.unwrap_or_else(Utc::now);
+    let since = query
+        .since
+        .unwrap_or(until - Duration::days(DEFAULT_USAGE_DAYS));
+    if since >= until {
+        return error(StatusCode::BAD_REQUEST, "since must be before until");
+    }
+
+    let spend = app_state.db.spend();
+    let result = async {
+        let periods = spend.period_usage(tenant_id).await?;
+        let buckets = spend.buckets(tenant_id, size, since, until).await?;
+        anyhow::Ok((periods, buckets))
+    }
+    .await;
+
+    match result {
+        // Only tenants that exist have periods
+        Ok((periods, _)) if periods.is_empty() => error(StatusCode::NOT_FOUND, "Tenant not found"),
+        Ok((periods, buckets)) => Json(serde_json::json!({
+            "tenant_id": tenant_id,
+            "periods": periods,
+            "bucket": size.as_str(),
+            "since": since,
+            "until": until,
+            "buckets": buckets,
+        }))
+        .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to read tenant usage: {}", e);
+            error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to read tenant usage",
+            )
+        }
+    }
+}
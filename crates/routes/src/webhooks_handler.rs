@@ -0,0 +1,110 @@
+use crate::auth::ApiKeyAuth;
+use crate::states::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use db::webhooks::DeliveryStatus;
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Deliveries returned when no `limit` is given, and the most returned at once.
+const DEFAULT_DELIVERIES_LIMIT: i64 = 50;
+const MAX_DELIVERIES_LIMIT: i64 = 500;
+
+/// Longest callback URL accepted with a relay request.
+const MAX_CALLBACK_URL_LEN: usize = 2048;
+
+#[derive(Debug, Deserialize)]
+pub struct DeliveriesQuery {
+    /// `pending`, `delivered` or `dead`.
+    pub status: Option<String>,
+    /// Only the deliveries of one relay request.
+    pub request_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+/// Whether `url` can receive callbacks: an absolute http(s) URL of reasonable length.
+pub fn valid_callback_url(url: &str) -> bool {
+    url.len() <= MAX_CALLBACK_URL_LEN
+        && url
+            .split_once("://")
+            .is_some_and(|(scheme, rest)| matches!(scheme, "http" | "https") && !rest.is_empty())
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+fn internal_error(action: &str, e: anyhow::Error) -> Response {
+    tracing::error!("Failed to {}: {}", action, e);
+    error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        &format!("Failed to {action}"),
+    )
+}
+
+/// The tenant's callback deliveries, newest first, with the last error of failing ones.
+pub async fn deliveries_handler(
+    State(app_state): State<AppState>,
+    auth: ApiKeyAuth,
+    Query(query): Query<DeliveriesQuery>,
+) -> Response {
+    let status = match query.status.map(DeliveryStatus::try_from).transpose() {
+        Ok(status) => status,
+        Err(e) => return error(StatusCode::BAD_REQUEST, &e),
+    };
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_DELIVERIES_LIMIT)
+        .clamp(1, MAX_DELIVERIES_LIMIT);
+
+    match app_state
+        .db
+        .webhooks()
+        .list(auth.tenant_id, status, query.request_id, limit)
+        .await
+    {
+        Ok(deliveries) => Json(deliveries).into_response(),
+        Err(e) => internal_error("list webhook deliveries", e),
+    }
+}
+
+/// Queues a delivered or dead-lettered event again, e.g. once the receiver is fixed.
+pub async fn redeliver_handler(
+    State(app_state): State<AppState>,
+    auth: ApiKeyAuth,
+    Path(id): Path<Uuid>,
+) -> Response {
+    match app_state.db.webhooks().redeliver(id, auth.tenant_id).await {
+        Ok(Some(delivery)) => {
+            tracing::info!(%id, tenant_id = %auth.tenant_id, "Webhook delivery queued again");
+            (StatusCode::ACCEPTED, Json(delivery)).into_response()
+        }
+        Ok(None) => error(
+            StatusCode::NOT_FOUND,
+            "Webhook delivery not found or still pending",
+        ),
+        Err(e) => internal_error("redeliver webhook", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_http_callback_urls() {
+        assert!(valid_callback_url("https://example.com/hooks/relay"));
+        assert!(valid_callback_url("http://localhost:3000"));
+        assert!(!valid_callback_url("ftp://example.com"));
+        assert!(!valid_callback_url("https://"));
+        assert!(!valid_callback_url("example.com"));
+        assert!(!valid_callback_url(&format!(
+            "https://example.com/{}",
+            "a".repeat(MAX_CALLBACK_URL_LEN)
+        )));
+    }
+}
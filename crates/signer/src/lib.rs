@@ -0,0 +1,3 @@
+pub mod signer;
+
+pub use signer::*;
@@ -0,0 +1,132 @@
+use alloy::network::EthereumWallet;
+use alloy::primitives::Address;
+use alloy::signers::local::{coins_bip39::English, MnemonicBuilder, PrivateKeySigner};
+use anyhow::Context;
+use config::config::{KeystorePassword, SignerConfig};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The account the relayer pays gas from.
+pub trait Signer: Send + Sync {
+    fn address(&self) -> Address;
+
+    /// Wallet handed to alloy providers so they can sign outgoing transactions.
+    fn wallet(&self) -> EthereumWallet;
+
+    fn kind(&self) -> &'static str;
+}
+
+/// A hex encoded private key, usually read from `signer.private_key`.
+pub struct RawKeySigner {
+    inner: PrivateKeySigner,
+}
+
+/// An encrypted JSON (V3) keystore file.
+pub struct KeystoreSigner {
+    inner: PrivateKeySigner,
+}
+
+/// An account derived from a BIP-39 mnemonic.
+pub struct MnemonicSigner {
+    inner: PrivateKeySigner,
+}
+
+impl RawKeySigner {
+    pub fn from_hex(private_key: &str) -> anyhow::Result<Self> {
+        let inner = private_key
+            .trim()
+            .parse::<PrivateKeySigner>()
+            .context("relayer private key is not a valid secp256k1 key")?;
+        Ok(Self { inner })
+    }
+}
+
+impl KeystoreSigner {
+    pub fn open(path: &Path, password: &str) -> anyhow::Result<Self> {
+        let inner = PrivateKeySigner::decrypt_keystore(path, password)
+            .with_context(|| format!("failed to decrypt keystore {}", path.display()))?;
+        Ok(Self { inner })
+    }
+}
+
+impl MnemonicSigner {
+    pub fn from_phrase(phrase: &str, derivation_path: &str) -> anyhow::Result<Self> {
+        let inner = MnemonicBuilder::<English>::default()
+            .phrase(phrase.trim())
+            .derivation_path(derivation_path)
+            .with_context(|| format!("invalid derivation path {derivation_path}"))?
+            .build()
+            .context("failed to derive relayer key from mnemonic")?;
+        Ok(Self { inner })
+    }
+}
+
+impl Signer for RawKeySigner {
+    fn address(&self) -> Address {
+        self.inner.address()
+    }
+
+    fn wallet(&self) -> EthereumWallet {
+        EthereumWallet::from(self.inner.clone())
+    }
+
+    fn kind(&self) -> &'static str {
+        "private_key"
+    }
+}
+
+impl Signer for KeystoreSigner {
+    fn address(&self) -> Address {
+        self.inner.address()
+    }
+
+    fn wallet(&self) -> EthereumWallet {
+        EthereumWallet::from(self.inner.clone())
+    }
+
+    fn kind(&self) -> &'static str {
+        "keystore"
+    }
+}
+
+impl Signer for MnemonicSigner {
+    fn address(&self) -> Address {
+        self.inner.address()
+    }
+
+    fn wallet(&self) -> EthereumWallet {
+        EthereumWallet::from(self.inner.clone())
+    }
+
+    fn kind(&self) -> &'static str {
+        "mnemonic"
+    }
+}
+
+/// Builds the signer selected in the configuration.
+pub fn load_signer(config: &SignerConfig) -> anyhow::Result<Arc<dyn Signer>> {
+    let signer: Arc<dyn Signer> = match config {
+        SignerConfig::PrivateKey(key) => Arc::new(RawKeySigner::from_hex(key.expose())?),
+        SignerConfig::Keystore { path, password } => {
+            let password = match password {
+                KeystorePassword::Value(value) => value.expose().to_string(),
+                KeystorePassword::File(file) => std::fs::read_to_string(file)
+                    .with_context(|| {
+                        format!("failed to read keystore password file {}", file.display())
+                    })?
+                    .trim_end_matches(['\r', '\n'])
+                    .to_string(),
+            };
+            Arc::new(KeystoreSigner::open(path, &password)?)
+        }
+        SignerConfig::Mnemonic {
+            phrase,
+            derivation_path,
+        } => Arc::new(MnemonicSigner::from_phrase(
+            phrase.expose(),
+            derivation_path,
+        )?),
+    };
+
+    Ok(signer)
+}
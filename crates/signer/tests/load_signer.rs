@@ -0,0 +1,70 @@
+//! The fixtures use the well-known Hardhat/Anvil development accounts.
+
+use alloy::primitives::{address, Address};
+use config::config::{KeystorePassword, SecretString, SignerConfig, DEFAULT_DERIVATION_PATH};
+use signer::load_signer;
+use std::path::PathBuf;
+
+const TEST_MNEMONIC: &str = "test test test test test test test test test test test junk";
+const ACCOUNT_0: Address = address!("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+const ACCOUNT_1: Address = address!("0x70997970C51812dc3A010C7d01b50e0d17dc79C8");
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+#[test]
+fn loads_raw_private_key() {
+    let config = SignerConfig::PrivateKey(SecretString::new(
+        "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80".to_string(),
+    ));
+
+    let signer = load_signer(&config).unwrap();
+    assert_eq!(signer.address(), ACCOUNT_0);
+    assert_eq!(signer.kind(), "private_key");
+}
+
+#[test]
+fn rejects_invalid_private_key() {
+    let config = SignerConfig::PrivateKey(SecretString::new("0x1234".to_string()));
+    assert!(load_signer(&config).is_err());
+}
+
+#[test]
+fn derives_accounts_from_mnemonic() {
+    let first = SignerConfig::Mnemonic {
+        phrase: SecretString::new(TEST_MNEMONIC.to_string()),
+        derivation_path: DEFAULT_DERIVATION_PATH.to_string(),
+    };
+    let second = SignerConfig::Mnemonic {
+        phrase: SecretString::new(TEST_MNEMONIC.to_string()),
+        derivation_path: "m/44'/60'/0'/0/1".to_string(),
+    };
+
+    assert_eq!(load_signer(&first).unwrap().address(), ACCOUNT_0);
+    assert_eq!(load_signer(&second).unwrap().address(), ACCOUNT_1);
+}
+
+#[test]
+fn decrypts_keystore() {
+    let config = SignerConfig::Keystore {
+        path: fixture("keystore.json"),
+        password: KeystorePassword::Value(SecretString::new("relayer-test-password".to_string())),
+    };
+
+    let signer = load_signer(&config).unwrap();
+    assert_eq!(signer.address(), ACCOUNT_1);
+    assert_eq!(signer.kind(), "keystore");
+}
+
+#[test]
+fn rejects_wrong_keystore_password() {
+    let config = SignerConfig::Keystore {
+        path: fixture("keystore.json"),
+        password: KeystorePassword::Value(SecretString::new("wrong".to_string())),
+    };
+
+    assert!(load_signer(&config).is_err());
+}
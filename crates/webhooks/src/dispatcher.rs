@@ -0,0 +1,225 @@
+use crate::signing::{sign, EVENT_ID_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use chrono::Utc;
+use config::config::WebhookConfig;
+use db::webhooks::{DueDelivery, WebhookRepository};
+use futures::future::join_all;
+use metrics::MetricsCollector;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Extra time a claimed delivery stays reserved beyond the request timeout.
+const LEASE_MARGIN: Duration = Duration::from_secs(30);
+
+/// Response bodies of failed deliveries are kept up to this length for debugging.
+const MAX_ERROR_BODY: usize = 512;
+
+/// POSTs due events from the `webhook_deliveries` outbox, retrying failures with
+/// exponential backoff until they are delivered or dead-lettered. Every replica may run
+/// one; claims are exclusive.
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    config: WebhookConfig,
+    deliveries: WebhookRepository,
+    client: reqwest::Client,
+    metrics: MetricsCollector,
+}
+
+/// Why an attempt failed, and the status code if the receiver answered at all.
+struct DeliveryFailure {
+    status_code: Option<i32>,
+    error: String,
+}
+
+impl WebhookDispatcher {
+    pub fn new(
+        config: WebhookConfig,
+        deliveries: WebhookRepository,
+        metrics: MetricsCollector,
+    ) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder().timeout(config.timeout).build()?;
+        Ok(Self {
+            config,
+            deliveries,
+            client,
+            metrics,
+        })
+    }
+
+    pub fn spawn(self, shutdown: watch::Receiver<bool>) -> JoinHandle<()> {
+        tokio::spawn(self.run(shutdown))
+    }
+
+    pub async fn run(self, mut shutdown: watch::Receiver<bool>) {
+        tracing::info!("Webhook dispatcher started");
+
+        while !*shutdown.borrow() {
+            let sent = match self.dispatch_due().await {
+                Ok(sent) => sent,
+                Err(e) => {
+                    tracing::error!("Failed to dispatch webhooks: {}", e);
+                    0
+                }
+            };
+            // A full batch means more are probably due
+            if sent == self.config.batch_size as usize {
+                continue;
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(self.config.poll_interval) => {}
+                _ = shutdown.changed() => {}
+            }
+        }
+
+        tracing::info!("Webhook dispatcher stopped");
+    }
+
+    /// Sends one batch of due deliveries concurrently and returns how many were claimed.
+    pub async fn dispatch_due(&self) -> anyhow::Result<usize> {
+        let lease = self.config.timeout + LEASE_MARGIN;
+        let due = self
+            .deliveries
+            .claim_due(self.config.batch_size as i64, lease)
+            .await?;
+        let claimed = due.len();
+
+        for result in join_all(due.into_iter().map(|delivery| self.deliver(delivery))).await {
+            if let Err(e) = result {
+                tracing::error!("Failed to record a webhook delivery: {}", e);
+            }
+        }
+        Ok(claimed)
+    }
+
+    async fn deliver(&self, delivery: DueDelivery) -> anyhow::Result<()> {
+        let failure = match self.send(&delivery).await {
+            Ok(status_code) => {
+                self.deliveries
+                    .mark_delivered(delivery.id, status_code)
+                    .await?;
+                self.metrics
+                    .webhook_deliveries
+                    .with_label_values(&["delivered"])
+                    .inc();
+                tracing::debug!(id = %delivery.id, url = %delivery.url, "Webhook delivered");
+                return Ok(());
+            }
+            Err(failure) => failure,
+        };
+
+        let attempts = delivery.attempts.max(1) as u32;
+        let retry_at = (attempts < self.config.max_attempts)
+            .then(|| Utc::now() + backoff(&self.config, attempts));
+        let outcome = if retry_at.is_some() {
+            "retried"
+        } else {
+            "dead"
+        };
+        self.metrics
+            .webhook_deliveries
+            .with_label_values(&[outcome])
+            .inc();
+        tracing::warn!(
+            id = %delivery.id,
+            url = %delivery.url,
+            attempts,
+            retry_at = ?retry_at,
+            "Webhook delivery failed: {}",
+            failure.error
+        );
+
+        self.deliveries
+            .mark_failed(delivery.id, failure.status_code, &failure.error, retry_at)
+            .await
+    }
+
+    /// POSTs the stored body as is, so the signature covers exactly the bytes sent.
+    async fn send(&self, delivery: &DueDelivery) -> Result<i32, DeliveryFailure> {
+        let secret = delivery
+            .secret
+            .as_deref()
+            .or(self.config.secret.as_ref().map(|secret| secret.expose()))
+            .ok_or_else(|| DeliveryFailure {
+                status_code: None,
+                error: "no webhook secret to sign with: set one for the tenant or webhooks.secret"
+                    .to_string(),
+            })?;
+        let timestamp = Utc::now().timestamp();
+
+        let response = self
+            .client
+            .post(&delivery.url)
+            .header("content-type", "application/json")
+            .header(EVENT_ID_HEADER, delivery.id.to_string())
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_HEADER, sign(secret, timestamp, &delivery.body))
+            .body(delivery.body.clone())
+            .send()
+            .await
+            .map_err(|e| DeliveryFailure {
+                status_code: None,
+                error: e.to_string(),
+            })?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(status.as_u16() as i32);
+        }
+        let body = response.text().await.unwrap_or_default();
+        Err(DeliveryFailure {
+            status_code: Some(status.as_u16() as i32),
+            error: format!("HTTP {status}: {}", truncate(&body, MAX_ERROR_BODY)),
+        })
+    }
+}
+
+/// Wait after the `attempts`-th failed attempt: the initial backoff, doubled after each
+/// further failure, capped at the maximum.
+pub fn backoff(config: &WebhookConfig, attempts: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+    config
+        .initial_backoff
+        .saturating_mul(factor)
+        .min(config.max_backoff)
+}
+
+fn truncate(value: &str, max: usize) -> &str {
+    match value.char_indices().nth(max) {
+        Some((index, _)) => &value[..index],
+        None => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> WebhookConfig {
+        WebhookConfig {
+            secret: None,
+            timeout: Duration::from_secs(5),
+            poll_interval: Duration::from_secs(1),
+            batch_size: 20,
+            max_attempts: 10,
+            initial_backoff: Duration::from_secs(10),
+            max_backoff: Duration::from_secs(3600),
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let config = config();
+        let waits: Vec<u64> = (1..=10)
+            .map(|attempts| backoff(&config, attempts).as_secs())
+            .collect();
+        assert_eq!(waits, vec![10, 20, 40, 80, 160, 320, 640, 1280, 2560, 3600]);
+        assert_eq!(backoff(&config, 64).as_secs(), 3600);
+    }
+
+    #[test]
+    fn truncates_on_char_boundaries() {
+        assert_eq!(truncate("héllo", 2), "hé");
+        assert_eq!(truncate("hi", 10), "hi");
+    }
+}
@@ -0,0 +1,5 @@
+pub mod dispatcher;
+pub mod signing;
+
+pub use dispatcher::*;
+pub use signing::*;
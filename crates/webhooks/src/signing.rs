@@ -0,0 +1,40 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Id of the event, the same across retries so receivers can drop duplicates.
+pub const EVENT_ID_HEADER: &str = "x-relayer-event-id";
+
+/// Unix seconds at which the attempt was signed. Receivers should reject old ones.
+pub const TIMESTAMP_HEADER: &str = "x-relayer-timestamp";
+
+/// `sha256=` followed by the hex HMAC-SHA256 of `{timestamp}.{body}`.
+pub const SIGNATURE_HEADER: &str = "x-relayer-signature";
+
+/// Signature of a callback body sent at `timestamp`. The timestamp is signed too, so a
+/// captured request cannot be replayed later with a fresh one.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_timestamp_and_body() {
+        // echo -n '1700000000.{"id":1}' | openssl dgst -sha256 -hmac 'whsec_test_secret_value'
+        assert_eq!(
+            sign("whsec_test_secret_value", 1_700_000_000, r#"{"id":1}"#),
+            "sha256=b80a973b29b3065a3a18e2c13fa8226f4513ad3a744e6b7a26400f53ee1280bc"
+        );
+        assert_ne!(
+            sign("whsec_test_secret_value", 1_700_000_001, r#"{"id":1}"#),
+            sign("whsec_test_secret_value", 1_700_000_000, r#"{"id":1}"#)
+        );
+    }
+}